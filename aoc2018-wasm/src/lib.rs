@@ -0,0 +1,84 @@
+//! Every day's solver in a single module, so they can be run from a web page.
+//!
+//! Without the `wasm` feature, this is an ordinary library. With it, `solve` is exported
+//! via `wasm-bindgen`:
+//!
+//! ```sh
+//! wasm-pack build aoc2018-wasm -- --features wasm
+//! ```
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+type Error = Box<dyn std::error::Error>;
+
+macro_rules! dispatch {
+    ($day:expr, $part:expr, $input:expr; $($n:literal => $krate:ident),* $(,)?) => {
+        match ($day, $part) {
+            $(
+                ($n, 1) => $krate::part1_from_str($input).map_err(Into::into),
+                ($n, 2) => $krate::part2_from_str($input).map_err(Into::into),
+            )*
+            (day, part) => Err(format!("no solver for day {} part {}", day, part).into()),
+        }
+    };
+}
+
+fn try_solve(day: u8, part: u8, input: &str) -> Result<String, Error> {
+    // text pasted into a browser may carry Windows line endings or a byte order mark
+    let input = util::normalize_input(input);
+    dispatch!(day, part, &input;
+        1 => day01,
+        2 => day02,
+        3 => day03,
+        4 => day04,
+        5 => day05,
+        6 => day06,
+        7 => day07,
+        8 => day08,
+        9 => day09,
+        10 => day10,
+        11 => day11,
+        12 => day12,
+        13 => day13,
+        14 => day14,
+        15 => day15,
+        16 => day16,
+    )
+}
+
+/// Solve a part of a day's puzzle for the given input.
+///
+/// Returns the solver's report, or a description of the error prefixed with `error: `.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn solve(day: u8, part: u8, input: &str) -> String {
+    try_solve(day, part, input).unwrap_or_else(|err| format!("error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_from_string() {
+        assert_eq!(solve(1, 1, "+1\n-2\n+3\n+1\n"), "frequency sum: 3");
+        assert_eq!(
+            solve(1, 2, "+3\n+3\n+4\n-2\n-4\n"),
+            "first duplicate: 10 (idx: 6)"
+        );
+    }
+
+    #[test]
+    fn solves_crlf_with_bom() {
+        assert_eq!(
+            solve(1, 1, &util::to_crlf_with_bom("+1\n-2\n+3\n+1\n")),
+            "frequency sum: 3"
+        );
+    }
+
+    #[test]
+    fn unknown_day() {
+        assert_eq!(solve(25, 1, ""), "error: no solver for day 25 part 1");
+        assert_eq!(solve(1, 3, ""), "error: no solver for day 1 part 3");
+    }
+}
@@ -1,44 +1,679 @@
-use aoclib::parse;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::{TryFrom, TryInto},
+    num::ParseIntError,
+    path::{Path, PathBuf},
+};
 
-use std::{collections::HashSet, path::Path};
+pub type Frequency = i64;
 
-pub type Frequency = i32;
+/// The default bound on the passes through the changes [`first_duplicate_bounded`] makes.
+pub const DEFAULT_MAX_CYCLES: usize = 1000;
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let frequency_sum = parse::<Frequency>(input)?.sum::<Frequency>();
-    println!("frequency sum: {}", frequency_sum);
-    Ok(())
+/// Split a line into whitespace-separated tokens, with the byte offset at which each begins.
+fn tokens(line: &str) -> impl '_ + Iterator<Item = (usize, &str)> {
+    let mut start = None;
+    line.char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+        .filter_map(move |(idx, ch)| match (start, ch.is_whitespace()) {
+            (None, false) => {
+                start = Some(idx);
+                None
+            }
+            (Some(token_start), true) => {
+                start = None;
+                Some((token_start, &line[token_start..idx]))
+            }
+            _ => None,
+        })
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
+/// Parse frequency changes from the input.
+///
+/// Each line may hold any number of whitespace-separated signed integers, like `+3 -2 +7`;
+/// all lines' changes are flattened in order. Any token which isn't an integer is an error
+/// reporting its 1-based line and column.
+pub fn parse_changes(input: &str) -> Result<Vec<Frequency>, Error> {
+    let mut changes = Vec::new();
+    for (line_idx, line) in input.lines().enumerate() {
+        for (offset, token) in tokens(line) {
+            let change = token.parse().map_err(|source| Error::InvalidToken {
+                line: line_idx + 1,
+                column: line[..offset].chars().count() + 1,
+                token: token.to_string(),
+                source,
+            })?;
+            changes.push(change);
+        }
+    }
+    Ok(changes)
+}
+
+/// Expand a list of paths into the files they name, in order.
+///
+/// A directory stands for the files directly within it, sorted by name; subdirectories
+/// are ignored.
+pub fn input_files<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            files.push(path.to_path_buf());
+            continue;
+        }
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                entries.push(entry.path());
+            }
+        }
+        entries.sort_unstable();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// Parse frequency changes from several files, concatenated in order.
+///
+/// Paths are expanded as by [`input_files`]. Errors name the file they occurred in.
+pub fn parse_many<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<Frequency>, Error> {
+    let mut changes = Vec::new();
+    for path in input_files(paths)? {
+        let in_file = |source| Error::InFile {
+            path: path.clone(),
+            source: Box::new(source),
+        };
+        let input = util::read_input(&path).map_err(|err| in_file(err.into()))?;
+        changes.extend(parse_changes(&input).map_err(in_file)?);
+    }
+    Ok(changes)
+}
+
+/// Sum a sequence of frequency changes, failing on overflow.
+pub fn checked_sum(changes: impl IntoIterator<Item = Frequency>) -> Result<Frequency, Error> {
+    changes.into_iter().try_fold(0, |acc: Frequency, change| {
+        acc.checked_add(change).ok_or(Error::Overflow)
+    })
+}
+
+/// Iterate over the accumulated frequency after each change, starting from 0.
+///
+/// The initial frequency of 0 is not itself yielded. The iterator ends early if the
+/// accumulated frequency would overflow; use [`checked_sum`] to detect that case.
+pub fn running_frequencies(
+    changes: impl IntoIterator<Item = Frequency>,
+) -> impl Iterator<Item = Frequency> {
+    changes
+        .into_iter()
+        .scan(0, |accumulated: &mut Frequency, change| {
+            *accumulated = accumulated.checked_add(change)?;
+            Some(*accumulated)
+        })
+}
+
+/// Find the first accumulated frequency which is reached twice, counting the initial 0.
+///
+/// Returns `(frequency, index)`, where `index` is the index of the change which reached the
+/// frequency for the second time. Returns `None` if the changes run out, or overflow, first;
+/// in particular, empty input has no repeat.
+///
+/// This doesn't cycle through the changes itself; pass `changes.iter().copied().cycle()` for that.
+pub fn first_repeat(changes: impl IntoIterator<Item = Frequency>) -> Option<(Frequency, usize)> {
+    repeats(changes).next()
+}
+
+/// Iterate over every revisit of an accumulated frequency, counting the initial 0.
+///
+/// Yields `(frequency, index)` as [`first_repeat`] does, for every visit to a frequency after
+/// its first, so the first item is the first repeat. A frequency reached three times is
+/// yielded twice.
+pub fn repeats(
+    changes: impl IntoIterator<Item = Frequency>,
+) -> impl Iterator<Item = (Frequency, usize)> {
     let mut states = HashSet::new();
     states.insert(0);
-    let mut accumulated = 0;
-    let mut count = None;
+    running_frequencies(changes)
+        .enumerate()
+        .filter(move |&(_, frequency)| !states.insert(frequency))
+        .map(|(idx, frequency)| (frequency, idx))
+}
 
-    for (idx, line) in parse::<Frequency>(input)?
-        .collect::<Vec<_>>()
-        .into_iter()
+/// Describe the first `limit` revisits of any frequency, one per line, simulating at most
+/// `max_cycles` passes through the changes.
+///
+/// Each line gives the pass through the changes, counted from 0, during which the revisit
+/// happened, and the index of the change which caused it, as [`repeats`] does.
+pub fn repeat_log(changes: &[Frequency], max_cycles: usize, limit: usize) -> String {
+    let cycled = changes
+        .iter()
+        .copied()
+        .cycle()
+        .take(changes.len().saturating_mul(max_cycles));
+    let lines: Vec<String> = repeats(cycled)
+        .take(limit)
+        .map(|(frequency, idx)| {
+            format!(
+                "cycle {}, change {}: frequency {} revisited",
+                idx / changes.len(),
+                idx,
+                frequency
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        format!("no frequency revisited within {} cycles", max_cycles)
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// Find the first accumulated frequency which is reached twice, cycling through the changes.
+///
+/// Returns `(frequency, index)`, where `index` counts changes applied across all cycles.
+///
+/// This doesn't simulate more than the first cycle. Each later cycle shifts every frequency
+/// in the first by the drift, the sum of all changes, so a frequency can only repeat one
+/// reached earlier in the first cycle which differs from it by a multiple of the drift.
+/// It fails with [`Error::NeverRepeats`] if there are none.
+pub fn first_duplicate(changes: &[Frequency]) -> Result<(Frequency, usize), Error> {
+    match first_duplicate_bounded(changes, 1) {
+        Err(Error::CycleLimit { .. }) => {}
+        result => return result,
+    }
+
+    // Every frequency in the first cycle is distinct, so the drift isn't 0: cycling would
+    // have returned to 0. Work in `i128` so that no intermediate value can overflow.
+    let n = changes.len() as i128;
+    let mut starts = Vec::with_capacity(changes.len());
+    let mut accumulated = 0_i128;
+    for &change in changes {
+        starts.push(accumulated);
+        accumulated += change as i128;
+    }
+    let drift = accumulated;
+
+    // Frequencies which differ by a multiple of the drift share a residue.
+    let mut by_residue: HashMap<i128, Vec<(i128, usize)>> = HashMap::new();
+    for (position, &start) in starts.iter().enumerate() {
+        by_residue
+            .entry(start.rem_euclid(drift))
+            .or_default()
+            .push((start, position));
+    }
+
+    // A frequency is repeated at the next position, in the direction of the drift, which
+    // shares its residue. `(time, frequency, position, cycle)`, where the time counts the
+    // initial frequency as 0.
+    let mut first: Option<(i128, i128, usize, i128)> = None;
+    for (_residue, mut group) in by_residue {
+        group.sort_unstable_by_key(|&(start, _)| start * drift.signum());
+        for pair in group.windows(2) {
+            let ((start, position), (frequency, _)) = (pair[0], pair[1]);
+            let cycle = (frequency - start) / drift;
+            let time = cycle * n + position as i128;
+            if first.map_or(true, |(first_time, ..)| time < first_time) {
+                first = Some((time, frequency, position, cycle));
+            }
+        }
+    }
+    // the first cycle didn't overflow, so neither does the drift
+    let (time, frequency, position, cycle) = first.ok_or(Error::NeverRepeats {
+        drift: drift as Frequency,
+    })?;
+
+    // the simulation would overflow if any frequency reached on the way did
+    let overflows = starts.iter().enumerate().any(|(other, &start)| {
+        let reached_cycle = if other <= position { cycle } else { cycle - 1 };
+        Frequency::try_from(start + reached_cycle * drift).is_err()
+    });
+    if overflows {
+        return Err(Error::Overflow);
+    }
+
+    let idx = (time - 1).try_into().map_err(|_| Error::Overflow)?;
+    Ok((frequency as Frequency, idx))
+}
+
+/// Find the first accumulated frequency which is reached twice, by simulating at most
+/// `max_cycles` passes through the changes.
+///
+/// Returns `(frequency, index)` as [`first_duplicate`] does, but may give up with
+/// [`Error::CycleLimit`] where that would find a solution.
+pub fn first_duplicate_bounded(
+    changes: &[Frequency],
+    max_cycles: usize,
+) -> Result<(Frequency, usize), Error> {
+    if changes.is_empty() {
+        return Err(Error::NoSolution);
+    }
+
+    let mut states = HashSet::new();
+    states.insert(0);
+    let mut accumulated: Frequency = 0;
+
+    let max_changes = changes.len().saturating_mul(max_cycles);
+    for (idx, change) in changes
+        .iter()
+        .copied()
         .cycle()
+        .take(max_changes)
         .enumerate()
     {
-        accumulated += line;
+        accumulated = accumulated.checked_add(change).ok_or(Error::Overflow)?;
         if !states.insert(accumulated) {
-            count = Some(idx);
-            break;
+            return Ok((accumulated, idx));
         }
     }
 
-    let count = count.ok_or(Error::NoSolution)?;
+    Err(Error::CycleLimit {
+        drift: checked_sum(changes.iter().copied())?,
+        cycles: max_cycles,
+    })
+}
 
-    println!("first duplicate: {} (idx: {})", accumulated, count);
+pub fn part1(input: &Path) -> Result<(), Error> {
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_from_changes(&parse_changes(input)?)
+}
+
+pub fn part1_from_changes(changes: &[Frequency]) -> Result<String, Error> {
+    let frequency_sum = checked_sum(changes.iter().copied())?;
+    Ok(format!("frequency sum: {}", frequency_sum))
+}
+
+pub fn part2(input: &Path) -> Result<(), Error> {
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_from_changes(&parse_changes(input)?, None)
+}
+
+/// Solve part 2 by simulation alone, giving up after `max_cycles` passes through the changes.
+pub fn part2_with_max_cycles(input: &str, max_cycles: usize) -> Result<String, Error> {
+    part2_from_changes(&parse_changes(input)?, Some(max_cycles))
+}
+
+/// Solve part 2 for already-parsed changes, simulating at most `max_cycles` passes
+/// through them if given.
+pub fn part2_from_changes(
+    changes: &[Frequency],
+    max_cycles: Option<usize>,
+) -> Result<String, Error> {
+    let (accumulated, count) = match max_cycles {
+        Some(max_cycles) => first_duplicate_bounded(changes, max_cycles)?,
+        None => first_duplicate(changes)?,
+    };
+    Ok(format!("first duplicate: {} (idx: {})", accumulated, count))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("no solution found")]
     NoSolution,
+    #[error("frequency overflowed")]
+    Overflow,
+    #[error("no solution found within {cycles} cycles (drift per cycle: {drift})")]
+    CycleLimit { drift: Frequency, cycles: usize },
+    #[error("no solution: no frequency ever repeats (drift per cycle: {drift})")]
+    NeverRepeats { drift: Frequency },
+    #[error("line {line}, column {column}: invalid frequency change \"{token}\"")]
+    InvalidToken {
+        line: usize,
+        column: usize,
+        token: String,
+        #[source]
+        source: ParseIntError,
+    },
+    #[error("in {}", path.display())]
+    InFile {
+        path: PathBuf,
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn sum_examples() {
+        assert_eq!(checked_sum(vec![1, -2, 3, 1]).unwrap(), 3);
+        assert_eq!(checked_sum(vec![1, 1, 1]).unwrap(), 3);
+        assert_eq!(checked_sum(vec![-1, -2, -3]).unwrap(), -6);
+    }
+
+    #[test]
+    fn sum_near_boundaries() {
+        assert_eq!(
+            checked_sum(vec![Frequency::MAX, -1, 1]).unwrap(),
+            Frequency::MAX
+        );
+        assert_eq!(
+            checked_sum(vec![Frequency::MIN, 1, -1]).unwrap(),
+            Frequency::MIN
+        );
+        // exceeds the range of `i32`
+        assert_eq!(
+            checked_sum(vec![i32::MAX as Frequency, i32::MAX as Frequency]).unwrap(),
+            2 * i32::MAX as Frequency
+        );
+    }
+
+    #[test]
+    fn sum_overflow() {
+        assert!(matches!(
+            checked_sum(vec![Frequency::MAX, 1]),
+            Err(Error::Overflow)
+        ));
+        assert!(matches!(
+            checked_sum(vec![Frequency::MIN, -1]),
+            Err(Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn duplicate_examples() {
+        assert_eq!(first_duplicate(&[1, -1]).unwrap().0, 0);
+        assert_eq!(first_duplicate(&[3, 3, 4, -2, -4]).unwrap().0, 10);
+        assert_eq!(first_duplicate(&[-6, 3, 8, 5, -6]).unwrap().0, 5);
+        assert_eq!(first_duplicate(&[7, 7, -2, -7, -4]).unwrap().0, 14);
+    }
+
+    #[test]
+    fn duplicate_overflow() {
+        assert!(matches!(
+            first_duplicate(&[Frequency::MAX, 1]),
+            Err(Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn duplicate_empty() {
+        assert!(matches!(first_duplicate(&[]), Err(Error::NoSolution)));
+        assert!(matches!(
+            first_duplicate_bounded(&[], DEFAULT_MAX_CYCLES),
+            Err(Error::NoSolution)
+        ));
+    }
+
+    #[test]
+    fn duplicate_bounded_examples() {
+        for changes in &[
+            &[1, -1][..],
+            &[3, 3, 4, -2, -4],
+            &[-6, 3, 8, 5, -6],
+            &[7, 7, -2, -7, -4],
+        ] {
+            assert_eq!(
+                first_duplicate_bounded(changes, DEFAULT_MAX_CYCLES).unwrap(),
+                first_duplicate(changes).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_never_repeats() {
+        // every frequency is distinct modulo the drift
+        assert!(matches!(
+            first_duplicate(&[1, 2]),
+            Err(Error::NeverRepeats { drift: 3 })
+        ));
+        assert!(matches!(
+            first_duplicate_bounded(&[1, 2], 10),
+            Err(Error::CycleLimit {
+                drift: 3,
+                cycles: 10
+            })
+        ));
+    }
+
+    #[test]
+    fn duplicate_after_many_cycles() {
+        // 1000000 is reached again after a million cycles
+        let changes = [1_000_000, -999_999];
+        assert_eq!(first_duplicate(&changes).unwrap(), (1_000_000, 1_999_999));
+        assert!(matches!(
+            first_duplicate_bounded(&changes, DEFAULT_MAX_CYCLES),
+            Err(Error::CycleLimit { drift: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn duplicate_near_boundaries() {
+        let half = Frequency::MAX / 2;
+        assert_eq!(first_duplicate(&[half, 1, -half]).unwrap(), (half + 1, 3));
+        // 1 and MAX - 1 differ by a multiple of the drift, but the frequency overflows
+        // during the third cycle, long before MAX - 1 comes around again
+        assert!(matches!(
+            first_duplicate(&[Frequency::MAX - 1, -(Frequency::MAX - 2)]),
+            Err(Error::Overflow)
+        ));
+        assert!(matches!(
+            first_duplicate_bounded(&[Frequency::MAX - 1, -(Frequency::MAX - 2)], 10),
+            Err(Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn parse_one_per_line() {
+        assert_eq!(
+            parse_changes("+1\n-2\n\n  +3\n+1\n").unwrap(),
+            vec![1, -2, 3, 1]
+        );
+    }
+
+    #[test]
+    fn parse_several_per_line() {
+        assert_eq!(
+            parse_changes("+3 -2 +7\n-1\t+1\n  \n4").unwrap(),
+            vec![3, -2, 7, -1, 1, 4]
+        );
+        assert_eq!(part1_from_str("+1 -2\n+3 +1").unwrap(), "frequency sum: 3");
+    }
+
+    #[test]
+    fn parse_invalid_token() {
+        match parse_changes("+1 +2\n-3  +x4 +5\n") {
+            Err(Error::InvalidToken {
+                line,
+                column,
+                token,
+                ..
+            }) => {
+                assert_eq!((line, column), (2, 5));
+                assert_eq!(token, "+x4");
+            }
+            other => panic!("expected invalid token, got {:?}", other),
+        }
+        // columns count characters, not bytes
+        assert!(matches!(
+            parse_changes("+1 ½"),
+            Err(Error::InvalidToken {
+                line: 1,
+                column: 4,
+                ..
+            })
+        ));
+    }
+
+    /// A fresh, empty directory for a test's input files.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("day01-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_many_files() {
+        let dir = scratch_dir("parse-many");
+        std::fs::write(dir.join("b.txt"), "+4\n-1").unwrap();
+        std::fs::write(dir.join("a.txt"), "+3 +3\n").unwrap();
+        std::fs::create_dir(dir.join("c")).unwrap();
+        std::fs::write(dir.join("c").join("ignored.txt"), "+100").unwrap();
+        let extra = dir.join("c").join("extra.txt");
+        std::fs::write(&extra, "-2\n-4").unwrap();
+
+        assert_eq!(
+            input_files(&[&dir, &extra]).unwrap(),
+            vec![dir.join("a.txt"), dir.join("b.txt"), extra.clone()]
+        );
+        // [3, 3, 4, -2, -4] is one of the puzzle's examples, split across files
+        let changes = parse_many(&[dir.join("a.txt"), dir.join("b.txt"), extra.clone()]);
+        assert_eq!(changes.unwrap(), vec![3, 3, 4, -1, -2, -4]);
+        std::fs::write(dir.join("b.txt"), "+4").unwrap();
+        let changes = parse_many(&[&dir, &extra]).unwrap();
+        assert_eq!(changes, vec![3, 3, 4, -2, -4]);
+        assert_eq!(
+            part2_from_changes(&changes, None).unwrap(),
+            part2_from_str("+3 +3 +4 -2 -4").unwrap()
+        );
+        assert_eq!(parse_many::<&Path>(&[]).unwrap(), Vec::<Frequency>::new());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parse_many_errors() {
+        let dir = scratch_dir("parse-many-errors");
+        std::fs::write(dir.join("good.txt"), "+1").unwrap();
+        std::fs::write(dir.join("bad.txt"), "+1\n+x").unwrap();
+
+        match parse_many(&[dir.join("good.txt"), dir.join("bad.txt")]) {
+            Err(Error::InFile { path, source }) => {
+                assert_eq!(path, dir.join("bad.txt"));
+                assert!(matches!(*source, Error::InvalidToken { line: 2, .. }));
+            }
+            other => panic!("expected an error in bad.txt, got {:?}", other),
+        }
+        assert!(matches!(
+            parse_many(&[dir.join("missing.txt")]),
+            Err(Error::InFile { source, .. }) if matches!(*source, Error::Io(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn running_examples() {
+        assert_eq!(
+            running_frequencies(vec![1, -2, 3, 1]).collect::<Vec<_>>(),
+            vec![1, -1, 2, 3]
+        );
+        assert_eq!(running_frequencies(vec![]).count(), 0);
+        assert_eq!(
+            running_frequencies(vec![Frequency::MAX, 1, 1]).collect::<Vec<_>>(),
+            vec![Frequency::MAX]
+        );
+    }
+
+    #[test]
+    fn repeat_examples() {
+        assert_eq!(first_repeat(vec![]), None);
+        assert_eq!(first_repeat(vec![1, 2, 3]), None);
+        assert_eq!(first_repeat(vec![1, -1]), Some((0, 1)));
+        let cycled = [3, 3, 4, -2, -4].iter().copied().cycle();
+        assert_eq!(first_repeat(cycled).unwrap().0, 10);
+    }
+
+    #[test]
+    fn repeats_examples() {
+        let cycled = [1, -2, 3, 1].iter().copied().cycle();
+        // 1 -1 2 3, 4 2 5 6, 7 5 8 9
+        assert_eq!(
+            repeats(cycled).take(3).collect::<Vec<_>>(),
+            vec![(2, 5), (5, 9), (8, 13)]
+        );
+        // 0 is revisited on every cycle, and 1 on every cycle after the first
+        assert_eq!(
+            repeats(vec![1, -1, 1, -1, 1]).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 2), (0, 3), (1, 4)]
+        );
+        assert_eq!(repeats(vec![1, 2, 3]).count(), 0);
+    }
+
+    #[test]
+    fn repeat_log_examples() {
+        assert_eq!(
+            repeat_log(&[1, -2, 3, 1], DEFAULT_MAX_CYCLES, 2),
+            "cycle 1, change 5: frequency 2 revisited\ncycle 2, change 9: frequency 5 revisited"
+        );
+        assert_eq!(
+            repeat_log(&[1, 1], 10, 5),
+            "no frequency revisited within 10 cycles"
+        );
+        assert_eq!(
+            repeat_log(&[], 10, 5),
+            "no frequency revisited within 10 cycles"
+        );
+        assert_eq!(
+            repeat_log(&[1, -2, 3, 1], 2, 5),
+            "cycle 1, change 5: frequency 2 revisited"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn running_ends_at_sum(changes in prop::collection::vec(-1_000_000..1_000_000_i64, 0..100)) {
+            let running: Vec<_> = running_frequencies(changes.clone()).collect();
+            prop_assert_eq!(running.len(), changes.len());
+            prop_assert_eq!(running.last().copied().unwrap_or_default(), checked_sum(changes).unwrap());
+        }
+
+        #[test]
+        fn repeat_matches_brute_force(changes in prop::collection::vec(-10..10_i64, 0..50)) {
+            let mut seen = vec![0];
+            let mut expect = None;
+            for (idx, frequency) in running_frequencies(changes.clone()).enumerate() {
+                if seen.contains(&frequency) {
+                    expect = Some((frequency, idx));
+                    break;
+                }
+                seen.push(frequency);
+            }
+            prop_assert_eq!(first_repeat(changes), expect);
+        }
+
+        #[test]
+        fn duplicate_matches_simulation(changes in prop::collection::vec(-10..10_i64, 1..20)) {
+            // frequencies stay within 200 of 0 during the first cycle, and the drift is at
+            // least 1, so any repeat happens within 400 cycles
+            match first_duplicate_bounded(&changes, 500) {
+                Ok(found) => prop_assert_eq!(first_duplicate(&changes).unwrap(), found),
+                Err(Error::CycleLimit { .. }) => {
+                    // `prop_assert!` can't format a pattern with braces
+                    let never_repeats =
+                        matches!(first_duplicate(&changes), Err(Error::NeverRepeats { .. }));
+                    prop_assert!(never_repeats);
+                }
+                Err(err) => panic!("unexpected error: {}", err),
+            }
+        }
+
+        #[test]
+        fn repeat_agrees_with_duplicate(changes in prop::collection::vec(-10..10_i64, 1..20)) {
+            // cycling can go forever if the changes drift; bound it
+            let cycled = changes.iter().copied().cycle().take(changes.len() * 50);
+            if let Some(repeat) = first_repeat(cycled) {
+                prop_assert_eq!(first_duplicate(&changes).unwrap(), repeat);
+            }
+        }
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day01-crlf", "+1\n-2\n+3\n+1\n").unwrap();
+        assert_eq!(parse_many(&[file.path()]).unwrap(), vec![1, -2, 3, 1]);
+    }
 }
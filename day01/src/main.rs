@@ -1,18 +1,22 @@
 use aoclib::{config::Config, website::get_input};
-use day01::{part1, part2};
+use day01::{
+    parse_changes, parse_many, part1_from_changes, part2_from_changes, repeat_log, Frequency,
+    DEFAULT_MAX_CYCLES,
+};
 
 use color_eyre::eyre::Result;
 use structopt::StructOpt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const YEAR: u32 = 2018;
 const DAY: u8 = 1;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input files or directories, whose changes are concatenated in order
+    /// (`-` to read from stdin, as in `cat input | day01 --input -`)
     #[structopt(long, parse(from_os_str))]
-    input: Option<PathBuf>,
+    input: Vec<PathBuf>,
 
     /// skip part 1
     #[structopt(long)]
@@ -21,33 +25,51 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// solve part 2 by simulating at most this many passes through the changes
+    #[structopt(long)]
+    max_cycles: Option<usize>,
+
+    /// before solving part 2, list the first few times any frequency is revisited
+    #[structopt(short, long)]
+    verbose: bool,
+
+    /// how many revisits to list in verbose mode
+    #[structopt(long, default_value = "10")]
+    repeats: usize,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
-            None => {
+    fn changes(&self) -> Result<Vec<Frequency>> {
+        let path = match self.input.as_slice() {
+            [] => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            [path] if path == Path::new("-") => path.clone(),
+            paths => return Ok(parse_many(paths)?),
+        };
+        Ok(parse_changes(&util::read_input_or_stdin(path)?)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let changes = args.changes()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_from_changes(&changes)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        if args.verbose {
+            let max_cycles = args.max_cycles.unwrap_or(DEFAULT_MAX_CYCLES);
+            println!("{}", repeat_log(&changes, max_cycles, args.repeats));
+        }
+        println!("{}", part2_from_changes(&changes, args.max_cycles)?);
     }
     Ok(())
 }
@@ -1,10 +1,67 @@
-use aoclib::parse;
 use counter::Counter;
 use itertools::Itertools;
-use std::{path::Path, str::FromStr};
+use rayon::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use util::parse_str;
 
-#[derive(Default, Debug)]
-struct BoxId {
+/// How to clean up IDs before analyzing them.
+///
+/// The default only trims surrounding whitespace, which is all the puzzle input needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalization {
+    /// Convert letters to lowercase.
+    pub lowercase: bool,
+    /// Remove leading and trailing whitespace.
+    pub trim: bool,
+    /// Remove every occurrence of this character.
+    pub separator: Option<char>,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization {
+            lowercase: false,
+            trim: true,
+            separator: None,
+        }
+    }
+}
+
+impl Normalization {
+    /// Leave IDs exactly as they are.
+    pub fn none() -> Self {
+        Normalization {
+            lowercase: false,
+            trim: false,
+            separator: None,
+        }
+    }
+
+    /// Normalize a single ID.
+    pub fn apply(&self, id: &str) -> String {
+        let id = if self.trim { id.trim() } else { id };
+        let mut normalized = String::with_capacity(id.len());
+        for ch in id.chars().filter(|&ch| Some(ch) != self.separator) {
+            if self.lowercase {
+                normalized.extend(ch.to_lowercase());
+            } else {
+                normalized.push(ch);
+            }
+        }
+        normalized
+    }
+}
+
+/// A box ID, along with the frequencies of its letters.
+#[derive(Default, Debug, Clone)]
+pub struct BoxId {
     word: String,
     freqs: Counter<char>,
 }
@@ -27,51 +84,281 @@ impl FromStr for BoxId {
 }
 
 impl BoxId {
-    fn has_n(&self, n: usize) -> bool {
-        self.freqs.values().any(|&v| v == n)
+    pub fn as_str(&self) -> &str {
+        &self.word
+    }
+
+    /// The number of times each letter appears, in letter order.
+    pub fn frequencies(&self) -> impl '_ + Iterator<Item = (char, usize)> {
+        self.freqs
+            .iter()
+            .map(|(&letter, &count)| (letter, count))
+            .sorted()
+    }
+
+    /// The number of times each of the letters `a` through `z` appears.
+    ///
+    /// Other characters are ignored.
+    pub fn frequency_vector(&self) -> [usize; 26] {
+        let mut vector = [0; 26];
+        for (&letter, &count) in self.freqs.iter() {
+            if letter.is_ascii_lowercase() {
+                vector[(letter as u8 - b'a') as usize] = count;
+            }
+        }
+        vector
+    }
+
+    /// For each number of repetitions, how many distinct letters appear exactly that often.
+    ///
+    /// For example, `bababc` has one letter appearing once, one twice, and one three times.
+    pub fn histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for &count in self.freqs.values() {
+            *histogram.entry(count).or_default() += 1;
+        }
+        histogram
     }
+
+    /// The ID's letters in sorted order. IDs are anagrams of one another exactly when
+    /// their signatures are equal.
+    pub fn signature(&self) -> String {
+        self.word.chars().sorted().collect()
+    }
+}
+
+/// Group IDs into classes of anagrams, keyed by their shared [signature](BoxId::signature).
+///
+/// Within each class, IDs keep their input order.
+pub fn anagram_groups(ids: &[BoxId]) -> BTreeMap<String, Vec<&BoxId>> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for id in ids {
+        groups.entry(id.signature()).or_default().push(id);
+    }
+    groups
+}
+
+/// Compute the checksum in a single pass, without retaining any IDs.
+///
+/// Lines are normalized, and lines which are then empty are skipped.
+pub fn checksum_streaming(
+    lines: impl Iterator<Item = String>,
+    normalization: &Normalization,
+) -> usize {
+    let (twos, threes) = lines
+        .map(|line| normalization.apply(&line))
+        .filter(|line| !line.is_empty())
+        .map(BoxId::from)
+        .fold((0, 0), |(twos, threes), id| {
+            let histogram = id.histogram();
+            (
+                twos + histogram.contains_key(&2) as usize,
+                threes + histogram.contains_key(&3) as usize,
+            )
+        });
+    twos * threes
 }
 
+/// The number of positions at which `a` and `b` differ.
+///
+/// Only the length of the shorter string is compared.
 pub fn hamming(a: &str, b: &str) -> usize {
     a.chars().zip(b.chars()).filter(|(a, b)| a != b).count()
 }
 
+/// Two IDs which differ at exactly one position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlmostMatch {
+    /// The index of the earlier ID among those searched.
+    pub first: usize,
+    /// The index of the later ID among those searched.
+    pub second: usize,
+    /// The earlier ID, as given, before normalization.
+    pub first_id: String,
+    /// The later ID, as given, before normalization.
+    pub second_id: String,
+    /// The letters the normalized IDs share at the same positions.
+    pub shared: String,
+}
+
+impl AlmostMatch {
+    fn new<S: AsRef<str>>(
+        strings: &[S],
+        normalized: &[String],
+        first: usize,
+        second: usize,
+    ) -> Self {
+        AlmostMatch {
+            first,
+            second,
+            first_id: strings[first].as_ref().to_string(),
+            second_id: strings[second].as_ref().to_string(),
+            shared: shared_letters(&normalized[first], &normalized[second]),
+        }
+    }
+}
+
+/// Find two strings which differ at exactly one position, and return the letters they share.
+///
+/// This is [`find_almost_match_pair`], keeping only the shared letters.
+pub fn find_almost_match<S>(strings: &[S], normalization: &Normalization) -> Option<String>
+where
+    S: AsRef<str>,
+{
+    find_almost_match_pair(strings, normalization).map(|almost_match| almost_match.shared)
+}
+
+/// Find two strings which differ at exactly one position.
+///
+/// Positions are compared as by [`hamming`]. If several pairs qualify, the first is chosen:
+/// the one whose first string comes earliest in `strings`, and among those, the one whose
+/// second string comes earliest.
+//
 // This variant of the function iterates over each string twice, but only
 // allocates when there's a known match. That turns out to be more performant
 // than an implementation which iterates only once but allocates as it goes.
-pub fn find_almost_match<S>(strings: &[S]) -> Option<String>
+//
+// Strings are normalized once up front, so that cost doesn't grow with the
+// number of pairs.
+pub fn find_almost_match_pair<S>(
+    strings: &[S],
+    normalization: &Normalization,
+) -> Option<AlmostMatch>
 where
     S: AsRef<str>,
 {
-    strings
+    let normalized: Vec<String> = strings
         .iter()
-        .map(|s| s.as_ref())
+        .map(|s| normalization.apply(s.as_ref()))
+        .collect();
+    normalized
+        .iter()
+        .enumerate()
         .tuple_combinations()
-        .find(|(a, b)| hamming(a, b) == 1)
-        .map(|(a, b)| {
-            a.chars()
-                .zip(b.chars())
-                .filter(|(a, b)| a == b)
-                .map(|(a, _)| a)
-                .collect()
+        .find(|((_, a), (_, b))| hamming(a, b) == 1)
+        .map(|((first, _), (second, _))| AlmostMatch::new(strings, &normalized, first, second))
+}
+
+/// As [`find_almost_match`], but comparing pairs in parallel.
+pub fn find_almost_match_par<S>(strings: &[S], normalization: &Normalization) -> Option<String>
+where
+    S: AsRef<str> + Sync,
+{
+    find_almost_match_pair_par(strings, normalization).map(|almost_match| almost_match.shared)
+}
+
+/// As [`find_almost_match_pair`], but comparing pairs in parallel.
+///
+/// Pairs are partitioned by their first string. Once a match is found, pairs whose first
+/// string comes later stop being compared, but pairs which might precede the match keep
+/// going, so the result is the same first pair the serial search finds.
+//
+// A plain "found" flag would let a later match cancel the search for an earlier one,
+// so the flag is the lowest first index matched so far.
+pub fn find_almost_match_pair_par<S>(
+    strings: &[S],
+    normalization: &Normalization,
+) -> Option<AlmostMatch>
+where
+    S: AsRef<str> + Sync,
+{
+    let normalized: Vec<String> = strings
+        .par_iter()
+        .map(|s| normalization.apply(s.as_ref()))
+        .collect();
+    let first_match = AtomicUsize::new(usize::MAX);
+    (0..normalized.len())
+        .into_par_iter()
+        .filter_map(|idx| {
+            let a = &normalized[idx];
+            for (offset, b) in normalized[idx + 1..].iter().enumerate() {
+                if first_match.load(Ordering::Relaxed) < idx {
+                    return None;
+                }
+                if hamming(a, b) == 1 {
+                    first_match.fetch_min(idx, Ordering::Relaxed);
+                    return Some((idx, idx + 1 + offset));
+                }
+            }
+            None
         })
+        .min_by_key(|&(first, _)| first)
+        .map(|(first, second)| AlmostMatch::new(strings, &normalized, first, second))
+}
+
+/// The letters which `a` and `b` share at the same positions.
+fn shared_letters(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .filter(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let ids: Vec<BoxId> = parse(input)?.collect();
-    let checksum =
-        ids.iter().filter(|id| id.has_n(2)).count() * ids.iter().filter(|id| id.has_n(3)).count();
-    println!("checksum: {}", checksum);
+    println!("{}", part1_from_reader(BufReader::new(File::open(input)?))?);
     Ok(())
 }
 
+/// Solve part 1 a line at a time, so the IDs never need to fit in memory.
+///
+/// Stops reading at the first I/O error, and returns it.
+pub fn part1_from_reader(reader: impl BufRead) -> Result<String, Error> {
+    let mut err = None;
+    // `lines` already strips CRLF line endings, but not a byte-order mark
+    let lines = reader
+        .lines()
+        .map_while(|line| line.map_err(|e| err = Some(e)).ok())
+        .enumerate()
+        .map(|(idx, line)| match idx {
+            0 => util::normalize_input(&line).into_owned(),
+            _ => line,
+        });
+    let checksum = checksum_streaming(lines, &Normalization::default());
+    if let Some(err) = err {
+        return Err(err.into());
+    }
+    Ok(format!("checksum: {}", checksum))
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with_normalization(input, &Normalization::default())
+}
+
+pub fn part1_with_normalization(
+    input: &str,
+    normalization: &Normalization,
+) -> Result<String, Error> {
+    let checksum = checksum_streaming(input.lines().map(str::to_string), normalization);
+    Ok(format!("checksum: {}", checksum))
+}
+
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let ids: Vec<String> = parse(input)?.collect();
-    let almost_match = find_almost_match(&ids).ok_or(Error::NoSolution)?;
-    println!("almost match: {}", almost_match);
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with_normalization(input, &Normalization::default())
+}
+
+pub fn part2_with_normalization(
+    input: &str,
+    normalization: &Normalization,
+) -> Result<String, Error> {
+    let ids: Vec<String> = parse_str(input).collect();
+    let almost_match = find_almost_match(&ids, normalization).ok_or(Error::NoSolution)?;
+    Ok(format!("almost match: {}", almost_match))
+}
+
+/// Find the almost-matching IDs as [`part2_from_str`] does, comparing them in parallel.
+pub fn part2_parallel_from_str(input: &str) -> Result<String, Error> {
+    let ids: Vec<String> = parse_str(input).collect();
+    let almost_match =
+        find_almost_match_par(&ids, &Normalization::default()).ok_or(Error::NoSolution)?;
+    Ok(format!("almost match: {}", almost_match))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -79,3 +366,330 @@ pub enum Error {
     #[error("no solution found")]
     NoSolution,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// The single-pass strategy: build the shared letters while comparing each pair,
+    /// giving up on the pair at its second difference.
+    fn find_almost_match_single_pass(strings: &[String]) -> Option<String> {
+        for (idx, a) in strings.iter().enumerate() {
+            for b in &strings[idx + 1..] {
+                let mut shared = String::with_capacity(a.len());
+                let mut differences = 0;
+                for (a, b) in a.chars().zip(b.chars()) {
+                    if a == b {
+                        shared.push(a);
+                    } else {
+                        differences += 1;
+                        if differences > 1 {
+                            break;
+                        }
+                    }
+                }
+                if differences == 1 {
+                    return Some(shared);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn almost_match_examples() {
+        let ids = [
+            "abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz",
+        ];
+        assert_eq!(
+            find_almost_match(&ids, &Normalization::default()),
+            Some("fgij".to_string())
+        );
+        assert_eq!(
+            find_almost_match(&ids[..3], &Normalization::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn almost_match_provenance() {
+        let ids = [
+            "abcde", "fghij", "klmno", "pqrst", " FGUIJ", "axcye", "wvxyz",
+        ];
+        let normalization = Normalization {
+            lowercase: true,
+            ..Normalization::default()
+        };
+        let expect = AlmostMatch {
+            first: 1,
+            second: 4,
+            first_id: "fghij".to_string(),
+            second_id: " FGUIJ".to_string(),
+            shared: "fgij".to_string(),
+        };
+        assert_eq!(
+            find_almost_match_pair(&ids, &normalization),
+            Some(expect.clone())
+        );
+        assert_eq!(
+            find_almost_match_pair_par(&ids, &normalization),
+            Some(expect)
+        );
+        assert_eq!(find_almost_match_pair(&ids[..3], &normalization), None);
+
+        // (0, 2), (1, 2) and (1, 3) all differ by one letter
+        let ids = ["abx", "ayc", "abc", "zyc"];
+        for found in &[
+            find_almost_match_pair(&ids, &Normalization::none()),
+            find_almost_match_pair_par(&ids, &Normalization::none()),
+        ] {
+            let found = found.as_ref().unwrap();
+            assert_eq!((found.first, found.second), (0, 2));
+            assert_eq!(
+                (found.first_id.as_str(), found.second_id.as_str()),
+                ("abx", "abc")
+            );
+        }
+    }
+
+    #[test]
+    fn first_pair_wins() {
+        // (0, 2), (1, 2) and (1, 3) all differ by one letter
+        let ids = ["abx", "ayc", "abc", "zyc"];
+        assert_eq!(
+            find_almost_match(&ids, &Normalization::none()),
+            Some("ab".to_string())
+        );
+        assert_eq!(
+            find_almost_match(&ids[1..], &Normalization::none()),
+            Some("ac".to_string())
+        );
+    }
+
+    #[test]
+    fn parallel_first_pair_wins() {
+        let ids = ["abx", "ayc", "abc", "zyc"];
+        assert_eq!(
+            find_almost_match_par(&ids, &Normalization::none()),
+            Some("ab".to_string())
+        );
+        assert_eq!(
+            find_almost_match_par(&ids[1..], &Normalization::none()),
+            Some("ac".to_string())
+        );
+        assert_eq!(
+            find_almost_match_par::<&str>(&[], &Normalization::none()),
+            None
+        );
+    }
+
+    #[test]
+    fn parallel_many_matches() {
+        // many near-identical IDs: every thread finds matches, but only the first may win
+        let mut state = 2_u32;
+        let mut next = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (b'a' + ((state >> 16) % 3) as u8) as char
+        };
+        let ids: Vec<String> = (0..2000)
+            .map(|_| (0..8).map(|_| next()).collect())
+            .collect();
+        let expect = find_almost_match(&ids, &Normalization::none());
+        assert!(expect.is_some());
+        for _ in 0..10 {
+            assert_eq!(find_almost_match_par(&ids, &Normalization::none()), expect);
+        }
+        assert_eq!(
+            part2_parallel_from_str(&ids.join("\n")).unwrap(),
+            part2_from_str(&ids.join("\n")).unwrap()
+        );
+    }
+
+    #[test]
+    fn checksum_example() {
+        let ids = "abcdef\nbababc\nabbcde\nabcccd\naabcdd\nabcdee\nababab\n";
+        assert_eq!(
+            checksum_streaming(ids.lines().map(str::to_string), &Normalization::default()),
+            12
+        );
+        assert_eq!(
+            part1_from_reader(ids.as_bytes()).unwrap(),
+            part1_from_str(ids).unwrap()
+        );
+    }
+
+    /// Yields each chunk in turn from `read`, then end of file.
+    struct Chunks(std::collections::VecDeque<std::io::Result<&'static [u8]>>);
+
+    impl std::io::Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.pop_front() {
+                None => Ok(0),
+                Some(Err(err)) => Err(err),
+                Some(Ok(chunk)) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn part1_stops_at_read_error() {
+        let mut chunks = Chunks(
+            vec![
+                Ok(&b"abcdef\nbababc\n"[..]),
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "first",
+                )),
+                Ok(&b"abbcde\n"[..]),
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "second",
+                )),
+            ]
+            .into(),
+        );
+        match part1_from_reader(BufReader::new(&mut chunks)) {
+            Err(Error::Io(err)) => assert_eq!(err.to_string(), "first"),
+            other => panic!("expected the first read error, got {:?}", other),
+        }
+        // nothing after the first error was read
+        assert_eq!(chunks.0.len(), 2);
+    }
+
+    #[test]
+    fn normalize() {
+        let normalization = Normalization {
+            lowercase: true,
+            trim: true,
+            separator: Some('-'),
+        };
+        assert_eq!(normalization.apply("  AB-cD-e \n"), "abcde");
+        assert_eq!(Normalization::default().apply("  AB-cD-e \n"), "AB-cD-e");
+        assert_eq!(Normalization::none().apply(" AB "), " AB ");
+    }
+
+    #[test]
+    fn normalized_checksum() {
+        let ids = "ABCDEF\nbaB-abc\nabbcde\nabc-CCD\n-\naabcDD\nabcdee\nAbAbAb\n";
+        // case and separators hide most of the example's repeated letters
+        assert_eq!(
+            checksum_streaming(ids.lines().map(str::to_string), &Normalization::default()),
+            5
+        );
+        let normalization = Normalization {
+            lowercase: true,
+            separator: Some('-'),
+            ..Normalization::default()
+        };
+        assert_eq!(
+            checksum_streaming(ids.lines().map(str::to_string), &normalization),
+            12
+        );
+    }
+
+    #[test]
+    fn normalized_almost_match() {
+        let ids = [
+            "abcde", "FGH-IJ", "klmno", "pqrst", "fgu-ij", "axcye", "wvxyz",
+        ];
+        assert_eq!(find_almost_match(&ids, &Normalization::default()), None);
+        let normalization = Normalization {
+            lowercase: true,
+            separator: Some('-'),
+            ..Normalization::default()
+        };
+        assert_eq!(
+            find_almost_match(&ids, &normalization),
+            Some("fgij".to_string())
+        );
+    }
+
+    #[test]
+    fn frequency_analysis() {
+        let id: BoxId = "bababc".parse().unwrap();
+        assert_eq!(
+            id.frequencies().collect::<Vec<_>>(),
+            vec![('a', 2), ('b', 3), ('c', 1)]
+        );
+        let vector = id.frequency_vector();
+        assert_eq!(&vector[..3], &[2, 3, 1]);
+        assert_eq!(vector.iter().sum::<usize>(), 6);
+        assert_eq!(
+            id.histogram().into_iter().collect::<Vec<_>>(),
+            vec![(1, 1), (2, 1), (3, 1)]
+        );
+        assert_eq!(id.signature(), "aabbbc");
+    }
+
+    proptest! {
+        // a small alphabet and short strings make near-matches, and multiple of them, common
+        #[test]
+        fn almost_match_strategies_agree(strings in prop::collection::vec("[abc]{0,4}", 0..12)) {
+            prop_assert_eq!(
+                find_almost_match(&strings, &Normalization::none()),
+                find_almost_match_single_pass(&strings)
+            );
+        }
+
+        #[test]
+        fn almost_match_strategies_agree_sparse(strings in prop::collection::vec("[a-z]{6}", 0..40)) {
+            prop_assert_eq!(
+                find_almost_match(&strings, &Normalization::none()),
+                find_almost_match_single_pass(&strings)
+            );
+        }
+
+        #[test]
+        fn almost_match_parallel_agrees(strings in prop::collection::vec("[abc]{0,4}", 0..40)) {
+            prop_assert_eq!(
+                find_almost_match_par(&strings, &Normalization::none()),
+                find_almost_match(&strings, &Normalization::none())
+            );
+        }
+
+        #[test]
+        fn planted_pair_is_found(strings in prop::collection::vec("[a-z]{6}", 0..20), position in 0..6_usize) {
+            // append a pair which differs only at `position`; any match must be at least as early
+            let mut strings = strings;
+            let original = "mmmmmm".to_string();
+            let mut modified = original.clone();
+            modified.replace_range(position..=position, "n");
+            strings.push(original);
+            strings.push(modified);
+            let found = find_almost_match(&strings, &Normalization::none());
+            prop_assert!(found.is_some());
+            prop_assert_eq!(found, find_almost_match_single_pass(&strings));
+        }
+    }
+
+    #[test]
+    fn anagrams() {
+        let ids: Vec<BoxId> = ["abcde", "fghij", "edcba", "klmno", "bacde", "ijhgf"]
+            .iter()
+            .map(|id| id.parse().unwrap())
+            .collect();
+        let groups = anagram_groups(&ids);
+        assert_eq!(groups.len(), 3);
+        let group = |signature: &str| -> Vec<&str> {
+            groups[signature].iter().map(|id| id.as_str()).collect()
+        };
+        assert_eq!(group("abcde"), vec!["abcde", "edcba", "bacde"]);
+        assert_eq!(group("fghij"), vec!["fghij", "ijhgf"]);
+        assert_eq!(group("klmno"), vec!["klmno"]);
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new(
+            "day02-crlf",
+            "abcdef\nbababc\nabbcde\nabcccd\naabcdd\nabcdee\nababab\n",
+        )
+        .unwrap();
+        let reader = BufReader::new(File::open(file.path()).unwrap());
+        assert_eq!(part1_from_reader(reader).unwrap(), "checksum: 12");
+    }
+}
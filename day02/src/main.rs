@@ -1,8 +1,12 @@
 use aoclib::{config::Config, website::get_input};
-use day02::{part1, part2};
+use day02::{part1_from_reader, part1_from_str, part2_from_str, part2_parallel_from_str};
 
 use color_eyre::eyre::Result;
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 use structopt::StructOpt;
 
 const YEAR: u32 = 2018;
@@ -10,7 +14,7 @@ const DAY: u8 = 2;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day02 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +25,80 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// compare IDs in parallel in part 2
+    #[structopt(long)]
+    parallel: bool,
+}
+
+/// Where the input comes from.
+enum Source {
+    File(PathBuf),
+    /// Standard input, read a line at a time.
+    Stdin,
+    /// Standard input, read into memory because part 2 needs all of it.
+    Buffered(String),
+}
+
+impl Source {
+    /// Solve part 1, streaming the input if it isn't already in memory.
+    fn part1(&self) -> Result<String> {
+        Ok(match self {
+            Source::File(path) => part1_from_reader(BufReader::new(File::open(path)?))?,
+            Source::Stdin => {
+                let stdin = std::io::stdin();
+                part1_from_reader(stdin.lock())?
+            }
+            Source::Buffered(input) => part1_from_str(input)?,
+        })
+    }
+
+    /// Read the whole input into memory.
+    fn into_string(self) -> Result<String> {
+        Ok(match self {
+            Source::File(path) => util::read_input(path)?,
+            Source::Stdin => util::read_stdin()?,
+            Source::Buffered(input) => input,
+        })
+    }
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn source(&self) -> Result<Source> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            // stdin can only be read once, so part 2 needs it buffered
+            Some(ref path) if path == Path::new("-") && self.part2 => {
+                return Ok(Source::Buffered(Source::Stdin.into_string()?));
+            }
+            Some(ref path) if path == Path::new("-") => return Ok(Source::Stdin),
+            Some(ref path) => path.clone(),
+        };
+        Ok(Source::File(path))
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let source = args.source()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", source.part1()?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        let input = source.into_string()?;
+        if args.parallel {
+            println!("{}", part2_parallel_from_str(&input)?);
+        } else {
+            println!("{}", part2_from_str(&input)?);
+        }
     }
     Ok(())
 }
@@ -0,0 +1,57 @@
+/// The number of claims covering a square inch of fabric, saturating at [`Count::MAX`].
+///
+/// Two bytes is plenty for any real input. Once saturated, a count no longer knows how many
+/// claims it has seen, so it stays saturated even as claims are removed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Count(u16);
+
+impl Count {
+    pub const MAX: Count = Count(u16::MAX);
+
+    pub fn get(self) -> u16 {
+        self.0
+    }
+
+    pub fn is_saturated(self) -> bool {
+        self == Self::MAX
+    }
+
+    /// Count one more claim, unless saturated.
+    pub(crate) fn increment(&mut self) {
+        self.0 = self.0.saturating_add(1);
+    }
+
+    /// Count one fewer claim, unless saturated.
+    pub(crate) fn decrement(&mut self) {
+        if !self.is_saturated() {
+            debug_assert_ne!(self.0, 0, "removed a claim which wasn't counted");
+            self.0 -= 1;
+        }
+    }
+}
+
+impl From<Count> for u32 {
+    fn from(count: Count) -> Self {
+        count.0.into()
+    }
+}
+
+/// What a [`Fabric`](crate::Fabric) does with a claim which would increment a saturated
+/// [`Count`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountPolicy {
+    /// Reject the claim with [`Error::CountOverflow`](crate::Error::CountOverflow).
+    Strict,
+    /// Add the claim, leaving saturated counts saturated.
+    ///
+    /// Overlaps are still exact while claims are only added. Removing claims from a
+    /// saturated square inch leaves it saturated, so it may go on counting as an overlap
+    /// after all but one of its claims are gone.
+    Saturate,
+}
+
+impl Default for CountPolicy {
+    fn default() -> Self {
+        CountPolicy::Saturate
+    }
+}
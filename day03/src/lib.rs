@@ -1,63 +1,487 @@
-use aoclib::{geometry::Point, parse};
-use std::{borrow::Borrow, path::Path};
+mod count;
+mod rect;
 
-type Map = aoclib::geometry::Map<u32>;
+use aoclib::geometry::Point;
+use std::{collections::BTreeMap, path::Path};
+use util::parse_str;
+
+pub use count::{Count, CountPolicy};
+pub use rect::{overlap_area, Rect};
+
+/// The number of claims covering each square inch of fabric.
+pub type Map = aoclib::geometry::Map<Count>;
 const EDGE: usize = 1000;
 
 #[derive(Debug, Clone, parse_display::Display, parse_display::FromStr)]
 #[display("#{id} @ {x},{y}: {width}x{height}")]
-struct Claim {
-    id: u32,
-    x: u32,
-    y: u32,
-    width: u32,
-    height: u32,
+pub struct Claim {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
 }
 
 impl Claim {
-    fn iter_points(&self) -> impl '_ + Iterator<Item = Point> {
-        (self.y..self.y + self.height).flat_map(move |y| {
-            (self.x..self.x + self.width).map(move |x| Point::new(x as i32, y as i32))
-        })
+    /// The region of fabric this claim covers.
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    fn iter_points(&self) -> impl Iterator<Item = Point> {
+        self.rect().iter_points()
     }
 }
 
-fn make_map<I, B>(claims: I) -> Map
-where
-    I: IntoIterator<Item = B>,
-    B: Borrow<Claim>,
-{
-    let mut map = Map::new(EDGE, EDGE);
-    for claim in claims {
-        for point in claim.borrow().iter_points() {
-            map[point] += 1;
+/// The smallest rectangle covering every claim, or `None` if there are no claims.
+pub fn bounding_box(claims: &[Claim]) -> Option<Rect> {
+    claims
+        .iter()
+        .map(Claim::rect)
+        .reduce(|bounds, rect| bounds.union(&rect))
+}
+
+impl From<&Claim> for Rect {
+    fn from(claim: &Claim) -> Self {
+        claim.rect()
+    }
+}
+
+/// Iterate over the ids of claims which intersect no other claim, in input order.
+///
+/// Unlike [`Fabric::uncontended_claims`], this works directly on the claims' rectangles,
+/// so it needs no map, and its cost doesn't depend on the size of the claims.
+pub fn uncontended_claims(claims: &[Claim]) -> impl '_ + Iterator<Item = u32> {
+    claims.iter().enumerate().filter_map(move |(idx, claim)| {
+        let rect = claim.rect();
+        claims
+            .iter()
+            .enumerate()
+            .all(|(other_idx, other)| other_idx == idx || rect.intersect(&other.rect()).is_none())
+            .then(|| claim.id)
+    })
+}
+
+/// The number of claims covering `point`; none, if it's beyond the map.
+fn coverage(map: &Map, point: Point) -> u16 {
+    if map.in_bounds(point) {
+        map[point].get()
+    } else {
+        0
+    }
+}
+
+/// The ids of the claims which overlap no other claim on `map`, in input order.
+///
+/// `map` must count every claim in `claims`, as [`Fabric::map`] does.
+pub fn uncontended(claims: &[Claim], map: &Map) -> Vec<u32> {
+    claims
+        .iter()
+        .filter(|claim| claim.iter_points().all(|point| coverage(map, point) <= 1))
+        .map(|claim| claim.id)
+        .collect()
+}
+
+/// The fraction of `claim`'s area which is also within some other claim on `map`.
+///
+/// `map` must count `claim`, as [`Fabric::map`] does. An empty claim is uncontested.
+pub fn contention_ratio(claim: &Claim, map: &Map) -> f64 {
+    let area = claim.rect().area();
+    if area == 0 {
+        return 0.0;
+    }
+    let contested = claim
+        .iter_points()
+        .filter(|&point| coverage(map, point) >= 2)
+        .count();
+    contested as f64 / area as f64
+}
+
+/// What a [`Fabric`] does with a claim which extends beyond its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsPolicy {
+    /// Reject the claim with [`Error::OutOfBounds`].
+    Strict,
+    /// Keep only the part of the claim within the fabric, with a warning on stderr.
+    ///
+    /// Claims which lie entirely outside the fabric are still rejected.
+    Clip,
+}
+
+impl Default for BoundsPolicy {
+    fn default() -> Self {
+        BoundsPolicy::Strict
+    }
+}
+
+/// A piece of fabric which tracks claims as they are added and removed.
+///
+/// Overlap counts are maintained incrementally, so queries are cheap at any point
+/// in a stream of claims.
+pub struct Fabric {
+    map: Map,
+    bounds: Rect,
+    bounds_policy: BoundsPolicy,
+    count_policy: CountPolicy,
+    claims: BTreeMap<u32, Claim>,
+    overlap_area: usize,
+}
+
+/// The standard fabric: 1000 inches square, with its corner at the origin.
+impl Default for Fabric {
+    fn default() -> Self {
+        Fabric::with_bounds(Rect::new(0, 0, EDGE as u32, EDGE as u32))
+    }
+}
+
+impl Fabric {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a fabric covering exactly `bounds`.
+    pub fn with_bounds(bounds: Rect) -> Self {
+        Fabric {
+            map: Map::new_offset(
+                bounds.origin(),
+                bounds.width as usize,
+                bounds.height as usize,
+            ),
+            bounds,
+            bounds_policy: BoundsPolicy::default(),
+            count_policy: CountPolicy::default(),
+            claims: BTreeMap::new(),
+            overlap_area: 0,
+        }
+    }
+
+    /// Set what happens to claims which extend beyond the fabric's bounds.
+    pub fn with_bounds_policy(mut self, bounds_policy: BoundsPolicy) -> Self {
+        self.bounds_policy = bounds_policy;
+        self
+    }
+
+    /// Set what happens to claims which would overflow the count of claims covering
+    /// some square inch.
+    pub fn with_count_policy(mut self, count_policy: CountPolicy) -> Self {
+        self.count_policy = count_policy;
+        self
+    }
+
+    /// The region this fabric covers.
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Add a claim to the fabric.
+    ///
+    /// Claim ids must be unique among the claims currently on the fabric. Claims must cover
+    /// some fabric. Claims which extend beyond the fabric's bounds are handled according
+    /// to its [`BoundsPolicy`]; a clipped claim is stored as clipped. Claims which would
+    /// overflow a [`Count`] are handled according to its [`CountPolicy`].
+    pub fn add_claim(&mut self, mut claim: Claim) -> Result<(), Error> {
+        if self.claims.contains_key(&claim.id) {
+            return Err(Error::DuplicateClaim(claim.id));
+        }
+        if claim.rect().is_empty() {
+            return Err(Error::EmptyClaim(claim.id));
+        }
+        if !self.bounds.contains_rect(&claim.rect()) {
+            let clipped = match (self.bounds_policy, self.bounds.intersect(&claim.rect())) {
+                (BoundsPolicy::Clip, Some(clipped)) => clipped,
+                _ => return Err(Error::OutOfBounds(claim.id)),
+            };
+            eprintln!(
+                "claim #{} extends beyond the fabric; clipped from {}x{} to {}x{}",
+                claim.id, claim.width, claim.height, clipped.width, clipped.height,
+            );
+            claim = Claim {
+                x: clipped.x,
+                y: clipped.y,
+                width: clipped.width,
+                height: clipped.height,
+                ..claim
+            };
+        }
+
+        if self.count_policy == CountPolicy::Strict
+            && claim
+                .iter_points()
+                .any(|point| self.map[point].is_saturated())
+        {
+            return Err(Error::CountOverflow(claim.id));
+        }
+
+        for point in claim.iter_points() {
+            self.map[point].increment();
+            if self.map[point].get() == 2 {
+                self.overlap_area += 1;
+            }
         }
+        self.claims.insert(claim.id, claim);
+        Ok(())
+    }
+
+    /// Remove a claim from the fabric, returning it if it was present.
+    pub fn remove_claim(&mut self, id: u32) -> Option<Claim> {
+        let claim = self.claims.remove(&id)?;
+        for point in claim.iter_points() {
+            if self.map[point].get() == 2 {
+                self.overlap_area -= 1;
+            }
+            self.map[point].decrement();
+        }
+        Some(claim)
+    }
+
+    /// The number of claims covering each square inch of the fabric.
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    /// The number of square inches of fabric within two or more claims.
+    pub fn overlap_area(&self) -> usize {
+        self.overlap_area
     }
-    map
+
+    /// Iterate over the ids of claims which overlap no other claim, in order.
+    pub fn uncontended_claims(&self) -> impl '_ + Iterator<Item = u32> {
+        self.claims
+            .values()
+            .filter(move |claim| claim.iter_points().all(|point| self.map[point].get() == 1))
+            .map(|claim| claim.id)
+    }
+}
+
+/// Make a fabric just large enough for all the claims, and add them to it.
+fn make_fabric(claims: impl IntoIterator<Item = Claim>) -> Result<Fabric, Error> {
+    let claims: Vec<_> = claims.into_iter().collect();
+    if let Some(empty) = claims.iter().find(|claim| claim.rect().is_empty()) {
+        return Err(Error::EmptyClaim(empty.id));
+    }
+    let mut fabric = match bounding_box(&claims) {
+        Some(bounds) => Fabric::with_bounds(bounds),
+        None => Fabric::new(),
+    };
+    for claim in claims {
+        fabric.add_claim(claim)?;
+    }
+    Ok(fabric)
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let map = make_map(parse::<Claim>(input)?);
-    let n_overlaps = map.iter().filter(|&&used| used > 1).count();
-    println!("num overlaps: {}", n_overlaps);
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let fabric = make_fabric(parse_str::<Claim>(input))?;
+    Ok(format!("num overlaps: {}", fabric.overlap_area()))
+}
+
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let claims: Vec<Claim> = parse(input)?.collect();
-    let map = make_map(&claims);
-    let non_overlapping = claims
-        .iter()
-        .find(|claim| claim.iter_points().all(|point| map[point] == 1))
-        .ok_or(Error::NoSolution)?;
-    println!("non overlapping claim: {}", non_overlapping.id);
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let fabric = make_fabric(parse_str::<Claim>(input))?;
+    let non_overlapping = fabric
+        .uncontended_claims()
+        .next()
+        .ok_or(Error::NoSolution)?;
+    Ok(format!("non overlapping claim: {}", non_overlapping))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("no solution found")]
     NoSolution,
+    #[error("claim #{0} is already on the fabric")]
+    DuplicateClaim(u32),
+    #[error("claim #{0} covers no fabric")]
+    EmptyClaim(u32),
+    #[error("claim #{0} extends beyond the fabric")]
+    OutOfBounds(u32),
+    #[error("claim #{0} would overflow the count of claims covering some square inch")]
+    CountOverflow(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &[&str] = &["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2"];
+
+    fn example_fabric() -> Fabric {
+        make_fabric(EXAMPLE.iter().map(|claim| claim.parse().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let fabric = example_fabric();
+        assert_eq!(fabric.overlap_area(), 4);
+        assert_eq!(fabric.uncontended_claims().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn remove_claim() {
+        let mut fabric = example_fabric();
+        let claim = fabric.remove_claim(2).unwrap();
+        assert_eq!(fabric.overlap_area(), 0);
+        assert_eq!(fabric.uncontended_claims().collect::<Vec<_>>(), vec![1, 3]);
+
+        fabric.add_claim(claim).unwrap();
+        assert_eq!(fabric.overlap_area(), 4);
+        assert!(fabric.remove_claim(2).is_some());
+        assert!(fabric.remove_claim(2).is_none());
+    }
+
+    #[test]
+    fn rect_computations_match_fabric() {
+        let claims: Vec<Claim> = EXAMPLE.iter().map(|claim| claim.parse().unwrap()).collect();
+        let rects: Vec<Rect> = claims.iter().map(Into::into).collect();
+        assert_eq!(overlap_area(&rects), example_fabric().overlap_area() as u64);
+        assert_eq!(uncontended_claims(&claims).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn contention() {
+        let claims: Vec<Claim> = EXAMPLE.iter().map(|claim| claim.parse().unwrap()).collect();
+        let fabric = example_fabric();
+        assert_eq!(uncontended(&claims, fabric.map()), vec![3]);
+        let ratios: Vec<f64> = claims
+            .iter()
+            .map(|claim| contention_ratio(claim, fabric.map()))
+            .collect();
+        assert_eq!(ratios, vec![0.25, 0.25, 0.0]);
+
+        let mut fabric = example_fabric();
+        fabric.add_claim("#4 @ 1,1: 2x2".parse().unwrap()).unwrap();
+        let claims: Vec<Claim> = fabric.claims.values().cloned().collect();
+        assert_eq!(uncontended(&claims, fabric.map()), vec![3, 4]);
+
+        fabric.add_claim("#5 @ 5,5: 1x1".parse().unwrap()).unwrap();
+        let claim: Claim = "#3 @ 5,5: 2x2".parse().unwrap();
+        assert_eq!(contention_ratio(&claim, fabric.map()), 0.25);
+    }
+
+    #[test]
+    fn negative_and_large_coordinates() {
+        let claims = ["#1 @ -5,-5: 4x4", "#2 @ -3,-3: 4x4", "#3 @ 1500,2000: 3x3"];
+        let fabric = make_fabric(claims.iter().map(|claim| claim.parse().unwrap())).unwrap();
+        assert_eq!(fabric.bounds(), Rect::new(-5, -5, 1508, 2008));
+        assert_eq!(fabric.overlap_area(), 4);
+        assert_eq!(fabric.uncontended_claims().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn empty_claim() {
+        let claims = ["#1 @ 1,3: 4x4", "#2 @ 3,1: 0x4"];
+        assert!(matches!(
+            make_fabric(claims.iter().map(|claim| claim.parse().unwrap())),
+            Err(Error::EmptyClaim(2))
+        ));
+        assert!(matches!(
+            Fabric::new().add_claim("#7 @ 3,1: 4x0".parse().unwrap()),
+            Err(Error::EmptyClaim(7))
+        ));
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let mut fabric = Fabric::new();
+        for claim in &["#1 @ -1,3: 4x4", "#2 @ 998,0: 3x1"] {
+            let claim: Claim = claim.parse().unwrap();
+            let id = claim.id;
+            assert!(matches!(fabric.add_claim(claim), Err(Error::OutOfBounds(i)) if i == id));
+        }
+        assert_eq!(fabric.overlap_area(), 0);
+    }
+
+    #[test]
+    fn clip_out_of_bounds() {
+        let mut fabric = Fabric::new().with_bounds_policy(BoundsPolicy::Clip);
+        for claim in &["#1 @ -1,3: 4x4", "#2 @ 998,0: 3x1", "#3 @ 0,4: 1x1"] {
+            fabric.add_claim(claim.parse().unwrap()).unwrap();
+        }
+        // #1 is clipped to 3x4 at 0,3, and so overlaps #3 at 0,4
+        assert_eq!(fabric.overlap_area(), 1);
+        assert_eq!(fabric.uncontended_claims().collect::<Vec<_>>(), vec![2]);
+
+        let clipped = fabric.remove_claim(2).unwrap();
+        assert_eq!(clipped.rect(), Rect::new(998, 0, 2, 1));
+
+        // nothing to clip to
+        assert!(matches!(
+            fabric.add_claim("#4 @ 1000,0: 1x1".parse().unwrap()),
+            Err(Error::OutOfBounds(4))
+        ));
+    }
+
+    /// Add `n` claims of the same square inch, with ids from 1.
+    fn pile_up(fabric: &mut Fabric, n: u32) -> Result<(), Error> {
+        for id in 1..=n {
+            fabric.add_claim(Claim {
+                id,
+                x: 2,
+                y: 2,
+                width: 1,
+                height: 1,
+            })?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn strict_counts() {
+        let max = u32::from(Count::MAX);
+        let mut fabric = Fabric::new().with_count_policy(CountPolicy::Strict);
+        pile_up(&mut fabric, max).unwrap();
+        assert!(fabric.map()[(2, 2)].is_saturated());
+        assert!(matches!(
+            fabric.add_claim("#0 @ 1,1: 2x2".parse().unwrap()),
+            Err(Error::CountOverflow(0))
+        ));
+        // the rejected claim left no trace
+        assert_eq!(fabric.map()[(1, 1)], Count::default());
+        assert_eq!(fabric.overlap_area(), 1);
+
+        fabric.remove_claim(1).unwrap();
+        assert_eq!(fabric.map()[(2, 2)].get(), u16::MAX);
+        assert_eq!(fabric.overlap_area(), 1);
+    }
+
+    #[test]
+    fn saturating_counts() {
+        let max = u32::from(Count::MAX);
+        let mut fabric = Fabric::new();
+        pile_up(&mut fabric, max + 10).unwrap();
+        assert!(fabric.map()[(2, 2)].is_saturated());
+        assert_eq!(fabric.overlap_area(), 1);
+
+        fabric.remove_claim(1).unwrap();
+        assert!(fabric.map()[(2, 2)].is_saturated());
+        assert_eq!(fabric.overlap_area(), 1);
+        assert_eq!(std::mem::size_of::<Count>(), 2);
+    }
+
+    #[test]
+    fn duplicate_claim() {
+        let mut fabric = example_fabric();
+        let claim = EXAMPLE[0].parse().unwrap();
+        assert!(matches!(
+            fabric.add_claim(claim),
+            Err(Error::DuplicateClaim(1))
+        ));
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day03-crlf", &EXAMPLE.join("\n")).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(part1_from_str(&input).unwrap(), "num overlaps: 4");
+    }
 }
@@ -1,16 +1,16 @@
 use aoclib::{config::Config, website::get_input};
-use day03::{part1, part2};
+use day03::{part1_from_str, part2_from_str};
 
 use color_eyre::eyre::Result;
-use structopt::StructOpt;
 use std::path::PathBuf;
+use structopt::StructOpt;
 
 const YEAR: u32 = 2018;
 const DAY: u8 = 3;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day03 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -24,30 +24,31 @@ struct RunArgs {
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_from_str(&input)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        println!("{}", part2_from_str(&input)?);
     }
     Ok(())
 }
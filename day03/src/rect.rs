@@ -0,0 +1,218 @@
+use aoclib::geometry::Point;
+
+/// An axis-aligned rectangle of whole square inches.
+///
+/// `(x, y)` is the corner with the least coordinates; the rectangle covers `x..x + width`
+/// and `y..y + height`. Coordinates may be negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The exclusive upper bound on `x`.
+    pub fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    /// The exclusive upper bound on `y`.
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    /// The least corner of the rectangle.
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.x..self.right()).contains(&point.x) && (self.y..self.bottom()).contains(&point.y)
+    }
+
+    /// Whether `other` lies entirely within this rectangle.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// The smallest rectangle covering both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect::new(x, y, (right - x) as u32, (bottom - y) as u32)
+    }
+
+    /// The region covered by both rectangles, if any.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (x < right && y < bottom).then(|| Rect::new(x, y, (right - x) as u32, (bottom - y) as u32))
+    }
+
+    /// Iterate over every point in the rectangle, row by row.
+    pub fn iter_points(&self) -> impl Iterator<Item = Point> {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+        (y..y + height as i32)
+            .flat_map(move |y| (x..x + width as i32).map(move |x| Point::new(x, y)))
+    }
+}
+
+/// Compute the area covered by at least two of the given rectangles.
+///
+/// This compresses the plane along the rectangles' edges, so its cost depends on the number
+/// of rectangles rather than on their size.
+pub fn overlap_area<'a>(rects: impl IntoIterator<Item = &'a Rect>) -> u64 {
+    let rects: Vec<_> = rects.into_iter().filter(|rect| !rect.is_empty()).collect();
+
+    let compress = |edges: &mut Vec<i32>| {
+        edges.sort_unstable();
+        edges.dedup();
+    };
+    let mut xs: Vec<_> = rects.iter().flat_map(|r| vec![r.x, r.right()]).collect();
+    let mut ys: Vec<_> = rects.iter().flat_map(|r| vec![r.y, r.bottom()]).collect();
+    compress(&mut xs);
+    compress(&mut ys);
+    if xs.len() < 2 || ys.len() < 2 {
+        return 0;
+    }
+
+    let position = |edges: &[i32], edge| edges.binary_search(&edge).expect("edge was recorded");
+    // 2d difference array over the compressed cells
+    let width = xs.len();
+    let mut coverage = vec![0_i32; width * ys.len()];
+    for rect in &rects {
+        let (x0, x1) = (position(&xs, rect.x), position(&xs, rect.right()));
+        let (y0, y1) = (position(&ys, rect.y), position(&ys, rect.bottom()));
+        coverage[y0 * width + x0] += 1;
+        coverage[y0 * width + x1] -= 1;
+        coverage[y1 * width + x0] -= 1;
+        coverage[y1 * width + x1] += 1;
+    }
+    // prefix sums turn the differences into per-cell coverage counts
+    for y in 0..ys.len() {
+        for x in 0..width {
+            let mut value = coverage[y * width + x];
+            if x > 0 {
+                value += coverage[y * width + x - 1];
+            }
+            if y > 0 {
+                value += coverage[(y - 1) * width + x];
+            }
+            if x > 0 && y > 0 {
+                value -= coverage[(y - 1) * width + x - 1];
+            }
+            coverage[y * width + x] = value;
+        }
+    }
+
+    let mut area = 0;
+    for y in 0..ys.len() - 1 {
+        for x in 0..width - 1 {
+            if coverage[y * width + x] >= 2 {
+                area += (xs[x + 1] - xs[x]) as u64 * (ys[y + 1] - ys[y]) as u64;
+            }
+        }
+    }
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersect() {
+        let a = Rect::new(1, 3, 4, 4);
+        let b = Rect::new(3, 1, 4, 4);
+        let c = Rect::new(5, 5, 2, 2);
+        assert_eq!(a.intersect(&b), Some(Rect::new(3, 3, 2, 2)));
+        assert_eq!(b.intersect(&a), a.intersect(&b));
+        assert_eq!(a.intersect(&c), None);
+        // touching edges don't overlap
+        assert_eq!(
+            Rect::new(0, 0, 2, 2).intersect(&Rect::new(2, 0, 2, 2)),
+            None
+        );
+    }
+
+    #[test]
+    fn iter_points() {
+        let rect = Rect::new(2, 3, 3, 2);
+        let points: Vec<_> = rect.iter_points().collect();
+        assert_eq!(points.len() as u64, rect.area());
+        assert!(points.iter().all(|&point| rect.contains(point)));
+        assert!(!rect.contains(Point::new(5, 3)));
+        assert!(!rect.contains(Point::new(-1, 3)));
+
+        let negative = Rect::new(-3, -2, 2, 2);
+        assert!(negative.contains(Point::new(-2, -1)));
+        assert!(!negative.contains(Point::new(-1, -1)));
+        assert_eq!(negative.iter_points().count(), 4);
+    }
+
+    #[test]
+    fn union() {
+        let a = Rect::new(-2, 3, 2, 1);
+        let b = Rect::new(1, -1, 3, 2);
+        let union = a.union(&b);
+        assert_eq!(union, Rect::new(-2, -1, 6, 5));
+        assert!(union.contains_rect(&a) && union.contains_rect(&b));
+        assert!(!a.contains_rect(&union));
+    }
+
+    #[test]
+    fn overlap_area_example() {
+        let rects = [
+            Rect::new(1, 3, 4, 4),
+            Rect::new(3, 1, 4, 4),
+            Rect::new(5, 5, 2, 2),
+        ];
+        assert_eq!(overlap_area(&rects), 4);
+    }
+
+    #[test]
+    fn overlap_area_counts_once() {
+        // three rectangles stacked on the same square still only overlap by its area
+        let rects = [Rect::new(0, 0, 3, 3); 3];
+        assert_eq!(overlap_area(&rects), 9);
+        assert_eq!(overlap_area(&rects[..1]), 0);
+        assert_eq!(overlap_area(&[]), 0);
+    }
+
+    #[test]
+    fn overlap_area_negative() {
+        let rects = [Rect::new(-5, -5, 4, 4), Rect::new(-3, -3, 4, 4)];
+        assert_eq!(overlap_area(&rects), 4);
+    }
+}
@@ -1,27 +1,62 @@
-use aoclib::parse;
-use std::{collections::HashMap, convert::TryInto, path::Path};
+mod schedule;
 
-type Id = u32;
-type Minute = u32;
+pub use schedule::{Analysis, Choice, GuardSchedule};
 
-#[derive(
-    Clone,
-    Copy,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    Debug,
-    parse_display::FromStr,
-    parse_display::Display,
-)]
-#[display("{year}-{month}-{day} {hour}:{minute}")]
-struct Timestamp {
-    year: i16,
-    month: u8,
-    day: u8,
-    hour: u8,
-    minute: u8,
+use chrono::{Duration, NaiveDateTime, Timelike};
+use std::{collections::HashMap, convert::TryInto, fmt, ops::Range, path::Path, str::FromStr};
+use util::try_parse_str;
+
+pub type Id = u32;
+pub type Minute = u32;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// A validated timestamp, like `1518-11-01 00:05`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct Timestamp(NaiveDateTime);
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT)
+            .map(Timestamp)
+            .map_err(|source| Error::InvalidTimestamp {
+                timestamp: s.to_string(),
+                source,
+            })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format(TIMESTAMP_FORMAT))
+    }
+}
+
+impl From<Timestamp> for NaiveDateTime {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+/// The minutes of the midnight hour which fall within `start..end`.
+///
+/// The relevant midnight hour is the first one which begins at or after `start`'s hour,
+/// so a nap beginning before midnight counts from minute 0.
+fn midnight_minutes(start: Timestamp, end: Timestamp) -> Range<Minute> {
+    let (start, end) = (start.0, end.0);
+    let mut midnight = start.date().and_hms(0, 0, 0);
+    if start.hour() != 0 {
+        midnight = midnight + Duration::days(1);
+    }
+
+    let start = start.max(midnight);
+    let end = end.min(midnight + Duration::hours(1));
+    if end <= start {
+        return 0..0;
+    }
+    (start - midnight).num_minutes() as Minute..(end - midnight).num_minutes() as Minute
 }
 
 #[derive(
@@ -44,55 +79,131 @@ enum Action {
     WakeUp,
 }
 
-#[derive(
-    Clone,
-    Copy,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    Debug,
-    parse_display::FromStr,
-    parse_display::Display,
-)]
+impl Action {
+    /// Order of actions which share a timestamp.
+    ///
+    /// Timestamps only have minute resolution. A guard who wakes and dozes off again within
+    /// a minute must wake first, and a guard who wakes as the next shift begins must wake
+    /// before the new guard arrives, who may in turn fall asleep immediately.
+    fn tie_break(self) -> u8 {
+        match self {
+            Action::WakeUp => 0,
+            Action::BeginShift(_) => 1,
+            Action::FallAsleep => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, parse_display::Display)]
 #[display("[{timestamp}] {action}")]
 struct LogEntry {
     timestamp: Timestamp,
     action: Action,
 }
 
+impl FromStr for LogEntry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::MalformedEntry(s.to_string());
+        let (timestamp, action) = s
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .ok_or_else(malformed)?;
+        Ok(LogEntry {
+            timestamp: timestamp.parse()?,
+            action: action.parse().map_err(|_| malformed())?,
+        })
+    }
+}
+
 type AsleepByMinute = [HashMap<Id, Minute>; 60];
 
 /// Produce a data structure recording for each minute, how many times each guard was asleep that minute.
-fn asleep_by_minute(logs: &[LogEntry]) -> AsleepByMinute {
+///
+/// Only minutes within the midnight hour are counted; a nap which begins before midnight
+/// counts from minute 0.
+///
+/// Logs must be sorted as by [`parse_logs`]. Some irregularities are tolerated:
+///
+/// - a repeated `BeginShift` for the guard already on duty is merged into the current shift,
+///   even if that guard is asleep
+/// - a nap which begins and ends within the same minute records nothing
+///
+/// A different guard beginning a shift while the current one sleeps, or two different guards
+/// beginning a shift at the same time, is an error.
+fn asleep_by_minute(logs: &[LogEntry]) -> Result<AsleepByMinute, Error> {
     let asleep = vec![HashMap::default(); 60];
     let mut asleep: [HashMap<_, _>; 60] =
         asleep.try_into().expect("just initialized; never changed");
     let mut guard = None;
+    let mut shift_start = None;
     let mut sleep_start = None;
 
-    for entry in logs {
+    let mut entries = logs.iter().peekable();
+    while let Some(entry) = entries.next() {
         match entry.action {
             Action::BeginShift(id) => {
-                debug_assert!(sleep_start.is_none());
+                if guard == Some(id) {
+                    // a duplicate entry; the shift continues
+                    continue;
+                }
+                if sleep_start.is_some() {
+                    return Err(Error::InvalidSequence(
+                        entry.to_string(),
+                        "shift began while asleep",
+                    ));
+                }
+                if shift_start == Some(entry.timestamp) {
+                    return Err(Error::InvalidSequence(
+                        entry.to_string(),
+                        "another guard began a shift at the same time",
+                    ));
+                }
                 guard = Some(id);
+                shift_start = Some(entry.timestamp);
             }
             Action::FallAsleep => {
-                debug_assert!(sleep_start.is_none());
-                sleep_start = entry.timestamp.minute.into();
+                if guard.is_none() {
+                    return Err(Error::InvalidSequence(
+                        entry.to_string(),
+                        "no guard on duty",
+                    ));
+                }
+                if sleep_start.is_some() {
+                    return Err(Error::InvalidSequence(entry.to_string(), "already asleep"));
+                }
+                sleep_start = Some(entry.timestamp);
             }
             Action::WakeUp => {
-                let sleep_start = sleep_start.take().expect("can't wake if not asleep");
-                let sleep_end = entry.timestamp.minute;
-                let guard = guard.expect("can't wake if not a guard");
-                for minute in sleep_start..sleep_end {
+                let sleep_start = match sleep_start.take() {
+                    Some(sleep_start) => sleep_start,
+                    None => {
+                        // a nap within a single minute sorts as waking, then falling asleep
+                        match entries.peek() {
+                            Some(next)
+                                if next.action == Action::FallAsleep
+                                    && next.timestamp == entry.timestamp
+                                    && guard.is_some() =>
+                            {
+                                entries.next();
+                                continue;
+                            }
+                            _ => {
+                                return Err(Error::InvalidSequence(entry.to_string(), "not asleep"))
+                            }
+                        }
+                    }
+                };
+                let guard = guard.expect("can't be asleep without a guard");
+                for minute in midnight_minutes(sleep_start, entry.timestamp) {
                     *asleep[minute as usize].entry(guard).or_default() += 1;
                 }
             }
         }
     }
 
-    asleep
+    Ok(asleep)
 }
 
 /// count total minutes asleep per guard
@@ -140,37 +251,271 @@ fn most_freq_asleep_per_minute(by_minute: &AsleepByMinute) -> Option<(Id, Minute
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let mut logs: Vec<LogEntry> = parse(input)?.collect();
-    logs.sort_unstable();
-
-    let by_minute = asleep_by_minute(&logs);
-    let sleep_times = total_minutes_by_guard(&by_minute);
-    let sleepiest_guard = sleepiest_guard(&sleep_times).ok_or(Error::NoSolution)?;
-    let sleepiest_minute = sleepiest_minute(sleepiest_guard, &by_minute);
-    println!("part 1");
-    dbg!(
-        sleepiest_guard,
-        sleepiest_minute,
-        sleepiest_guard * sleepiest_minute
-    );
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
-    let mut logs: Vec<LogEntry> = parse(input)?.collect();
-    logs.sort_unstable();
+/// Parse and sort the log entries.
+///
+/// Entries are ordered by their full timestamp, so logs may span several years. Entries
+/// with equal timestamps are ordered by [`Action::tie_break`], then by their order in the input.
+///
+/// Unlike most days, a malformed line is an error instead of being skipped:
+/// dropping an entry would silently corrupt the sleep records.
+fn parse_logs(input: &str) -> Result<Vec<LogEntry>, Error> {
+    let mut logs: Vec<LogEntry> = try_parse_str(input)?;
+    logs.sort_by_key(|entry| (entry.timestamp, entry.action.tie_break()));
+    Ok(logs)
+}
+
+/// Describe the choice of strategy 1.
+pub fn describe_strategy1(choice: Choice) -> String {
+    format!(
+        "sleepiest guard: {}; sleepiest minute: {}; product: {}",
+        choice.guard, choice.minute, choice.score
+    )
+}
+
+/// Describe the choice of strategy 2.
+pub fn describe_strategy2(choice: Choice) -> String {
+    format!(
+        "most frequently asleep guard: {}; minute: {}; product: {}",
+        choice.guard, choice.minute, choice.score
+    )
+}
 
-    let by_minute = asleep_by_minute(&logs);
-    let (guard, minute) = most_freq_asleep_per_minute(&by_minute).ok_or(Error::NoSolution)?;
-    println!("part 2");
-    dbg!(guard, minute, guard * minute);
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let choice = GuardSchedule::from_logs(input)?.strategy1()?;
+    Ok(describe_strategy1(choice))
+}
+
+pub fn part2(input: &Path) -> Result<(), Error> {
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let choice = GuardSchedule::from_logs(input)?.strategy2()?;
+    Ok(describe_strategy2(choice))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("invalid timestamp \"{timestamp}\"")]
+    InvalidTimestamp {
+        timestamp: String,
+        #[source]
+        source: chrono::ParseError,
+    },
+    #[error("malformed log entry: \"{0}\"")]
+    MalformedEntry(String),
+    #[error("invalid log sequence at \"{0}\": {1}")]
+    InvalidSequence(String, &'static str),
     #[error("No solution found")]
     NoSolution,
+    #[cfg(feature = "png")]
+    #[error("encoding png")]
+    Png(#[from] png::EncodingError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) const EXAMPLE: &str = "
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up
+";
+
+    #[test]
+    fn part1_example() {
+        assert!(part1_from_str(EXAMPLE).unwrap().ends_with("product: 240"));
+    }
+
+    #[test]
+    fn part2_example() {
+        assert!(part2_from_str(EXAMPLE).unwrap().ends_with("product: 4455"));
+    }
+
+    #[test]
+    fn sleep_before_midnight() {
+        let logs = parse_logs(
+            "
+[1518-03-04 23:50] Guard #7 begins shift
+[1518-03-04 23:55] falls asleep
+[1518-03-05 00:03] wakes up
+",
+        )
+        .unwrap();
+        let by_minute = asleep_by_minute(&logs).unwrap();
+        assert_eq!(total_minutes_by_guard(&by_minute)[&7], 3);
+        assert_eq!(by_minute[0][&7], 1);
+        assert!(by_minute[3].is_empty());
+    }
+
+    #[test]
+    fn sleep_across_year_end() {
+        let start: Timestamp = "1518-12-31 23:58".parse().unwrap();
+        let end: Timestamp = "1519-01-01 00:02".parse().unwrap();
+        assert_eq!(midnight_minutes(start, end), 0..2);
+    }
+
+    #[test]
+    fn invalid_timestamp() {
+        for timestamp in &["1518-13-01 00:00", "1518-02-30 00:00", "1518-01-01 24:00"] {
+            assert!(matches!(
+                timestamp.parse::<Timestamp>(),
+                Err(Error::InvalidTimestamp { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn logs_spanning_years() {
+        // out of order, across a year boundary; 1519 must not sort before 1518
+        let logs = parse_logs(
+            "
+[1519-01-01 00:10] wakes up
+[1518-12-31 23:59] Guard #3 begins shift
+[1519-01-02 00:00] Guard #3 begins shift
+[1519-01-01 00:05] falls asleep
+[1519-01-02 00:08] falls asleep
+[1519-01-02 00:12] wakes up
+",
+        )
+        .unwrap();
+        assert_eq!(logs[0].action, Action::BeginShift(3));
+        let by_minute = asleep_by_minute(&logs).unwrap();
+        assert_eq!(total_minutes_by_guard(&by_minute)[&3], 9);
+        assert_eq!(by_minute[8][&3], 2);
+    }
+
+    #[test]
+    fn shared_timestamps() {
+        // guard 5 wakes and dozes off again within a minute, and wakes as guard 6 arrives,
+        // who falls asleep at once
+        let logs = parse_logs(
+            "
+[1518-06-01 00:00] Guard #5 begins shift
+[1518-06-01 00:10] falls asleep
+[1518-06-01 00:20] falls asleep
+[1518-06-01 00:20] wakes up
+[1518-06-01 00:30] falls asleep
+[1518-06-01 00:30] wakes up
+[1518-06-01 00:40] wakes up
+[1518-06-01 00:40] Guard #6 begins shift
+[1518-06-01 00:40] falls asleep
+[1518-06-01 00:45] wakes up
+",
+        )
+        .unwrap();
+        let by_minute = asleep_by_minute(&logs).unwrap();
+        let totals = total_minutes_by_guard(&by_minute);
+        assert_eq!(totals[&5], 30);
+        assert_eq!(totals[&6], 5);
+    }
+
+    #[test]
+    fn instantaneous_nap() {
+        let logs = parse_logs(
+            "
+[1518-06-01 00:00] Guard #5 begins shift
+[1518-06-01 00:10] falls asleep
+[1518-06-01 00:10] wakes up
+",
+        )
+        .unwrap();
+        let by_minute = asleep_by_minute(&logs).unwrap();
+        assert!(total_minutes_by_guard(&by_minute).is_empty());
+    }
+
+    #[test]
+    fn repeated_shift_merged() {
+        let logs = parse_logs(
+            "
+[1518-06-01 00:00] Guard #5 begins shift
+[1518-06-01 00:00] Guard #5 begins shift
+[1518-06-01 00:10] falls asleep
+[1518-06-01 00:15] Guard #5 begins shift
+[1518-06-01 00:20] wakes up
+",
+        )
+        .unwrap();
+        let by_minute = asleep_by_minute(&logs).unwrap();
+        assert_eq!(total_minutes_by_guard(&by_minute)[&5], 10);
+    }
+
+    #[test]
+    fn shift_change_while_asleep() {
+        let logs = parse_logs(
+            "
+[1518-06-01 00:00] Guard #5 begins shift
+[1518-06-01 00:10] falls asleep
+[1518-06-01 00:15] Guard #6 begins shift
+[1518-06-01 00:20] wakes up
+",
+        )
+        .unwrap();
+        assert!(matches!(
+            asleep_by_minute(&logs),
+            Err(Error::InvalidSequence(_, "shift began while asleep"))
+        ));
+    }
+
+    #[test]
+    fn simultaneous_shifts() {
+        let logs = parse_logs(
+            "
+[1518-06-01 00:00] Guard #5 begins shift
+[1518-06-01 00:00] Guard #6 begins shift
+",
+        )
+        .unwrap();
+        assert!(matches!(
+            asleep_by_minute(&logs),
+            Err(Error::InvalidSequence(
+                _,
+                "another guard began a shift at the same time"
+            ))
+        ));
+    }
+
+    #[test]
+    fn wake_without_sleep() {
+        let logs = parse_logs(
+            "
+[1518-03-05 00:00] Guard #7 begins shift
+[1518-03-05 00:03] wakes up
+",
+        )
+        .unwrap();
+        assert!(matches!(
+            asleep_by_minute(&logs),
+            Err(Error::InvalidSequence(_, "not asleep"))
+        ));
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day04-crlf", EXAMPLE).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert!(part1_from_str(&input).unwrap().ends_with("product: 240"));
+    }
 }
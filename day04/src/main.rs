@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day04::{part1, part2};
+use day04::{describe_strategy1, describe_strategy2, GuardSchedule};
 
 use color_eyre::eyre::Result;
 use std::path::PathBuf;
@@ -10,7 +10,7 @@ const DAY: u8 = 4;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day04 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +21,53 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// show how often each guard was asleep during each minute
+    #[structopt(long)]
+    render: bool,
+
+    /// write how often each guard was asleep during each minute to a png file
+    #[cfg(feature = "png")]
+    #[structopt(long, parse(from_os_str))]
+    render_png: Option<PathBuf>,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let schedule = GuardSchedule::from_logs(&args.input()?)?;
 
-    if !args.no_part1 {
-        part1(&input_path)?;
+    if !args.no_part1 || args.part2 {
+        let analysis = schedule.analyze()?;
+        if !args.no_part1 {
+            println!("{}", describe_strategy1(analysis.strategy1));
+        }
+        if args.part2 {
+            println!("{}", describe_strategy2(analysis.strategy2));
+        }
+    }
+    if args.render {
+        print!("{}", schedule);
     }
-    if args.part2 {
-        part2(&input_path)?;
+    #[cfg(feature = "png")]
+    if let Some(path) = &args.render_png {
+        schedule.write_png(path, 8)?;
     }
     Ok(())
 }
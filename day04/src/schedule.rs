@@ -0,0 +1,262 @@
+use crate::{
+    asleep_by_minute, most_freq_asleep_per_minute, parse_logs, sleepiest_guard, sleepiest_minute,
+    total_minutes_by_guard, AsleepByMinute, Error, Id, Minute,
+};
+use std::fmt;
+
+/// A guard chosen by one of the strategies, and the minute to sneak in during their shift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Choice {
+    pub guard: Id,
+    pub minute: Minute,
+    /// The puzzle's answer: the guard's id multiplied by the minute.
+    pub score: u64,
+}
+
+impl Choice {
+    fn new(guard: Id, minute: Minute) -> Self {
+        Choice {
+            guard,
+            minute,
+            score: guard as u64 * minute as u64,
+        }
+    }
+}
+
+/// The choices of both strategies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Analysis {
+    pub strategy1: Choice,
+    pub strategy2: Choice,
+}
+
+/// How many times each guard was asleep during each minute of the midnight hour.
+#[derive(Debug, Clone)]
+pub struct GuardSchedule {
+    by_minute: AsleepByMinute,
+}
+
+impl GuardSchedule {
+    /// Parse and validate a guard log.
+    pub fn from_logs(input: &str) -> Result<Self, Error> {
+        let logs = parse_logs(input)?;
+        let by_minute = asleep_by_minute(&logs)?;
+        Ok(GuardSchedule { by_minute })
+    }
+
+    /// Every guard who was ever asleep, in ascending order.
+    pub fn guards(&self) -> Vec<Id> {
+        let mut guards: Vec<Id> = self
+            .by_minute
+            .iter()
+            .flat_map(|by_guard| by_guard.keys().copied())
+            .collect();
+        guards.sort_unstable();
+        guards.dedup();
+        guards
+    }
+
+    /// The number of times `guard` was asleep during `minute`.
+    pub fn times_asleep(&self, guard: Id, minute: Minute) -> Minute {
+        self.by_minute
+            .get(minute as usize)
+            .and_then(|by_guard| by_guard.get(&guard))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The number of times `guard` was asleep during each minute.
+    pub fn histogram(&self, guard: Id) -> [Minute; 60] {
+        let mut histogram = [0; 60];
+        for (minute, times_asleep) in histogram.iter_mut().enumerate() {
+            *times_asleep = self.times_asleep(guard, minute as Minute);
+        }
+        histogram
+    }
+
+    /// Strategy 1: choose the guard asleep the most minutes in total, and the minute they
+    /// were most often asleep.
+    ///
+    /// Fails with [`Error::NoSolution`] if no guard was ever asleep.
+    pub fn strategy1(&self) -> Result<Choice, Error> {
+        let guard =
+            sleepiest_guard(&total_minutes_by_guard(&self.by_minute)).ok_or(Error::NoSolution)?;
+        Ok(Choice::new(guard, sleepiest_minute(guard, &self.by_minute)))
+    }
+
+    /// Strategy 2: choose the guard most often asleep during the same minute, and that minute.
+    ///
+    /// Fails with [`Error::NoSolution`] if no guard was ever asleep.
+    pub fn strategy2(&self) -> Result<Choice, Error> {
+        let (guard, minute) =
+            most_freq_asleep_per_minute(&self.by_minute).ok_or(Error::NoSolution)?;
+        Ok(Choice::new(guard, minute))
+    }
+
+    /// Apply both strategies.
+    pub fn analyze(&self) -> Result<Analysis, Error> {
+        Ok(Analysis {
+            strategy1: self.strategy1()?,
+            strategy2: self.strategy2()?,
+        })
+    }
+
+    /// The greatest number of times any guard was asleep during any minute.
+    pub fn max_times_asleep(&self) -> Minute {
+        self.by_minute
+            .iter()
+            .flat_map(|by_guard| by_guard.values().copied())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Render the schedule as a greyscale image: guards are rows, in ascending order, and
+    /// minutes are columns. Each cell is a square `scale` pixels on a side, brighter where
+    /// the guard was asleep more often.
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, path: &std::path::Path, scale: usize) -> Result<(), Error> {
+        let guards = self.guards();
+        let scale = scale.max(1);
+        let (width, height) = (60 * scale, guards.len() * scale);
+        let max_times_asleep = self.max_times_asleep().max(1);
+        let mut data = Vec::with_capacity(width * height);
+        for &guard in &guards {
+            let row: Vec<u8> = self
+                .histogram(guard)
+                .iter()
+                .flat_map(|&times_asleep| {
+                    let intensity = (255 * times_asleep / max_times_asleep) as u8;
+                    std::iter::repeat(intensity).take(scale)
+                })
+                .collect();
+            for _ in 0..scale {
+                data.extend_from_slice(&row);
+            }
+        }
+
+        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+}
+
+/// An aligned table: guards are rows, and minutes are columns.
+///
+/// Each cell is the number of times the guard was asleep that minute, or `.` for never.
+impl fmt::Display for GuardSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let guards = self.guards();
+        let guard_width = guards
+            .iter()
+            .map(|guard| guard.to_string().len() + 1)
+            .max()
+            .unwrap_or_default()
+            .max("guard".len());
+        let cell_width = self.max_times_asleep().to_string().len().max(2);
+
+        write!(f, "{:>width$}", "guard", width = guard_width)?;
+        for minute in 0..60 {
+            write!(
+                f,
+                " {:>width$}",
+                format!("{:02}", minute),
+                width = cell_width
+            )?;
+        }
+        writeln!(f)?;
+
+        for guard in guards {
+            write!(f, "{:>width$}", format!("#{}", guard), width = guard_width)?;
+            for times_asleep in self.histogram(guard).iter() {
+                if *times_asleep == 0 {
+                    write!(f, " {:>width$}", ".", width = cell_width)?;
+                } else {
+                    write!(f, " {:>width$}", times_asleep, width = cell_width)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+
+    #[test]
+    fn example_histograms() {
+        let schedule = GuardSchedule::from_logs(EXAMPLE).unwrap();
+        assert_eq!(schedule.guards(), vec![10, 99]);
+        assert_eq!(schedule.times_asleep(10, 24), 2);
+        assert_eq!(schedule.times_asleep(99, 45), 3);
+        assert_eq!(schedule.times_asleep(99, 60), 0);
+        assert_eq!(schedule.histogram(10).iter().sum::<Minute>(), 50);
+        assert_eq!(schedule.max_times_asleep(), 3);
+    }
+
+    #[test]
+    fn example_strategies() {
+        let schedule = GuardSchedule::from_logs(EXAMPLE).unwrap();
+        let strategy1 = Choice {
+            guard: 10,
+            minute: 24,
+            score: 240,
+        };
+        let strategy2 = Choice {
+            guard: 99,
+            minute: 45,
+            score: 4455,
+        };
+        assert_eq!(schedule.strategy1().unwrap(), strategy1);
+        assert_eq!(schedule.strategy2().unwrap(), strategy2);
+        assert_eq!(
+            schedule.analyze().unwrap(),
+            Analysis {
+                strategy1,
+                strategy2
+            }
+        );
+    }
+
+    #[test]
+    fn strategies_without_sleep() {
+        let schedule =
+            GuardSchedule::from_logs("[1518-11-01 00:00] Guard #10 begins shift").unwrap();
+        assert!(matches!(schedule.strategy1(), Err(Error::NoSolution)));
+        assert!(matches!(schedule.strategy2(), Err(Error::NoSolution)));
+        assert!(matches!(schedule.analyze(), Err(Error::NoSolution)));
+    }
+
+    #[test]
+    fn large_score() {
+        let schedule = GuardSchedule::from_logs(
+            "
+[1518-11-01 00:00] Guard #4000000000 begins shift
+[1518-11-01 00:58] falls asleep
+[1518-11-01 00:59] wakes up
+",
+        )
+        .unwrap();
+        assert_eq!(schedule.strategy1().unwrap().score, 4_000_000_000 * 58);
+    }
+
+    #[test]
+    fn example_table() {
+        let schedule = GuardSchedule::from_logs(EXAMPLE).unwrap();
+        let table = schedule.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("guard 00 01"));
+        assert!(lines[0].ends_with(" 59"));
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+        // guard 10 naps 00:05-00:25 one night and 00:24-00:29 another
+        assert!(lines[1].starts_with("  #10  .  .  .  .  .  1"));
+        assert_eq!(&lines[1][5 + 3 * 24..5 + 3 * 25], "  2");
+        assert_eq!(&lines[2][5 + 3 * 45..5 + 3 * 46], "  3");
+    }
+}
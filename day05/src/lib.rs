@@ -1,81 +1,153 @@
-use aoclib::parse;
-use bitvec::{bitvec, order::LocalBits};
+mod unicode;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use std::{collections::HashSet, path::Path, string::FromUtf8Error};
+pub use unicode::{fold, minimal_reaction_unicode, react_unicode, reacts_folded, UnicodeMode};
+use util::parse_str;
 
-fn reacts(a: u8, b: u8) -> bool {
-    debug_assert!(a.is_ascii_alphabetic());
-    debug_assert!(b.is_ascii_alphabetic());
-    a != b && a.eq_ignore_ascii_case(&b)
+/// Determines which sequences of units react away.
+pub trait Reactivity {
+    /// Given the unreacted polymer so far, ending with the unit most recently added,
+    /// return how many units at the end react away. `0` means nothing reacts.
+    ///
+    /// Implementations only need to inspect as many trailing units as their longest reaction.
+    fn reaction_len(&self, polymer: &[u8]) -> usize;
+}
+
+/// The AoC rule: adjacent units of the same type and opposite polarity annihilate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AocRule;
+
+impl Reactivity for AocRule {
+    fn reaction_len(&self, polymer: &[u8]) -> usize {
+        match polymer {
+            [.., a, b] if reacts(*a, *b) => 2,
+            _ => 0,
+        }
+    }
 }
 
-/// Perform the entire reaction in a single pass, using two pointers into the
-/// input data.
-fn react_to_completion(data: &[u8]) -> Vec<u8> {
-    if data.len() < 2 {
-        return data.into();
+/// Any predicate over a pair of adjacent units is a reactivity rule.
+impl<F> Reactivity for F
+where
+    F: Fn(u8, u8) -> bool,
+{
+    fn reaction_len(&self, polymer: &[u8]) -> usize {
+        match polymer {
+            [.., a, b] if self(*a, *b) => 2,
+            _ => 0,
+        }
     }
+}
 
-    // vector of items which have been reacted / excluded
-    let mut exclusions = bitvec![LocalBits, u32; 0; data.len()];
+/// An explicit table of reactive sequences. Sequences which aren't listed are inert.
+///
+/// When several sequences match the end of the polymer, the longest reacts.
+#[derive(Debug, Default, Clone)]
+pub struct ReactionTable {
+    sequences: HashSet<Vec<u8>>,
+    max_len: usize,
+}
 
-    // two pointers into the data
-    let mut lead = 1;
-    let mut trail = 0;
+impl ReactionTable {
+    pub fn new<I, S>(sequences: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Vec<u8>>,
+    {
+        let sequences: HashSet<Vec<u8>> = sequences
+            .into_iter()
+            .map(Into::into)
+            .filter(|sequence| !sequence.is_empty())
+            .collect();
+        let max_len = sequences.iter().map(Vec::len).max().unwrap_or_default();
+        ReactionTable { sequences, max_len }
+    }
+}
 
-    // this is worst case of O(n**2) in the event that all elements are excluded.
-    // the simplest way to accomplish that is to ensure that all adjacent elements pair each other.
-    // however, I'm pretty confident that the actual length of runs of reactions in the input will
-    // be short enough that we don't mind.
-    while lead < data.len() {
-        // neither lead nor trail must currently be excluded
-        debug_assert!(!exclusions[lead]);
-        debug_assert!(!exclusions[trail]);
+impl Reactivity for ReactionTable {
+    fn reaction_len(&self, polymer: &[u8]) -> usize {
+        (1..=self.max_len.min(polymer.len()))
+            .rev()
+            .find(|&len| self.sequences.contains(&polymer[polymer.len() - len..]))
+            .unwrap_or_default()
+    }
+}
 
-        if reacts(data[lead], data[trail]) {
-            // exclude both of these elements; they reacted away
-            exclusions.set(lead, true);
-            exclusions.set(trail, true);
+/// Only ASCII letters react. Any other byte, including every byte of a multi-byte
+/// UTF-8 sequence, is inert.
+fn reacts(a: u8, b: u8) -> bool {
+    a != b && a.eq_ignore_ascii_case(&b)
+}
 
-            // advance the lead
-            lead += 1;
+/// Perform the entire reaction in a single pass.
+///
+/// Unreacted units are kept on a stack. Each new unit is pushed, and then any
+/// reactions at the top of the stack are resolved. Because a reaction can only
+/// expose units which were previously unreactive, this reaches the same result
+/// as repeatedly scanning the whole polymer.
+pub fn react_to_completion(data: &[u8], reactivity: &impl Reactivity) -> Vec<u8> {
+    let mut stack = Vec::with_capacity(data.len());
+    react_onto(&mut stack, data, reactivity);
+    stack
+}
 
-            // trail backs up to the most recent non-excluded char
-            while trail > 0 && exclusions[trail] {
-                trail -= 1;
-            }
-            // if we run out of chars, we might need to reset entirely
-            if trail == 0 && exclusions[trail] {
-                trail = lead;
-                lead += 1;
+/// Push each unit of `data` onto `stack`, resolving reactions as they occur.
+fn react_onto(stack: &mut Vec<u8>, data: &[u8], reactivity: &impl Reactivity) {
+    for &unit in data {
+        stack.push(unit);
+        loop {
+            let len = reactivity.reaction_len(stack);
+            if len == 0 {
+                break;
             }
-        } else {
-            trail = lead;
-            lead += 1;
+            stack.truncate(stack.len() - len);
         }
     }
+}
 
-    // the output is the input minus all exclusions
-    data.iter()
-        .copied()
-        .zip(exclusions.into_iter())
-        .filter_map(|(byte, excluded)| (!excluded).then(move || byte))
-        .collect()
+/// Perform the entire reaction under the [`AocRule`], reacting chunks in parallel.
+///
+/// Each chunk of `chunk_size` units is reacted on its own, then adjacent results are
+/// stitched together by reacting the right one onto the left. Under the AoC rule a fully
+/// reacted polymer never retains a reactive pair, so reacting pieces first can't change
+/// the final result, and the stitching can happen in any grouping.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is zero.
+#[cfg(feature = "rayon")]
+pub fn react_parallel(data: &[u8], chunk_size: usize) -> Vec<u8> {
+    assert_ne!(chunk_size, 0, "chunks must not be empty");
+    data.par_chunks(chunk_size)
+        .map(|chunk| react_to_completion(chunk, &AocRule))
+        .reduce(Vec::new, |mut left, right| {
+            react_onto(&mut left, &right, &AocRule);
+            left
+        })
 }
 
-fn react_str(polymer: String) -> Result<String, Error> {
-    String::from_utf8(react_to_completion(polymer.as_bytes())).map_err(Into::into)
+pub fn react_str(polymer: String, reactivity: &impl Reactivity) -> Result<String, Error> {
+    String::from_utf8(react_to_completion(polymer.as_bytes(), reactivity)).map_err(Into::into)
 }
 
-fn react_completele_excluding(polymer: &[u8], excluding: u8) -> Vec<u8> {
+fn react_completele_excluding(
+    polymer: &[u8],
+    excluding: u8,
+    reactivity: &impl Reactivity,
+) -> Vec<u8> {
     let smallymer: Vec<_> = polymer
         .iter()
         .copied()
         .filter(|byte| !excluding.eq_ignore_ascii_case(byte))
         .collect();
-    react_to_completion(&smallymer)
+    react_to_completion(&smallymer, reactivity)
 }
 
-fn minimal_reaction(polymer: &str) -> Result<String, Error> {
+/// Find the shortest polymer obtainable by removing all units of a single type
+/// (ignoring case) before reacting.
+pub fn minimal_reaction(polymer: &str, reactivity: &impl Reactivity) -> Result<String, Error> {
     let chars: HashSet<_> = polymer
         .as_bytes()
         .iter()
@@ -86,7 +158,7 @@ fn minimal_reaction(polymer: &str) -> Result<String, Error> {
     let (_, shortest_reacted_polymer) = chars
         .into_iter()
         .map(|excluding| {
-            let reacted = react_completele_excluding(polymer.as_bytes(), excluding);
+            let reacted = react_completele_excluding(polymer.as_bytes(), excluding, reactivity);
             (reacted.len(), reacted)
         })
         .min()
@@ -96,23 +168,60 @@ fn minimal_reaction(polymer: &str) -> Result<String, Error> {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    for (idx, data) in parse::<String>(input)?.enumerate() {
-        let reacted = react_str(data)?;
-        println!("{}: fully reacted len: {}", idx, reacted.len());
-    }
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with_mode(input, UnicodeMode::default())
+}
+
+/// Solve part 1, treating units which aren't ASCII letters according to `mode`.
+///
+/// Lengths are counted in units.
+pub fn part1_with_mode(input: &str, mode: UnicodeMode) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for (idx, data) in parse_str::<String>(input).enumerate() {
+        let reacted_len = match mode {
+            UnicodeMode::Reject => {
+                unicode::check_ascii(idx, &data)?;
+                react_str(data, &AocRule)?.len()
+            }
+            UnicodeMode::CaseFold => react_unicode(&data).chars().count(),
+        };
+        lines.push(format!("{}: fully reacted len: {}", idx, reacted_len));
+    }
+    Ok(lines.join("\n"))
+}
+
 pub fn part2(input: &Path) -> Result<(), Error> {
-    for (idx, data) in parse::<String>(input)?.enumerate() {
-        let reacted = minimal_reaction(&data)?;
-        println!(
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with_mode(input, UnicodeMode::default())
+}
+
+/// Solve part 2, treating units which aren't ASCII letters according to `mode`.
+///
+/// Lengths are counted in units.
+pub fn part2_with_mode(input: &str, mode: UnicodeMode) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for (idx, data) in parse_str::<String>(input).enumerate() {
+        let reacted_len = match mode {
+            UnicodeMode::Reject => {
+                unicode::check_ascii(idx, &data)?;
+                minimal_reaction(&data, &AocRule)?.len()
+            }
+            UnicodeMode::CaseFold => minimal_reaction_unicode(&data).chars().count(),
+        };
+        lines.push(format!(
             "{}: fully reacted len (excluding a char): {}",
-            idx,
-            reacted.len()
-        );
+            idx, reacted_len
+        ));
     }
-    Ok(())
+    Ok(lines.join("\n"))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -123,6 +232,8 @@ pub enum Error {
     NoSolution,
     #[error("re-building string from bytes")]
     FromBytes(#[from] FromUtf8Error),
+    #[error("polymer {polymer} contains {unit:?}, which isn't an ASCII letter")]
+    InvalidUnit { polymer: usize, unit: char },
 }
 
 #[cfg(test)]
@@ -133,7 +244,7 @@ mod test {
         ($name:ident($example:expr, $expect:expr)) => {
             #[test]
             fn $name() {
-                let result = react_str($example.into()).unwrap();
+                let result = react_str($example.into(), &AocRule).unwrap();
                 assert_eq!($expect, result);
             }
         };
@@ -146,4 +257,102 @@ mod test {
     case!(aabaab("aabAAB", "aabAAB"));
     case!(example("dabAcCaCBAcCcaDA", "dabCBAcaDA"));
     case!(head("YyLlXxYK", "YK"));
+
+    #[test]
+    fn minimal_example() {
+        assert_eq!(
+            minimal_reaction("dabAcCaCBAcCcaDA", &AocRule).unwrap(),
+            "daDA"
+        );
+    }
+
+    #[test]
+    fn pair_predicate() {
+        // only `a` and `A` react; every other pair is inert
+        let only_a =
+            |a: u8, b: u8| a != b && a.eq_ignore_ascii_case(&b) && a.eq_ignore_ascii_case(&b'a');
+        assert_eq!(
+            react_str("dabAcCaCBAcCcaDA".into(), &only_a).unwrap(),
+            "dabAcCaCBAcCcaDA"
+        );
+        assert_eq!(react_str("baAB".into(), &only_a).unwrap(), "bB");
+    }
+
+    #[test]
+    fn table_with_triples() {
+        let table = ReactionTable::new(vec!["abc", "xy"]);
+        assert_eq!(react_str("aabcbc".into(), &table).unwrap(), "");
+        assert_eq!(react_str("axybcz".into(), &table).unwrap(), "z");
+        assert_eq!(react_str("aA".into(), &table).unwrap(), "aA");
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial() {
+        // a deterministic pseudo-random polymer over few unit types, so that it reacts a lot
+        let mut state = 5_u32;
+        let polymer: Vec<u8> = (0..10_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                b"abcABC"[(state >> 16) as usize % 6]
+            })
+            .collect();
+        let serial = react_to_completion(&polymer, &AocRule);
+        for &chunk_size in &[1, 2, 3, 7, 64, 1000, 10_000, 20_000] {
+            assert_eq!(
+                react_parallel(&polymer, chunk_size),
+                serial,
+                "{}",
+                chunk_size
+            );
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_example() {
+        let example = b"dabAcCaCBAcCcaDA";
+        for chunk_size in 1..=example.len() {
+            assert_eq!(react_parallel(example, chunk_size), b"dabCBAcaDA");
+        }
+        assert!(react_parallel(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn unicode_modes() {
+        let input = "dabAcCaCBAcCcaDA\naßẞAb\n";
+        assert!(matches!(
+            part1_from_str(input),
+            Err(Error::InvalidUnit {
+                polymer: 1,
+                unit: 'ß'
+            })
+        ));
+        assert!(part2_with_mode(input, UnicodeMode::Reject).is_err());
+        assert_eq!(
+            part1_with_mode(input, UnicodeMode::CaseFold).unwrap(),
+            "0: fully reacted len: 10\n1: fully reacted len: 1"
+        );
+        assert_eq!(
+            part2_with_mode(input, UnicodeMode::CaseFold).unwrap(),
+            "0: fully reacted len (excluding a char): 4\n1: fully reacted len (excluding a char): 0"
+        );
+        for &mode in &[UnicodeMode::Reject, UnicodeMode::CaseFold] {
+            assert_eq!(mode.to_string().parse::<UnicodeMode>().unwrap(), mode);
+        }
+    }
+
+    #[test]
+    fn table_prefers_longest() {
+        let table = ReactionTable::new(vec!["bc", "abc"]);
+        assert_eq!(react_str("abc".into(), &table).unwrap(), "");
+        assert_eq!(react_str("dbc".into(), &table).unwrap(), "d");
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day05-crlf", "dabAcCaCBAcCcaDA\n").unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(part1_from_str(&input).unwrap(), "0: fully reacted len: 10");
+    }
 }
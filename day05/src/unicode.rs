@@ -0,0 +1,149 @@
+//! Polymers whose units aren't all ASCII letters.
+//!
+//! The puzzle's polymers are ASCII, and the byte-oriented reactions assume as much. For
+//! anything else, either reject the polymer, or react it unit by unit under Unicode case
+//! folding: two different units react when they fold to the same unit.
+
+use crate::Error;
+use std::collections::HashSet;
+
+/// How to treat polymers containing units which aren't ASCII letters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, parse_display::Display, parse_display::FromStr)]
+#[display(style = "kebab-case")]
+pub enum UnicodeMode {
+    /// Such polymers are an error.
+    Reject,
+    /// Units are `char`s, and react when they're different but fold to the same case.
+    CaseFold,
+}
+
+impl Default for UnicodeMode {
+    fn default() -> Self {
+        UnicodeMode::Reject
+    }
+}
+
+/// Ensure every unit of the polymer is an ASCII letter.
+pub(crate) fn check_ascii(polymer_idx: usize, polymer: &str) -> Result<(), Error> {
+    match polymer.chars().find(|unit| !unit.is_ascii_alphabetic()) {
+        Some(unit) => Err(Error::InvalidUnit {
+            polymer: polymer_idx,
+            unit,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// A single-`char` mapping, or `None` if the mapping expands to several `char`s.
+fn single(mut mapped: impl Iterator<Item = char>) -> Option<char> {
+    match (mapped.next(), mapped.next()) {
+        (Some(mapped), None) => Some(mapped),
+        _ => None,
+    }
+}
+
+/// The simple case folding of a unit.
+///
+/// The standard library has no case folding, so this is the lowercase of the uppercase,
+/// taking only mappings to a single `char`. That agrees with Unicode's simple case folding
+/// for the pairs which matter here: `ẞ` folds to `ß`, and `ς` and `ſ` fold to `σ` and `s`.
+pub fn fold(unit: char) -> char {
+    let upper = single(unit.to_uppercase()).unwrap_or(unit);
+    single(upper.to_lowercase()).unwrap_or(unit)
+}
+
+/// Whether two adjacent units react: they're different, but fold to the same unit.
+pub fn reacts_folded(a: char, b: char) -> bool {
+    a != b && fold(a) == fold(b)
+}
+
+/// Perform the entire reaction unit by unit, as [`react_to_completion`](crate::react_to_completion)
+/// does for bytes.
+pub fn react_unicode(polymer: &str) -> String {
+    let mut stack: Vec<char> = Vec::with_capacity(polymer.len());
+    for unit in polymer.chars() {
+        match stack.last() {
+            Some(&top) if reacts_folded(top, unit) => {
+                stack.pop();
+            }
+            _ => stack.push(unit),
+        }
+    }
+    stack.into_iter().collect()
+}
+
+/// Find the shortest polymer obtainable by removing all units which fold to the same unit
+/// before reacting, as [`minimal_reaction`](crate::minimal_reaction) does for bytes.
+pub fn minimal_reaction_unicode(polymer: &str) -> String {
+    let types: HashSet<char> = polymer.chars().map(fold).collect();
+    types
+        .into_iter()
+        .map(|excluding| {
+            let smallymer: String = polymer
+                .chars()
+                .filter(|&unit| fold(unit) != excluding)
+                .collect();
+            let reacted = react_unicode(&smallymer);
+            (reacted.chars().count(), reacted)
+        })
+        .min()
+        .map(|(_, reacted)| reacted)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{react_str, AocRule};
+
+    #[test]
+    fn folds() {
+        assert_eq!(fold('A'), 'a');
+        assert_eq!(fold('ẞ'), 'ß');
+        assert_eq!(fold('ß'), 'ß');
+        assert_eq!(fold('Σ'), 'σ');
+        assert_eq!(fold('ς'), 'σ');
+        assert_eq!(fold('ſ'), 's');
+        // the Kelvin sign
+        assert_eq!(fold('\u{212a}'), 'k');
+        // dotted capital I lowercases to two chars, so it folds only to itself
+        assert_eq!(fold('İ'), 'İ');
+        assert_eq!(fold('7'), '7');
+    }
+
+    #[test]
+    fn unicode_pairs() {
+        assert_eq!(react_unicode("aßẞA"), "");
+        assert_eq!(react_unicode("ßß"), "ßß");
+        assert_eq!(react_unicode("xΣσy"), "xy");
+        assert_eq!(react_unicode("ǅǆ"), "");
+        assert_eq!(react_unicode("İi"), "İi");
+        assert_eq!(react_unicode("12"), "12");
+    }
+
+    #[test]
+    fn ascii_matches_bytes() {
+        let example = "dabAcCaCBAcCcaDA";
+        assert_eq!(
+            react_unicode(example),
+            react_str(example.into(), &AocRule).unwrap()
+        );
+        assert_eq!(minimal_reaction_unicode(example), "daDA");
+        assert_eq!(minimal_reaction_unicode("bẞaAßc"), "b");
+        assert_eq!(minimal_reaction_unicode(""), "");
+    }
+
+    #[test]
+    fn reject_non_ascii() {
+        assert!(check_ascii(0, "dabAcCaCBAcCcaDA").is_ok());
+        assert!(matches!(
+            check_ascii(3, "aßẞA"),
+            Err(Error::InvalidUnit {
+                polymer: 3,
+                unit: 'ß'
+            })
+        ));
+        // the byte-oriented rule leaves multi-byte units inert rather than panicking
+        assert_eq!(react_str("aßẞA".into(), &AocRule).unwrap(), "aßẞA");
+    }
+}
@@ -0,0 +1,189 @@
+//! Labeled maps which grow one point at a time.
+
+use crate::{
+    check_point_count, fill_map, largest_non_infinite_region, make_map, Error, Map, PointId,
+    TiePolicy, Tile,
+};
+use aoclib::geometry::{Direction, Point};
+use std::collections::{HashSet, VecDeque};
+use util::spatial::L1Index;
+
+type Distances = aoclib::geometry::Map<i32>;
+
+/// A filled map which can label new points without being refilled from scratch.
+///
+/// Each tile remembers its distance to the nearest point. A new point takes over only the
+/// tiles at least as near to it as to any existing point. Those tiles are connected: each
+/// step from one of them towards the new point brings it a step nearer, and any other point
+/// at most a step nearer. So a flood fill from the new point finds them all, examining only
+/// them and their neighbors.
+pub struct LabeledMap {
+    map: Map,
+    distances: Distances,
+    index: L1Index,
+    policy: TiePolicy,
+}
+
+impl LabeledMap {
+    /// Label a map with the given points, assigning ties according to `policy`.
+    pub fn new(points: &[Point], policy: TiePolicy) -> Result<Self, Error> {
+        let mut map = make_map(points)?;
+        let index = L1Index::new(points);
+        fill_map(&mut map, &index, policy)?;
+        let distances = Distances::procedural(map.width(), map.height(), |tile| {
+            index
+                .nearest(tile)
+                .map(|(_, distance)| distance)
+                .unwrap_or_default()
+        });
+        Ok(LabeledMap {
+            map,
+            distances,
+            index,
+            policy,
+        })
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    /// The labeled points, in order of insertion.
+    pub fn points(&self) -> &[Point] {
+        self.index.points()
+    }
+
+    /// Compute the area of the largest non-infinite region.
+    pub fn largest_finite_area(&self) -> Result<f64, Error> {
+        largest_non_infinite_region(&self.map, &self.index, self.policy)
+    }
+
+    /// Label a new point, and relabel the tiles nearest to it.
+    ///
+    /// Returns the number of tiles relabeled, including the point's own. A point beyond
+    /// the bounds of the map grows it, so then the whole map is refilled.
+    pub fn insert(&mut self, point: Point) -> Result<usize, Error> {
+        if self.points().contains(&point) {
+            return Err(Error::DuplicatePoint {
+                x: point.x,
+                y: point.y,
+            });
+        }
+        check_point_count(self.points().len() + 1)?;
+        let mut points = self.points().to_vec();
+        points.push(point);
+
+        if !self.map.in_bounds(point) {
+            *self = LabeledMap::new(&points, self.policy)?;
+            return Ok(self.map.width() * self.map.height());
+        }
+
+        let id = (points.len() - 1) as PointId;
+        self.index = L1Index::new(&points);
+        self.map[point] = Tile::Point(id);
+        self.distances[point] = 0;
+
+        let mut relabeled = 1;
+        let mut visited: HashSet<Point> = std::iter::once(point).collect();
+        let mut queue: VecDeque<Point> = std::iter::once(point).collect();
+        while let Some(tile) = queue.pop_front() {
+            for direction in Direction::iter() {
+                let neighbor = tile + direction;
+                if !self.map.in_bounds(neighbor) || !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let distance = (neighbor - point).manhattan();
+                let current = self.distances[neighbor];
+                if distance > current {
+                    continue;
+                }
+                if distance < current {
+                    self.map[neighbor] = Tile::Region(id);
+                    self.distances[neighbor] = distance;
+                    relabeled += 1;
+                } else if self.policy != TiePolicy::LowestIndex
+                    && self.map[neighbor] != Tile::Equidistant
+                {
+                    // under `LowestIndex`, the existing label has the lower index
+                    self.map[neighbor] = Tile::Equidistant;
+                    relabeled += 1;
+                }
+                queue.push_back(neighbor);
+            }
+        }
+
+        Ok(relabeled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point::parse_points, tests::EXAMPLE};
+
+    fn assert_matches_refill(labeled: &LabeledMap) {
+        let refilled = LabeledMap::new(labeled.points(), labeled.policy).unwrap();
+        assert_eq!(labeled.map.width(), refilled.map.width());
+        assert_eq!(labeled.map.height(), refilled.map.height());
+        assert!(labeled.map.iter().eq(refilled.map.iter()));
+        assert!(labeled.distances.iter().eq(refilled.distances.iter()));
+        assert_eq!(
+            labeled.largest_finite_area().ok(),
+            refilled.largest_finite_area().ok()
+        );
+    }
+
+    #[test]
+    fn example_insertions() {
+        let mut points = parse_points(EXAMPLE);
+        // start from the point farthest from the origin, so the map never grows
+        let last = points.remove(points.len() - 1);
+        for &policy in TiePolicy::ALL.iter() {
+            let mut labeled = LabeledMap::new(&[last], policy).unwrap();
+            for &point in &points {
+                let relabeled = labeled.insert(point).unwrap();
+                assert!(relabeled < labeled.map.width() * labeled.map.height());
+                assert_matches_refill(&labeled);
+            }
+        }
+    }
+
+    #[test]
+    fn scattered_insertions() {
+        let mut state = 6_u32;
+        let mut next = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((state >> 16) % 60) as i32
+        };
+        for &policy in TiePolicy::ALL.iter() {
+            let mut labeled = LabeledMap::new(&[Point::new(59, 59)], policy).unwrap();
+            while labeled.points().len() < 40 {
+                let point = Point::new(next(), next());
+                if labeled.points().contains(&point) {
+                    continue;
+                }
+                labeled.insert(point).unwrap();
+                assert_matches_refill(&labeled);
+            }
+        }
+    }
+
+    #[test]
+    fn growth_refills() {
+        let mut labeled = LabeledMap::new(&[Point::new(1, 1)], TiePolicy::default()).unwrap();
+        assert_eq!(labeled.insert(Point::new(4, 2)).unwrap(), 5 * 3);
+        assert_matches_refill(&labeled);
+    }
+
+    #[test]
+    fn duplicate_point() {
+        let mut labeled = LabeledMap::new(&parse_points(EXAMPLE), TiePolicy::default()).unwrap();
+        assert!(matches!(
+            labeled.insert(Point::new(3, 4)),
+            Err(Error::DuplicatePoint { x: 3, y: 4 })
+        ));
+        assert_eq!(labeled.points().len(), 6);
+        assert_eq!(labeled.largest_finite_area().unwrap(), 17.0);
+    }
+}
@@ -1,19 +1,58 @@
+mod labeled;
 mod point;
 mod tile;
 
 use aoclib::geometry::{Direction, Point};
+pub use labeled::LabeledMap;
 use point::parse_points;
 use std::{
     collections::{HashMap, HashSet},
     path::Path,
 };
-use tile::Tile;
+use tile::{PointId, Tile, MAX_POINTS};
+use util::spatial::L1Index;
 
 pub type Map = aoclib::geometry::Map<Tile>;
 
 pub const SAFETY_THRESHOLD: i32 = 10_000;
 
-fn make_map(points: &[Point]) -> Map {
+/// How to assign a location which is equally near several points.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, parse_display::Display, parse_display::FromStr)]
+#[display(style = "kebab-case")]
+pub enum TiePolicy {
+    /// The location belongs to no region. This is the puzzle's rule.
+    NoRegion,
+    /// The location belongs to the region of the tied point with the lowest index.
+    LowestIndex,
+    /// The location's area is split evenly between the regions of the tied points.
+    Split,
+}
+
+impl TiePolicy {
+    pub const ALL: [TiePolicy; 3] = [
+        TiePolicy::NoRegion,
+        TiePolicy::LowestIndex,
+        TiePolicy::Split,
+    ];
+}
+
+impl Default for TiePolicy {
+    fn default() -> Self {
+        TiePolicy::NoRegion
+    }
+}
+
+/// Fail with [`Error::TooManyPoints`] unless a map can label `count` points.
+fn check_point_count(count: usize) -> Result<(), Error> {
+    if count > MAX_POINTS {
+        return Err(Error::TooManyPoints(count));
+    }
+    Ok(())
+}
+
+fn make_map(points: &[Point]) -> Result<Map, Error> {
+    check_point_count(points.len())?;
+
     let mut max_x = 0;
     let mut max_y = 0;
     for point in points {
@@ -24,14 +63,18 @@ fn make_map(points: &[Point]) -> Map {
     let mut map = Map::new((max_x + 1) as usize, (max_y + 1) as usize);
 
     for (idx, point) in points.iter().copied().enumerate() {
-        map[point] = Tile::Point(idx);
+        map[point] = Tile::Point(idx as PointId);
     }
 
-    map
+    Ok(map)
 }
 
-fn fill_map(map: &mut Map, points: &[Point]) -> Result<(), Error> {
-    match points.len() {
+/// Assign every empty tile to the region of its nearest point.
+///
+/// Under [`TiePolicy::LowestIndex`], ties are resolved here. Under the other policies,
+/// tied tiles are marked [`Tile::Equidistant`].
+fn fill_map(map: &mut Map, index: &L1Index, policy: TiePolicy) -> Result<(), Error> {
+    match index.len() {
         0 => return Err(Error::NoSolution),
         1 => map.for_each_mut(|tile| {
             if !matches!(tile, Tile::Point(_)) {
@@ -40,23 +83,20 @@ fn fill_map(map: &mut Map, points: &[Point]) -> Result<(), Error> {
         }),
         _ => map.for_each_point_mut(|tile, tile_point| {
             if *tile == Tile::Empty {
-                let mut distances = Vec::with_capacity(points.len());
-
-                for (idx, coord) in points.iter().copied().enumerate() {
-                    distances.push(((tile_point - coord).manhattan(), idx));
-                }
-
-                distances.sort_unstable();
-
-                let (first_dist, idx) = distances[0];
-                let (second_dist, _) = distances[1];
-
-                if first_dist == second_dist {
-                    // the nearest two coordinates are equidistant
-                    *tile = Tile::Equidistant;
-                } else {
-                    // the nearest coordinate is unique
-                    *tile = Tile::Region(idx);
+                // we only care about the nearest two coordinates
+                match index.k_nearest(tile_point, 2).as_slice() {
+                    [(_, first_dist), (_, second_dist)]
+                        if first_dist == second_dist && policy != TiePolicy::LowestIndex =>
+                    {
+                        // the nearest two coordinates are equidistant
+                        *tile = Tile::Equidistant;
+                    }
+                    [(idx, _), ..] => {
+                        // the nearest coordinate is unique, or has the lowest index among
+                        // those tied
+                        *tile = Tile::Region(*idx as PointId);
+                    }
+                    [] => unreachable!("index holds at least two points"),
                 }
             }
         }),
@@ -67,43 +107,118 @@ fn fill_map(map: &mut Map, points: &[Point]) -> Result<(), Error> {
     Ok(())
 }
 
-fn largest_non_infinite_region(map: &Map) -> Result<usize, Error> {
-    let infinite_regions: HashSet<_> = Direction::iter()
-        .flat_map(|direction| map.edge(direction))
-        .filter_map(|point| match map[point] {
-            Tile::Point(idx) | Tile::Region(idx) => Some(idx),
-            _ => None,
+/// Every point at the minimum distance from `point`.
+///
+/// The index must not be empty.
+fn tied_nearest(index: &L1Index, point: Point) -> Vec<usize> {
+    let mut k = 2;
+    loop {
+        let nearest = index.k_nearest(point, k);
+        let min_dist = nearest[0].1;
+        if nearest.len() < k || nearest[k - 1].1 != min_dist {
+            return nearest
+                .into_iter()
+                .take_while(|&(_, dist)| dist == min_dist)
+                .map(|(idx, _)| idx)
+                .collect();
+        }
+        k *= 2;
+    }
+}
+
+/// Whether the region of `points[idx]` extends infinitely far, assigning ties according to
+/// `policy`.
+///
+/// Beyond every point in some direction, say to the right, a location at height `y` is
+/// `x - qx + |y - qy|` from each point `q`, so which point is nearest doesn't depend on `x`:
+/// it's the one which maximizes `qx - |y - qy|`. Any point which beats `p` at `p`'s own
+/// height beats it at every height. So the region of `p` is infinite exactly when, in some
+/// direction, no other point lies in the quarter-plane opening that way from `p` between
+/// the diagonals through it: when no point dominates it.
+///
+/// A point on one of those diagonals ties `p` at its height. That dominates `p` when ties
+/// belong to no region, or under [`TiePolicy::LowestIndex`] when the other point's index is
+/// lower. Under [`TiePolicy::Split`] it doesn't: `p` gets a share of infinitely many tiles.
+fn is_infinite(points: &[Point], idx: usize, policy: TiePolicy) -> bool {
+    let point = points[idx];
+    Direction::iter().any(|direction| {
+        let (dx, dy) = direction.deltas();
+        !points.iter().enumerate().any(|(other_idx, &other)| {
+            if other_idx == idx {
+                return false;
+            }
+            let offset = other - point;
+            let ahead = offset.x * dx + offset.y * dy;
+            let aside = (offset.x * dy - offset.y * dx).abs();
+            match policy {
+                TiePolicy::NoRegion => ahead >= aside,
+                TiePolicy::LowestIndex => ahead > aside || (ahead == aside && other_idx < idx),
+                TiePolicy::Split => ahead > aside,
+            }
         })
-        .collect();
+    })
+}
+
+/// The ids of every point whose region is infinite; see [`is_infinite`].
+fn infinite_regions(points: &[Point], policy: TiePolicy) -> HashSet<PointId> {
+    (0..points.len())
+        .filter(|&idx| is_infinite(points, idx, policy))
+        .map(|idx| idx as PointId)
+        .collect()
+}
+
+/// Compute the area of every finite region.
+///
+/// Under [`TiePolicy::Split`], each equidistant tile adds an equal fraction to the area of
+/// every region it's tied between.
+fn region_areas(map: &Map, index: &L1Index, policy: TiePolicy) -> HashMap<PointId, f64> {
+    let infinite_regions = infinite_regions(index.points(), policy);
 
-    let mut region_areas: HashMap<usize, usize> = HashMap::new();
-    for tile in map.iter().copied() {
-        if let Tile::Point(idx) | Tile::Region(idx) = tile {
-            if !infinite_regions.contains(&idx) {
-                *region_areas.entry(idx).or_default() += 1;
+    let mut region_areas: HashMap<PointId, f64> = HashMap::new();
+    map.for_each_point(|&tile, point| match tile {
+        Tile::Point(idx) | Tile::Region(idx) => {
+            *region_areas.entry(idx).or_default() += 1.0;
+        }
+        Tile::Equidistant if policy == TiePolicy::Split => {
+            let tied = tied_nearest(index, point);
+            let share = 1.0 / tied.len() as f64;
+            for idx in tied {
+                *region_areas.entry(idx as PointId).or_default() += share;
             }
         }
-    }
+        _ => {}
+    });
 
-    let mut region_areas: Vec<_> = region_areas.into_iter().collect();
-    region_areas.sort_unstable_by_key(|(_idx, area)| std::cmp::Reverse(*area));
+    region_areas.retain(|idx, _| !infinite_regions.contains(idx));
+    region_areas
+}
 
-    match region_areas.first() {
-        Some((_idx, area)) => Ok(*area),
-        None => Err(Error::NoSolution),
-    }
+fn largest_non_infinite_region(
+    map: &Map,
+    index: &L1Index,
+    policy: TiePolicy,
+) -> Result<f64, Error> {
+    region_areas(map, index, policy)
+        .values()
+        .copied()
+        .max_by(|a, b| a.partial_cmp(b).expect("areas are never NaN"))
+        .ok_or(Error::NoSolution)
 }
 
-fn size_of_safe_region(map: &Map, points: &[Point]) -> usize {
+/// Compute the area of the largest non-infinite region, assigning ties according to `policy`.
+fn largest_area_with_tie_policy(input: &str, policy: TiePolicy) -> Result<f64, Error> {
+    let points = parse_points(input);
+    let mut map = make_map(&points)?;
+    let index = L1Index::new(&points);
+    fill_map(&mut map, &index, policy)?;
+    largest_non_infinite_region(&map, &index, policy)
+}
+
+fn size_of_safe_region(map: &Map, index: &L1Index) -> usize {
     let mut count = 0;
 
     map.for_each_point(|_tile, point| {
-        if points
-            .iter()
-            .map(|&coord| (coord - point).manhattan())
-            .sum::<i32>()
-            < SAFETY_THRESHOLD
-        {
+        if index.sum_of_distances(point) < SAFETY_THRESHOLD as i64 {
             count += 1;
         }
     });
@@ -112,29 +227,211 @@ fn size_of_safe_region(map: &Map, points: &[Point]) -> usize {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let points = parse_points(input)?;
-    let mut map = make_map(&points);
-    fill_map(&mut map, &points)?;
-    let area = largest_non_infinite_region(&map)?;
-
-    println!("area of largest non-infinite region: {}", area);
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
-    let points = parse_points(input)?;
-    let mut map = make_map(&points);
-    fill_map(&mut map, &points)?;
-    let ssr = size_of_safe_region(&map, &points);
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with_tie_policy(input, TiePolicy::default())
+}
+
+pub fn part1_with_tie_policy(input: &str, policy: TiePolicy) -> Result<String, Error> {
+    let area = largest_area_with_tie_policy(input, policy)?;
+    Ok(format!("area of largest non-infinite region: {}", area))
+}
 
-    println!("size of safe region: {}", ssr);
+/// Compute the area of the largest non-infinite region under each [`TiePolicy`], one per line.
+pub fn compare_tie_policies_from_str(input: &str) -> Result<String, Error> {
+    let mut lines = Vec::with_capacity(TiePolicy::ALL.len());
+    for &policy in TiePolicy::ALL.iter() {
+        let area = largest_area_with_tie_policy(input, policy)?;
+        lines.push(format!("{}: {}", policy, area));
+    }
+    Ok(lines.join("\n"))
+}
+
+pub fn part2(input: &Path) -> Result<(), Error> {
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let points = parse_points(input);
+    let map = make_map(&points)?;
+    let ssr = size_of_safe_region(&map, &L1Index::new(&points));
+
+    Ok(format!("size of safe region: {}", ssr))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("No solution found")]
     NoSolution,
+    #[error("too many points: {0} exceeds the limit of {}", MAX_POINTS)]
+    TooManyPoints(usize),
+    #[error("duplicate point at {x}, {y}")]
+    DuplicatePoint { x: i32, y: i32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) const EXAMPLE: &str = "
+1, 1
+1, 6
+8, 3
+3, 4
+5, 5
+8, 9
+";
+
+    fn areas(policy: TiePolicy) -> HashMap<PointId, f64> {
+        let points = parse_points(EXAMPLE);
+        let mut map = make_map(&points).unwrap();
+        let index = L1Index::new(&points);
+        fill_map(&mut map, &index, policy).unwrap();
+        region_areas(&map, &index, policy)
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(
+            part1_from_str(EXAMPLE).unwrap(),
+            "area of largest non-infinite region: 17"
+        );
+    }
+
+    #[test]
+    fn point_limit() {
+        assert!(check_point_count(MAX_POINTS).is_ok());
+        let err = check_point_count(MAX_POINTS + 1).unwrap_err();
+        assert!(matches!(err, Error::TooManyPoints(n) if n == MAX_POINTS + 1));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "too many points: {} exceeds the limit of {}",
+                MAX_POINTS + 1,
+                MAX_POINTS
+            )
+        );
+    }
+
+    #[test]
+    fn finite_regions_by_policy() {
+        // only D and E are finite under every policy
+        for &policy in TiePolicy::ALL.iter() {
+            let mut finite: Vec<_> = areas(policy).keys().copied().collect();
+            finite.sort_unstable();
+            assert_eq!(finite, vec![3, 4], "{}", policy);
+        }
+
+        let lowest_index = areas(TiePolicy::LowestIndex);
+        assert_eq!(lowest_index[&3], 9.0);
+        assert_eq!(lowest_index[&4], 17.0);
+
+        let split = areas(TiePolicy::Split);
+        assert!((split[&3] - 37.0 / 3.0).abs() < 1e-9);
+        assert_eq!(split[&4], 18.0);
+    }
+
+    #[test]
+    fn compare_policies() {
+        assert_eq!(
+            compare_tie_policies_from_str(EXAMPLE).unwrap(),
+            "no-region: 17\nlowest-index: 17\nsplit: 18"
+        );
+    }
+
+    /// The regions which reach the edge of the map: the heuristic [`infinite_regions`] replaced.
+    ///
+    /// Tiles split between regions count only when `count_ties`.
+    fn border_regions(map: &Map, index: &L1Index, count_ties: bool) -> HashSet<PointId> {
+        Direction::iter()
+            .flat_map(|direction| map.edge(direction))
+            .flat_map(|point| match map[point] {
+                Tile::Point(idx) | Tile::Region(idx) => vec![idx],
+                Tile::Equidistant if count_ties => tied_nearest(index, point)
+                    .into_iter()
+                    .map(|idx| idx as PointId)
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn exact_matches_border_heuristic() {
+        // a deterministic pseudo-random sequence of point sets
+        let mut seed = 0x2018_u64;
+        let mut next = move |modulus: u64| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) % modulus
+        };
+        for _ in 0..200 {
+            let n = 2 + next(11) as usize;
+            let mut points = Vec::with_capacity(n);
+            while points.len() < n {
+                let point = Point::new(next(20) as i32, next(20) as i32);
+                if !points.contains(&point) {
+                    points.push(point);
+                }
+            }
+            let index = L1Index::new(&points);
+            for &policy in TiePolicy::ALL.iter() {
+                let mut map = make_map(&points).unwrap();
+                fill_map(&mut map, &index, policy).unwrap();
+                let exact = infinite_regions(&points, policy);
+                // the map's edges lie beyond the points, so sampling them with ties is exact
+                assert_eq!(
+                    exact,
+                    border_regions(&map, &index, policy == TiePolicy::Split),
+                    "{:?} under {}",
+                    points,
+                    policy
+                );
+                assert!(border_regions(&map, &index, false).is_subset(&exact));
+            }
+        }
+    }
+
+    #[test]
+    fn split_ties_reach_infinity() {
+        // rightward from the first point, every tile is tied with the two points beside it
+        let input = "5, 2\n6, 1\n6, 3\n3, 2\n5, 4\n5, 0\n";
+        let points = parse_points(input);
+        let index = L1Index::new(&points);
+        let mut map = make_map(&points).unwrap();
+        fill_map(&mut map, &index, TiePolicy::Split).unwrap();
+        assert!(!border_regions(&map, &index, false).contains(&0));
+        assert!(is_infinite(&points, 0, TiePolicy::Split));
+        assert!(!is_infinite(&points, 0, TiePolicy::NoRegion));
+        assert!(matches!(
+            largest_area_with_tie_policy(input, TiePolicy::Split),
+            Err(Error::NoSolution)
+        ));
+        assert_eq!(
+            part1_from_str(input).unwrap(),
+            "area of largest non-infinite region: 1"
+        );
+    }
+
+    #[test]
+    fn policy_names_round_trip() {
+        for &policy in TiePolicy::ALL.iter() {
+            assert_eq!(policy.to_string().parse::<TiePolicy>().unwrap(), policy);
+        }
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day06-crlf", EXAMPLE).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part1_from_str(&input).unwrap(),
+            "area of largest non-infinite region: 17"
+        );
+    }
 }
@@ -1,5 +1,7 @@
 use aoclib::{config::Config, website::get_input};
-use day06::{part1, part2};
+use day06::{
+    compare_tie_policies_from_str, part1_with_tie_policy, part2_from_str, TiePolicy,
+};
 
 use color_eyre::eyre::Result;
 use structopt::StructOpt;
@@ -10,7 +12,7 @@ const DAY: u8 = 6;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day06 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +23,48 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// how part 1 assigns locations equally near several points:
+    /// `no-region`, `lowest-index`, or `split`
+    #[structopt(long, default_value = "no-region")]
+    tie_policy: TiePolicy,
+
+    /// instead of solving, compare the largest region's area under each tie policy
+    #[structopt(long)]
+    compare_tie_policies: bool,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
+
+    if args.compare_tie_policies {
+        println!("{}", compare_tie_policies_from_str(&input)?);
+        return Ok(());
+    }
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_with_tie_policy(&input, args.tie_policy)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        println!("{}", part2_from_str(&input)?);
     }
     Ok(())
 }
@@ -1,5 +1,5 @@
-use aoclib::{geometry::Point as LibPoint, parse};
-use std::path::Path;
+use aoclib::geometry::Point as LibPoint;
+use util::parse_str;
 
 #[derive(Debug, parse_display::FromStr, parse_display::Display)]
 #[display("{x}, {y}")]
@@ -14,6 +14,6 @@ impl From<Point> for LibPoint {
     }
 }
 
-pub fn parse_points(input: &Path) -> std::io::Result<Vec<LibPoint>> {
-    parse::<Point>(input).map(|iter| iter.map(Into::into).collect())
+pub fn parse_points(input: &str) -> Vec<LibPoint> {
+    parse_str::<Point>(input).map(Into::into).collect()
 }
@@ -1,13 +1,22 @@
 use aoclib::geometry::tile::DisplayWidth;
 
+/// Index of an input point.
+///
+/// The map stores one tile per cell, so keeping this small matters for large inputs:
+/// with a `u32` id, a `Tile` takes 8 bytes instead of 16.
+pub type PointId = u32;
+
+/// The maximum number of input points which can be labeled on a map.
+pub const MAX_POINTS: usize = PointId::MAX as usize;
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, parse_display::Display)]
 pub enum Tile {
     #[display(" ")]
     Empty,
     #[display("o")]
-    Point(usize),
+    Point(PointId),
     #[display("R")]
-    Region(usize),
+    Region(PointId),
     #[display("X")]
     Equidistant,
 }
@@ -0,0 +1,389 @@
+use crate::{step::sort_key, Edge, Error, Seconds};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap},
+};
+
+/// A step label, ordered the same way as [`Labels`](crate::Labels).
+///
+/// This deliberately doesn't implement `Borrow<str>`: its ordering differs from that of `str`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Step(String);
+
+impl From<&str> for Step {
+    fn from(label: &str) -> Self {
+        Step(label.to_string())
+    }
+}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Self) -> Ordering {
+        sort_key(&self.0).cmp(&sort_key(&other.0))
+    }
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Links {
+    prereq: BTreeSet<Step>,
+    blocked: BTreeSet<Step>,
+}
+
+/// A dependency graph which can be edited between scheduling queries.
+///
+/// Unlike the graphs built for the puzzle parts, this one is keyed by label, so steps can
+/// be added and removed freely. The set of steps with no prerequisites is kept up to date
+/// with every edit, so queries start scheduling immediately instead of rescanning the graph.
+///
+/// ```
+/// # use day07::DependencyGraph;
+/// let mut graph = DependencyGraph::new();
+/// graph.add_edge("C", "A").unwrap();
+/// graph.add_edge("A", "B").unwrap();
+/// assert_eq!(graph.topo_order(), vec!["C", "A", "B"]);
+///
+/// // what if A weren't required?
+/// graph.invalidate("A");
+/// assert_eq!(graph.ready().collect::<Vec<_>>(), vec!["B", "C"]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DependencyGraph {
+    steps: BTreeMap<Step, Links>,
+    ready: BTreeSet<Step>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_edges(edges: &[Edge]) -> Result<Self, Error> {
+        let mut graph = Self::new();
+        for edge in edges {
+            graph.add_edge(&edge.prereq, &edge.blocked)?;
+        }
+        Ok(graph)
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn contains(&self, step: &str) -> bool {
+        self.steps.contains_key(&Step::from(step))
+    }
+
+    /// Steps with no prerequisites, in label order.
+    pub fn ready(&self) -> impl '_ + Iterator<Item = &str> {
+        self.ready.iter().map(|step| step.0.as_str())
+    }
+
+    /// Add a step with no dependencies, if it isn't already present.
+    ///
+    /// Return `true` if the step was added.
+    pub fn add_step(&mut self, step: &str) -> bool {
+        if self.contains(step) {
+            return false;
+        }
+        let step = Step::from(step);
+        self.ready.insert(step.clone());
+        self.steps.insert(step, Links::default());
+        true
+    }
+
+    /// Require that `prereq` is finished before `blocked` can begin, adding either step as needed.
+    ///
+    /// Return `true` if the edge is new. Fails with [`Error::Cycle`] without changing the
+    /// graph if `prereq` already depends on `blocked`.
+    pub fn add_edge(&mut self, prereq: &str, blocked: &str) -> Result<bool, Error> {
+        if self.depends_on(prereq, blocked) {
+            return Err(Error::Cycle {
+                prereq: prereq.to_string(),
+                blocked: blocked.to_string(),
+            });
+        }
+        self.add_step(prereq);
+        self.add_step(blocked);
+
+        let (prereq, blocked) = (Step::from(prereq), Step::from(blocked));
+        let blocked_links = self.steps.get_mut(&blocked).expect("step was just added");
+        if !blocked_links.prereq.insert(prereq.clone()) {
+            return Ok(false);
+        }
+        self.ready.remove(&blocked);
+        self.steps
+            .get_mut(&prereq)
+            .expect("step was just added")
+            .blocked
+            .insert(blocked);
+        Ok(true)
+    }
+
+    /// Remove the requirement that `prereq` is finished before `blocked` can begin.
+    ///
+    /// Both steps remain in the graph. Return `true` if the edge existed.
+    pub fn remove_edge(&mut self, prereq: &str, blocked: &str) -> bool {
+        let (prereq, blocked) = (Step::from(prereq), Step::from(blocked));
+        let removed = match self.steps.get_mut(&blocked) {
+            Some(links) => links.prereq.remove(&prereq),
+            None => false,
+        };
+        if !removed {
+            return false;
+        }
+        self.steps
+            .get_mut(&prereq)
+            .expect("edges always join known steps")
+            .blocked
+            .remove(&blocked);
+        self.update_ready(&blocked);
+        true
+    }
+
+    /// Remove a step and every edge touching it, as if it had never been required.
+    ///
+    /// Steps which it blocked lose that prerequisite, and may become ready.
+    /// Return `true` if the step was present.
+    pub fn invalidate(&mut self, step: &str) -> bool {
+        let step = Step::from(step);
+        let links = match self.steps.remove(&step) {
+            Some(links) => links,
+            None => return false,
+        };
+        self.ready.remove(&step);
+        for prereq in &links.prereq {
+            if let Some(prereq_links) = self.steps.get_mut(prereq) {
+                prereq_links.blocked.remove(&step);
+            }
+        }
+        for blocked in &links.blocked {
+            if let Some(blocked_links) = self.steps.get_mut(blocked) {
+                blocked_links.prereq.remove(&step);
+            }
+            self.update_ready(blocked);
+        }
+        true
+    }
+
+    fn update_ready(&mut self, step: &Step) {
+        if self.steps[step].prereq.is_empty() {
+            self.ready.insert(step.clone());
+        }
+    }
+
+    /// Whether `step` must wait, directly or transitively, for `prereq`.
+    ///
+    /// A step counts as depending on itself.
+    fn depends_on(&self, step: &str, prereq: &str) -> bool {
+        let step = Step::from(step);
+        let prereq = Step::from(prereq);
+        let mut stack = vec![&prereq];
+        let mut seen = BTreeSet::new();
+        while let Some(current) = stack.pop() {
+            if *current == step {
+                return true;
+            }
+            if !seen.insert(current) {
+                continue;
+            }
+            if let Some(links) = self.steps.get(current) {
+                stack.extend(&links.blocked);
+            }
+        }
+        false
+    }
+
+    /// Number of unfinished prerequisites of each step.
+    fn prereq_counts(&self) -> HashMap<&Step, usize> {
+        self.steps
+            .iter()
+            .map(|(step, links)| (step, links.prereq.len()))
+            .collect()
+    }
+
+    /// The order in which a single worker completes the steps: whenever several steps are
+    /// ready, the first in label order goes next.
+    pub fn topo_order(&self) -> Vec<&str> {
+        let mut remaining = self.prereq_counts();
+        let mut ready: BinaryHeap<_> = self.ready.iter().map(Reverse).collect();
+        let mut out = Vec::with_capacity(self.len());
+
+        while let Some(Reverse(step)) = ready.pop() {
+            out.push(step.0.as_str());
+            for blocked in &self.steps[step].blocked {
+                let count = remaining.get_mut(blocked).expect("all steps are counted");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(Reverse(blocked));
+                }
+            }
+        }
+
+        debug_assert_eq!(out.len(), self.len(), "graph is acyclic");
+        out
+    }
+
+    /// How long `workers` workers take to complete every step.
+    ///
+    /// Whenever a worker is free, it takes the first ready step in label order.
+    /// Fails with [`Error::UnknownDuration`] if `duration_of` has no duration for some step,
+    /// or with [`Error::NoWorkers`] if `workers` is 0.
+    pub fn assembly_time(
+        &self,
+        workers: usize,
+        duration_of: impl Fn(&str) -> Option<Seconds>,
+    ) -> Result<Seconds, Error> {
+        if workers == 0 {
+            return Err(Error::NoWorkers);
+        }
+        let mut remaining = self.prereq_counts();
+        let mut ready: BTreeSet<&Step> = self.ready.iter().collect();
+        let mut in_progress = BinaryHeap::new();
+        let mut time = 0;
+
+        loop {
+            while in_progress.len() < workers {
+                let step = match ready.iter().next() {
+                    Some(&step) => step,
+                    None => break,
+                };
+                ready.remove(step);
+                let duration =
+                    duration_of(&step.0).ok_or_else(|| Error::UnknownDuration(step.0.clone()))?;
+                in_progress.push(Reverse((time + duration, step)));
+            }
+
+            time = match in_progress.peek() {
+                Some(Reverse((finish, _))) => *finish,
+                None => break,
+            };
+            // every step finishing now unblocks its dependents before workers choose again
+            while let Some(&Reverse((finish, step))) = in_progress.peek() {
+                if finish != time {
+                    break;
+                }
+                in_progress.pop();
+                for blocked in &self.steps[step].blocked {
+                    let count = remaining.get_mut(blocked).expect("all steps are counted");
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(blocked);
+                    }
+                }
+            }
+        }
+
+        Ok(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make_duration_of;
+    use util::parse_str;
+
+    const EXAMPLE: &str = "
+Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.
+";
+
+    fn example() -> DependencyGraph {
+        let edges: Vec<Edge> = parse_str(EXAMPLE).collect();
+        DependencyGraph::from_edges(&edges).unwrap()
+    }
+
+    #[test]
+    fn example_queries() {
+        let graph = example();
+        assert_eq!(graph.topo_order().concat(), "CABDFE");
+        assert_eq!(graph.assembly_time(2, make_duration_of(0)).unwrap(), 15);
+        assert_eq!(graph.ready().collect::<Vec<_>>(), vec!["C"]);
+    }
+
+    #[test]
+    fn no_workers() {
+        let graph = example();
+        assert!(matches!(
+            graph.assembly_time(0, make_duration_of(0)),
+            Err(Error::NoWorkers)
+        ));
+        assert_eq!(graph.assembly_time(1, make_duration_of(0)).unwrap(), 21);
+    }
+
+    #[test]
+    fn what_if_removed() {
+        let mut graph = example();
+        assert!(graph.invalidate("F"));
+        assert!(!graph.invalidate("F"));
+        assert_eq!(graph.topo_order().concat(), "CABDE");
+
+        // removing the root makes its dependents ready
+        let mut graph = example();
+        graph.invalidate("C");
+        assert_eq!(graph.ready().collect::<Vec<_>>(), vec!["A", "F"]);
+        assert_eq!(graph.topo_order().concat(), "ABDFE");
+    }
+
+    #[test]
+    fn edit_edges() {
+        let mut graph = example();
+        assert!(graph.remove_edge("C", "F"));
+        assert!(!graph.remove_edge("C", "F"));
+        assert_eq!(graph.ready().collect::<Vec<_>>(), vec!["C", "F"]);
+        assert_eq!(graph.topo_order().concat(), "CABDFE");
+        // with three workers, F no longer waits for C
+        assert_eq!(graph.assembly_time(3, make_duration_of(0)).unwrap(), 13);
+
+        assert!(graph.add_edge("C", "F").unwrap());
+        assert!(!graph.add_edge("C", "F").unwrap());
+        assert_eq!(graph, example());
+    }
+
+    #[test]
+    fn matches_rebuilt_graph() {
+        let mut graph = example();
+        graph.add_edge("E", "G").unwrap();
+        graph.invalidate("D");
+        let rebuilt = "
+Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step B must be finished before step E can begin.
+Step F must be finished before step E can begin.
+Step E must be finished before step G can begin.
+";
+        let edges: Vec<Edge> = parse_str(rebuilt).collect();
+        assert_eq!(graph, DependencyGraph::from_edges(&edges).unwrap());
+        let expect = crate::part1_from_str(rebuilt).unwrap();
+        assert_eq!(
+            format!("instruction order: {}", graph.topo_order().concat()),
+            expect
+        );
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut graph = example();
+        assert!(matches!(
+            graph.add_edge("E", "C"),
+            Err(Error::Cycle { prereq, blocked }) if prereq == "E" && blocked == "C"
+        ));
+        assert!(matches!(graph.add_edge("A", "A"), Err(Error::Cycle { .. })));
+        assert_eq!(graph, example());
+    }
+}
@@ -0,0 +1,132 @@
+//! How long each step takes.
+//!
+//! Durations start from a preset: a base duration, plus each single-letter step's position
+//! in the alphabet. Individual steps can be overridden in a section after the instructions,
+//! headed `durations:`, one per line as `label=seconds`:
+//!
+//! ```text
+//! Step C must be finished before step A can begin.
+//! Step C must be finished before step Design can begin.
+//!
+//! durations:
+//! A=12
+//! Design=40
+//! ```
+//!
+//! Multi-character labels have no preset duration, so every such label needs an override.
+
+use crate::{make_duration_of, Error, Seconds, TASK_BASE_DURATION};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Durations {
+    base: Seconds,
+    overrides: HashMap<String, Seconds>,
+}
+
+impl Durations {
+    /// Letters take their position in the alphabet, plus `base` seconds.
+    pub fn with_base(base: Seconds) -> Self {
+        Durations {
+            base,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// The puzzle's durations: letters take 60 seconds plus their position in the alphabet.
+    pub fn puzzle() -> Self {
+        Self::with_base(TASK_BASE_DURATION)
+    }
+
+    /// The example's durations: letters take their position in the alphabet.
+    pub fn example() -> Self {
+        Self::with_base(0)
+    }
+
+    /// Set the duration of a single step, replacing any preset or earlier override.
+    pub fn set(&mut self, label: impl Into<String>, duration: Seconds) {
+        self.overrides.insert(label.into(), duration);
+    }
+
+    pub fn duration_of(&self, label: &str) -> Option<Seconds> {
+        self.overrides
+            .get(label)
+            .copied()
+            .or_else(|| make_duration_of(self.base)(label))
+    }
+
+    /// Apply the overrides in a `durations:` section.
+    pub(crate) fn parse_overrides(&mut self, section: &str) -> Result<(), Error> {
+        for line in section
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let invalid = || Error::InvalidDuration(line.to_string());
+            let (label, duration) = line.split_once('=').ok_or_else(invalid)?;
+            let label = label.trim();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return Err(invalid());
+            }
+            let duration = duration.trim().parse().map_err(|_| invalid())?;
+            self.set(label, duration);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Durations {
+    fn default() -> Self {
+        Self::puzzle()
+    }
+}
+
+/// Split the input into the instructions and the durations section, if any.
+pub(crate) fn split_input(input: &str) -> (&str, Option<&str>) {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim() == "durations:" {
+            return (&input[..offset], Some(&input[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    (input, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_presets() {
+        let mut durations = Durations::example();
+        durations.parse_overrides("F=12\n  Design = 40 \n").unwrap();
+        assert_eq!(durations.duration_of("A"), Some(1));
+        assert_eq!(durations.duration_of("F"), Some(12));
+        assert_eq!(durations.duration_of("Design"), Some(40));
+        assert_eq!(durations.duration_of("Other"), None);
+        assert_eq!(Durations::puzzle().duration_of("F"), Some(66));
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        for section in &["F", "F=", "=3", "F=-1", "F G=3"] {
+            assert!(
+                matches!(
+                    Durations::example().parse_overrides(section),
+                    Err(Error::InvalidDuration(_))
+                ),
+                "{:?} should be invalid",
+                section
+            );
+        }
+    }
+
+    #[test]
+    fn split_on_header() {
+        let (instructions, section) = split_input("a\nb\n\ndurations:\nF=1\n");
+        assert_eq!(instructions, "a\nb\n\n");
+        assert_eq!(section, Some("F=1\n"));
+        assert_eq!(split_input("a\nb\n"), ("a\nb\n", None));
+    }
+}
@@ -1,29 +1,39 @@
-use aoclib::parse;
+mod dependency_graph;
+mod durations;
+mod step;
+
+pub use dependency_graph::DependencyGraph;
+pub use durations::Durations;
 use std::{
     cmp::Reverse,
     collections::{BTreeSet, BinaryHeap, HashMap},
     path::Path,
     str::FromStr,
 };
+pub use step::{Labels, StepId};
 use text_io::try_scan;
+use util::parse_str;
 
-pub type Step = char;
 pub type Seconds = u32;
 
 pub const N_WORKERS: usize = 5;
 pub const TASK_BASE_DURATION: Seconds = 60;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Separator between labels in part 1's output, when some label is longer than one character.
+pub const DEFAULT_SEPARATOR: &str = ",";
+
+/// Steps may have any label which doesn't contain whitespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Edge {
-    prereq: Step,
-    blocked: Step,
+    prereq: String,
+    blocked: String,
 }
 
 impl FromStr for Edge {
     type Err = text_io::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (prereq, blocked): (Step, Step);
+        let (prereq, blocked): (String, String);
         try_scan!(s.bytes() => "Step {} must be finished before step {} can begin.", prereq, blocked);
         Ok(Edge { prereq, blocked })
     }
@@ -31,40 +41,39 @@ impl FromStr for Edge {
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Node {
-    prereq: BTreeSet<Step>,
-    blocked: BTreeSet<Step>,
+    prereq: BTreeSet<StepId>,
+    blocked: BTreeSet<StepId>,
 }
 
 /// a graph lists, for each step, all of its prerequisites in sorted order
-pub type Graph = HashMap<Step, Node>;
-
-fn make_graph(edges: &[Edge]) -> Graph {
+pub type Graph = HashMap<StepId, Node>;
+
+fn make_graph(edges: &[Edge]) -> (Labels, Graph) {
+    let labels = Labels::new(
+        edges
+            .iter()
+            .flat_map(|edge| [edge.prereq.as_str(), edge.blocked.as_str()]),
+    );
+    let id = |label: &str| labels.id(label).expect("all labels were interned");
     let mut graph = Graph::new();
 
     for edge in edges {
-        graph
-            .entry(edge.blocked)
-            .or_default()
-            .prereq
-            .insert(edge.prereq);
-        graph
-            .entry(edge.prereq)
-            .or_default()
-            .blocked
-            .insert(edge.blocked);
+        let (prereq, blocked) = (id(&edge.prereq), id(&edge.blocked));
+        graph.entry(blocked).or_default().prereq.insert(prereq);
+        graph.entry(prereq).or_default().blocked.insert(blocked);
     }
 
-    graph
+    (labels, graph)
 }
 
-fn no_prerequisites(graph: &Graph) -> impl '_ + Iterator<Item = Step> {
+fn no_prerequisites(graph: &Graph) -> impl '_ + Iterator<Item = StepId> {
     graph
         .iter()
         .filter(|(_step, node)| node.prereq.is_empty())
         .map(|(&step, _node)| step)
 }
 
-fn topo_sort(mut graph: Graph) -> Vec<Step> {
+fn topo_sort(mut graph: Graph) -> Vec<StepId> {
     let mut out = Vec::with_capacity(graph.len());
 
     let mut ready: BinaryHeap<_> = no_prerequisites(&graph).map(Reverse).collect();
@@ -89,19 +98,29 @@ fn topo_sort(mut graph: Graph) -> Vec<Step> {
     out
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Event {
-    CompleteTask(Seconds),    // unblocks a worker
-    Unblocked(Seconds, Step), // when a task becomes available
+/// Join the labels of sorted steps for output.
+///
+/// With no `separator`, single-character labels are run together, as the puzzle expects;
+/// if any label is longer, they're joined by [`DEFAULT_SEPARATOR`] so they can be told apart.
+pub fn format_order<'a, I>(order: I, separator: Option<&str>) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let order: Vec<&str> = order.into_iter().collect();
+    let separator = separator.unwrap_or_else(|| {
+        if order.iter().all(|label| label.chars().count() == 1) {
+            ""
+        } else {
+            DEFAULT_SEPARATOR
+        }
+    });
+    order.join(separator)
 }
 
-impl Event {
-    fn time(self) -> Seconds {
-        match self {
-            Event::CompleteTask(t) => t,
-            Event::Unblocked(t, _) => t,
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    CompleteTask(Seconds),      // unblocks a worker
+    Unblocked(Seconds, StepId), // when a task becomes available
 }
 
 impl Ord for Event {
@@ -125,22 +144,33 @@ impl PartialOrd for Event {
     }
 }
 
-fn make_duration_of(duration_base: Seconds) -> impl Fn(Step) -> Seconds {
-    move |step| duration_base + 1 + (step as u8 - b'A') as Seconds
-}
-
-fn assembly_time(graph: Graph) -> Seconds {
-    let duration_of = make_duration_of(TASK_BASE_DURATION);
-    assembly_time_with(graph, N_WORKERS, duration_of)
+/// Make the puzzle's duration function: each step named by a single letter takes
+/// `duration_base` plus its position in the alphabet, regardless of case.
+///
+/// Other labels have no defined duration.
+pub fn make_duration_of(duration_base: Seconds) -> impl Fn(&str) -> Option<Seconds> {
+    move |label| {
+        let mut chars = label.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) if letter.is_ascii_alphabetic() => {
+                Some(duration_base + 1 + (letter.to_ascii_uppercase() as u8 - b'A') as Seconds)
+            }
+            _ => None,
+        }
+    }
 }
 
 fn assembly_time_with(
     mut graph: Graph,
+    labels: &Labels,
     workers: usize,
-    duration_of: impl Fn(Step) -> Seconds,
-) -> Seconds {
+    duration_of: impl Fn(&str) -> Option<Seconds>,
+) -> Result<Seconds, Error> {
     let mut time = 0;
     let mut workers_working = 0;
+    // a step can't start until its last prerequisite finishes, which isn't necessarily
+    // the last prerequisite to start
+    let mut unblocked_at: HashMap<StepId, Seconds> = HashMap::new();
 
     // ready: Heap<Event>
     let mut ready: BinaryHeap<_> = no_prerequisites(&graph)
@@ -162,17 +192,25 @@ fn assembly_time_with(
                 );
                 if workers_working == workers {
                     // no workers available
-                    // reset and try again after the next event
-                    let Reverse(next_event) = ready
-                        .peek()
-                        .expect("if all workers are occupied, there must be more events");
-                    let next_time = next_event.time();
+                    // reset and try again once the next worker is free; retrying after
+                    // merely the next event could loop forever on other unblocked steps
+                    let next_time = ready
+                        .iter()
+                        .filter_map(|Reverse(event)| match event {
+                            Event::CompleteTask(t) => Some(*t),
+                            Event::Unblocked(..) => None,
+                        })
+                        .min()
+                        .expect("if all workers are occupied, one of them must finish");
                     ready.push(Reverse(Event::Unblocked(next_time, step)));
                     continue;
                 }
 
                 if let Some(node) = graph.remove(&step) {
-                    let finish = time + duration_of(step);
+                    let label = labels.label(step);
+                    let duration = duration_of(label)
+                        .ok_or_else(|| Error::UnknownDuration(label.to_string()))?;
+                    let finish = time + duration;
 
                     workers_working += 1;
                     ready.push(Reverse(Event::CompleteTask(finish)));
@@ -180,8 +218,10 @@ fn assembly_time_with(
                     for was_blocked in node.blocked {
                         if let Some(wb_node) = graph.get_mut(&was_blocked) {
                             wb_node.prereq.remove(&step);
+                            let at = unblocked_at.entry(was_blocked).or_default();
+                            *at = (*at).max(finish);
                             if wb_node.prereq.is_empty() {
-                                ready.push(Reverse(Event::Unblocked(finish, was_blocked)));
+                                ready.push(Reverse(Event::Unblocked(*at, was_blocked)));
                             }
                         }
                     }
@@ -190,29 +230,197 @@ fn assembly_time_with(
         }
     }
 
-    time
+    Ok(time)
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let edges: Vec<Edge> = parse(input)?.collect();
-    let graph = make_graph(&edges);
-    let sorted_steps: String = topo_sort(graph).into_iter().collect();
-
-    println!("instruction order: {}", sorted_steps);
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<(), Error> {
-    let edges: Vec<Edge> = parse(input)?.collect();
-    let graph = make_graph(&edges);
-    let assembly_time = assembly_time(graph);
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with_separator(input, None)
+}
+
+/// Compute the instruction order, joining labels as [`format_order`] does.
+pub fn part1_with_separator(input: &str, separator: Option<&str>) -> Result<String, Error> {
+    let (instructions, _overrides) = durations::split_input(input);
+    let edges: Vec<Edge> = parse_str(instructions).collect();
+    let (labels, graph) = make_graph(&edges);
+    let sorted_steps = format_order(
+        topo_sort(graph).into_iter().map(|step| labels.label(step)),
+        separator,
+    );
+
+    Ok(format!("instruction order: {}", sorted_steps))
+}
 
-    println!("assembly time: {}", assembly_time);
+pub fn part2(input: &Path) -> Result<(), Error> {
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with(input, N_WORKERS, Durations::puzzle())
+}
+
+/// Compute the assembly time with `workers` workers, starting from the `durations` preset.
+///
+/// Overrides in the input's `durations:` section take precedence over the preset.
+/// Fails with [`Error::NoWorkers`] if `workers` is 0.
+pub fn part2_with(input: &str, workers: usize, mut durations: Durations) -> Result<String, Error> {
+    if workers == 0 {
+        return Err(Error::NoWorkers);
+    }
+    let (instructions, overrides) = durations::split_input(input);
+    if let Some(overrides) = overrides {
+        durations.parse_overrides(overrides)?;
+    }
+    let edges: Vec<Edge> = parse_str(instructions).collect();
+    let (labels, graph) = make_graph(&edges);
+    let assembly_time = assembly_time_with(graph, &labels, workers, |label| {
+        durations.duration_of(label)
+    })?;
+
+    Ok(format!("assembly time: {}", assembly_time))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("no duration defined for step {0}")]
+    UnknownDuration(String),
+    #[error("requiring {prereq} before {blocked} would create a cycle")]
+    Cycle { prereq: String, blocked: String },
+    #[error("invalid duration override: {0:?}")]
+    InvalidDuration(String),
+    #[error("no workers to assemble the steps")]
+    NoWorkers,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.
+";
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(
+            part1_from_str(EXAMPLE).unwrap(),
+            "instruction order: CABDFE"
+        );
+    }
+
+    #[test]
+    fn part2_example() {
+        let edges: Vec<Edge> = parse_str(EXAMPLE).collect();
+        let (labels, graph) = make_graph(&edges);
+        assert_eq!(
+            assembly_time_with(graph, &labels, 2, make_duration_of(0)).unwrap(),
+            15
+        );
+    }
+
+    #[test]
+    fn no_workers() {
+        assert!(matches!(
+            part2_with(EXAMPLE, 0, Durations::example()),
+            Err(Error::NoWorkers)
+        ));
+        // a lone worker does every step in turn
+        assert_eq!(
+            part2_with(EXAMPLE, 1, Durations::example()).unwrap(),
+            "assembly time: 21"
+        );
+    }
+
+    #[test]
+    fn lowercase_and_multicharacter_labels() {
+        let input = "
+Step b must be finished before step Ab can begin.
+Step a must be finished before step Ab can begin.
+Step C must be finished before step a can begin.
+";
+        assert_eq!(
+            part1_from_str(input).unwrap(),
+            "instruction order: b,C,a,Ab"
+        );
+
+        let edges: Vec<Edge> = parse_str(input).collect();
+        let (labels, graph) = make_graph(&edges);
+        let duration_of = |label: &str| make_duration_of(0)(label).or(Some(10));
+        assert_eq!(
+            assembly_time_with(graph.clone(), &labels, 2, duration_of).unwrap(),
+            14
+        );
+        assert!(matches!(
+            assembly_time_with(graph, &labels, 2, make_duration_of(0)),
+            Err(Error::UnknownDuration(label)) if label == "Ab"
+        ));
+    }
+
+    #[test]
+    fn duration_overrides() {
+        let input = format!("{}\ndurations:\nF=12\n", EXAMPLE);
+        assert_eq!(part1_from_str(&input).unwrap(), "instruction order: CABDFE");
+        // C 0-3, then A 3-4 and F 3-15; B 4-6, D 6-10; E 15-20
+        assert_eq!(
+            part2_with(&input, 2, Durations::example()).unwrap(),
+            "assembly time: 20"
+        );
+        assert_eq!(
+            part2_with(EXAMPLE, 2, Durations::example()).unwrap(),
+            "assembly time: 15"
+        );
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(format_order(vec!["C", "A", "B"], None), "CAB");
+        assert_eq!(format_order(vec!["C", "Ab", "B"], None), "C,Ab,B");
+        assert_eq!(
+            format_order(vec!["C", "A", "B"], Some(" -> ")),
+            "C -> A -> B"
+        );
+        assert_eq!(format_order(vec!["ß", "é"], None), "ßé");
+        assert_eq!(format_order(Vec::new(), None), "");
+        assert_eq!(
+            part1_with_separator(EXAMPLE, Some(" ")).unwrap(),
+            "instruction order: C A B D F E"
+        );
+
+        // the dependency graph's order goes through the same formatter
+        let edges: Vec<Edge> = parse_str(EXAMPLE).collect();
+        let graph = DependencyGraph::from_edges(&edges).unwrap();
+        assert_eq!(format_order(graph.topo_order(), None), "CABDFE");
+    }
+
+    #[test]
+    fn labels_ordered_case_insensitively() {
+        let labels = Labels::new(vec!["b", "B", "a", "C"]);
+        let ids: Vec<_> = ["a", "B", "b", "C"]
+            .iter()
+            .map(|label| labels.id(label).unwrap())
+            .collect();
+        assert!(ids.windows(2).all(|window| window[0] < window[1]));
+        assert_eq!(labels.label(ids[1]), "B");
+        assert_eq!(labels.id("c"), None);
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day07-crlf", EXAMPLE).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(part1_from_str(&input).unwrap(), "instruction order: CABDFE");
+    }
 }
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day07::{part1, part2};
+use day07::{part1_with_separator, part2_with, Durations, Seconds, N_WORKERS, TASK_BASE_DURATION};
 
 use color_eyre::eyre::Result;
 use structopt::StructOpt;
@@ -10,7 +10,7 @@ const DAY: u8 = 7;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day07 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +21,53 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// separator between step labels in part 1 (default: none for single-letter steps, else `,`)
+    #[structopt(long)]
+    separator: Option<String>,
+
+    /// number of workers for part 2 (default: 5)
+    #[structopt(long)]
+    workers: Option<usize>,
+
+    /// seconds added to each lettered step's duration in part 2 (the example uses 0)
+    #[structopt(long)]
+    base_duration: Option<Seconds>,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!(
+            "{}",
+            part1_with_separator(&input, args.separator.as_deref())?
+        );
     }
     if args.part2 {
-        part2(&input_path)?;
+        let durations = Durations::with_base(args.base_duration.unwrap_or(TASK_BASE_DURATION));
+        println!(
+            "{}",
+            part2_with(&input, args.workers.unwrap_or(N_WORKERS), durations)?
+        );
     }
     Ok(())
 }
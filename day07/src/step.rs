@@ -0,0 +1,56 @@
+use intern::{Interner, Symbol};
+use std::collections::BTreeSet;
+
+/// Identifier of a step, interned from its label.
+///
+/// Ids are assigned in label order, so comparing ids compares labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StepId(Symbol);
+
+/// Interned table of step labels.
+///
+/// Labels are ordered case-insensitively, so `a` comes before `B`. Labels which differ
+/// only in case are distinct steps; they're ordered uppercase first.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Labels(Interner);
+
+pub(crate) fn sort_key(label: &str) -> (String, &str) {
+    (label.to_lowercase(), label)
+}
+
+impl Labels {
+    /// Intern a set of labels.
+    pub fn new<I, S>(labels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let labels: BTreeSet<String> = labels.into_iter().map(Into::into).collect();
+        let mut labels: Vec<String> = labels.into_iter().collect();
+        labels.sort_unstable_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        // interning in sorted order makes symbol order agree with label order
+        Labels(labels.into_iter().collect())
+    }
+
+    /// Look up the id of a label.
+    pub fn id(&self, label: &str) -> Option<StepId> {
+        self.0.get(label).map(StepId)
+    }
+
+    /// Look up the label of an id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id was not produced by this table.
+    pub fn label(&self, id: StepId) -> &str {
+        self.0.resolve(id.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -1,106 +1,455 @@
-use std::path::Path;
+use std::{convert::TryInto, fmt, path::Path};
 
-fn parse_input(s: &str) -> Result<Vec<u8>, Error> {
+/// A single number from the license: a header quantity or a metadata entry.
+pub type Value = u32;
+
+/// How deeply nodes may nest.
+///
+/// Each level of nesting is a level of recursion while parsing, so without a limit,
+/// a long enough chain of single children would overflow the stack.
+pub const MAX_DEPTH: usize = 1024;
+
+#[cfg(test)]
+fn parse_input(s: &str) -> Result<Vec<Value>, Error> {
+    parse_input_with_offsets(s).map(|(values, _)| values)
+}
+
+/// Parse the input, returning each value along with the byte offset of its token.
+fn parse_input_with_offsets(s: &str) -> Result<(Vec<Value>, Vec<usize>), Error> {
     s.split_whitespace()
-        .map(|n| n.parse().map_err(Into::into))
-        .collect()
+        .map(|n| {
+            let offset = n.as_ptr() as usize - s.as_ptr() as usize;
+            n.parse::<Value>()
+                .map(|value| (value, offset))
+                .map_err(|source| Error::ParseInt {
+                    token: n.to_string(),
+                    source,
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|parsed| parsed.into_iter().unzip())
+}
+
+/// The path from the root to a node: the index of each child taken along the way.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NodePath(pub Vec<usize>);
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for index in self.0.iter().rev() {
+            write!(f, "child {} of ", index)?;
+        }
+        write!(f, "root")
+    }
+}
+
+/// What a truncated node was missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Missing {
+    /// The header needs two values, but fewer remained.
+    Header { found: usize },
+    /// The header called for more metadata entries than remained.
+    Metadata { expected: usize, found: usize },
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Missing::Header { found } => {
+                write!(f, "expected a header of 2 values, found {}", found)
+            }
+            Missing::Metadata { expected, found } => {
+                write!(f, "expected {} metadata entries, found {}", expected, found)
+            }
+        }
+    }
 }
 
-fn parse_input_file(input: &Path) -> Result<Vec<u8>, Error> {
-    let s = std::fs::read_to_string(input)?;
-    parse_input(&s)
+/// Where a node begins in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The index of the node's first value.
+    pub value: usize,
+    /// The byte offset of the node's first value, when parsed from text.
+    pub byte: Option<usize>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value {}", self.value)?;
+        if let Some(byte) = self.byte {
+            write!(f, " (byte {})", byte)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Node<'i> {
     children: Vec<Node<'i>>,
-    metadata: &'i [u8],
+    metadata: &'i [Value],
 }
 
 impl<'i> Node<'i> {
     /// Try to parse this node from the given input.
     ///
     /// Returns the node and the unconsumed input.
-    pub fn try_parse(mut input: &'i [u8]) -> Result<(Node<'_>, &[u8]), Error> {
+    pub fn try_parse(input: &'i [Value]) -> Result<(Node<'_>, &[Value]), Error> {
+        let mut out = Node::default();
+        let remaining = out.parse_into(input, input.len(), &mut NodePath::default())?;
+        Ok((out, remaining))
+    }
+
+    /// Parse as much of a node as the given input allows.
+    ///
+    /// Returns the node, and the error which stopped parsing, if any. On error, the node
+    /// holds every child and metadata entry parsed before it: the node at the error's path
+    /// keeps whatever metadata remained, and it and its ancestors are incomplete.
+    pub fn parse_partial(input: &'i [Value]) -> (Node<'i>, Option<Error>) {
+        let mut out = Node::default();
+        let err = out
+            .parse_into(input, input.len(), &mut NodePath::default())
+            .err();
+        (out, err)
+    }
+
+    /// Parse into this node, which must be empty, and return the unconsumed input.
+    ///
+    /// `total` is the length of the whole input, so errors can report where this node begins.
+    /// `path` leads to this node; it's left that way even on error.
+    fn parse_into(
+        &mut self,
+        mut input: &'i [Value],
+        total: usize,
+        path: &mut NodePath,
+    ) -> Result<&'i [Value], Error> {
+        if path.0.len() >= MAX_DEPTH {
+            return Err(Error::TooDeep);
+        }
+        let start = total - input.len();
+        let not_enough_input = |missing, path: &NodePath| Error::NotEnoughInput {
+            path: path.clone(),
+            missing,
+            location: Location {
+                value: start,
+                byte: None,
+            },
+        };
         if input.len() < 2 {
-            return Err(Error::NotEnoughInput);
+            return Err(not_enough_input(
+                Missing::Header { found: input.len() },
+                path,
+            ));
         }
 
-        let nchildren = input[0] as usize;
-        let nmetadata = input[1] as usize;
+        let nchildren = input[0];
+        let nmetadata: usize = input[1].try_into().map_err(|_| {
+            not_enough_input(
+                Missing::Metadata {
+                    expected: usize::MAX,
+                    found: input.len() - 2,
+                },
+                path,
+            )
+        })?;
 
         input = &input[2..];
 
-        let mut out = Node::default();
-
         // parse child nodes
-        for _ in 0..nchildren {
-            let (child, remaining) = Node::try_parse(input)?;
-            out.children.push(child);
-            input = remaining;
+        for index in 0..nchildren as usize {
+            self.children.push(Node::default());
+            path.0.push(index);
+            let child = self.children.last_mut().expect("just pushed a child");
+            input = child.parse_into(input, total, path)?;
+            path.0.pop();
         }
 
         // store node metadata and remove it from input
         // (can't use `.split_at(nmetadata)` because destructuring assignment _still_ isn't a thing,
         // (but it's getting closer now!))
-        out.metadata = &input[..nmetadata];
+        if input.len() < nmetadata {
+            self.metadata = input;
+            return Err(not_enough_input(
+                Missing::Metadata {
+                    expected: nmetadata,
+                    found: input.len(),
+                },
+                path,
+            ));
+        }
+        self.metadata = &input[..nmetadata];
         input = &input[nmetadata..];
 
-        Ok((out, input))
+        Ok(input)
     }
 
     pub fn iter(&'i self) -> Box<dyn 'i + Iterator<Item = &Node>> {
         Box::new(std::iter::once(self).chain(self.children.iter().flat_map(|child| child.iter())))
     }
 
-    pub fn sum_metadata(&self) -> u32 {
+    /// Sum of all metadata in this node and its descendants.
+    ///
+    /// Sums are `u64` so that many large metadata entries can't overflow.
+    pub fn sum_metadata(&self) -> u64 {
         self.iter()
-            .map(|node| node.metadata.iter().map(|&m| m as u32).sum::<u32>())
+            .map(|node| node.metadata.iter().map(|&m| m as u64).sum::<u64>())
             .sum()
     }
 
-    fn child_value(&self, index: u8) -> u32 {
-        self.children
-            // metadata indices are 1-indices; our lists are 0-indices
-            .get(index as usize - 1)
-            .map(|child| child.value())
-            .unwrap_or_default()
-    }
-
-    pub fn value(&self) -> u32 {
+    /// The value of this node: the sum of its metadata if it has no children, or else the sum
+    /// of the values of the children its metadata refers to.
+    ///
+    /// Each child's value is computed once, however many times it's referred to, so this takes
+    /// time proportional to the size of the tree. Repeated references multiply values at each
+    /// level of nesting, so the value saturates at `u64::MAX` rather than overflowing.
+    pub fn value(&self) -> u64 {
         if self.children.is_empty() {
-            self.sum_metadata()
-        } else {
-            self.metadata.iter().map(|&n| self.child_value(n)).sum()
+            return self.sum_metadata();
         }
+
+        let child_values: Vec<u64> = self.children.iter().map(Node::value).collect();
+        self.metadata
+            .iter()
+            .map(|&index| {
+                // metadata indices are 1-indices; our lists are 0-indices
+                (index as usize)
+                    .checked_sub(1)
+                    .and_then(|index| child_values.get(index))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .fold(0, u64::saturating_add)
     }
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let input = parse_input_file(input)?;
-    let (node, remainder) = Node::try_parse(&input)?;
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+/// Parse as much of a tree as the input allows, and describe it.
+///
+/// This is for debugging hand-crafted inputs: on error, the partial tree is shown along with
+/// where and why parsing stopped.
+pub fn diagnose_from_str(input: &str) -> Result<String, Error> {
+    let (values, offsets) = parse_input_with_offsets(input)?;
+    let (node, err) = Node::parse_partial(&values);
+    let mut out = format!("{:#?}", node);
+    if let Some(err) = err {
+        out.push_str(&format!("\n{}", err.with_offsets(&offsets, input.len())));
+    }
+    Ok(out)
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let (values, offsets) = parse_input_with_offsets(input)?;
+    let (node, remainder) =
+        Node::try_parse(&values).map_err(|err| err.with_offsets(&offsets, input.len()))?;
     if !remainder.is_empty() {
         eprintln!("found {} extra bytes in input", remainder.len());
     }
 
-    println!("sum of metadata: {}", node.sum_metadata());
-    Ok(())
+    Ok(format!("sum of metadata: {}", node.sum_metadata()))
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let input = parse_input_file(input)?;
-    let (node, _) = Node::try_parse(&input)?;
-
-    println!("value of root: {}", node.value());
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let (values, offsets) = parse_input_with_offsets(input)?;
+    let (node, _) =
+        Node::try_parse(&values).map_err(|err| err.with_offsets(&offsets, input.len()))?;
+
+    Ok(format!("value of root: {}", node.value()))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error("failed to parse integer")]
-    ParseInt(#[from] std::num::ParseIntError),
-    #[error("Nodes require at least two digits to describe the quantity of children and metadata")]
-    NotEnoughInput,
+    #[error("invalid number \"{token}\"")]
+    ParseInt {
+        token: String,
+        #[source]
+        source: std::num::ParseIntError,
+    },
+    #[error("not enough input for {path}, which begins at {location}: {missing}")]
+    NotEnoughInput {
+        path: NodePath,
+        missing: Missing,
+        location: Location,
+    },
+    #[error("nodes are nested more than {} deep", MAX_DEPTH)]
+    TooDeep,
+}
+
+impl Error {
+    /// Fill in the byte offset of a truncated node, given the offset of each value
+    /// and the length of the text they were parsed from.
+    fn with_offsets(mut self, offsets: &[usize], len: usize) -> Self {
+        if let Error::NotEnoughInput { location, .. } = &mut self {
+            location.byte = Some(offsets.get(location.value).copied().unwrap_or(len));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2";
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(part1_from_str(EXAMPLE).unwrap(), "sum of metadata: 138");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2_from_str(EXAMPLE).unwrap(), "value of root: 66");
+    }
+
+    #[test]
+    fn wide_metadata() {
+        // one leaf with 300 metadata entries, then a leaf with large metadata
+        let mut input = vec![2, 2, 0, 300];
+        input.extend(std::iter::repeat(1_000).take(300));
+        input.extend(&[0, 2, 70_000, 4_000_000_000, 1, 2]);
+        let (node, remainder) = Node::try_parse(&input).unwrap();
+        assert!(remainder.is_empty());
+        assert_eq!(node.sum_metadata(), 300_000 + 70_000 + 4_000_000_000 + 3);
+        assert_eq!(node.value(), 300_000 + 70_000 + 4_000_000_000);
+    }
+
+    #[test]
+    fn large_child_index() {
+        let input = parse_input("1 2 0 1 5 256 1").unwrap();
+        let (node, _) = Node::try_parse(&input).unwrap();
+        // index 256 refers to no child, and 0 is never a valid index
+        assert_eq!(node.value(), 5);
+        let input = parse_input("1 1 0 1 5 0").unwrap();
+        assert_eq!(Node::try_parse(&input).unwrap().0.value(), 0);
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(matches!(
+            parse_input("1 1 0 1 4294967296 1"),
+            Err(Error::ParseInt { token, .. }) if token == "4294967296"
+        ));
+    }
+
+    /// A chain of `depth` nodes, each of whose metadata refers to its only child `width` times.
+    fn repeated_references(depth: usize, width: usize) -> Vec<Value> {
+        let mut input = Vec::new();
+        for _ in 1..depth {
+            input.extend(&[1, width as Value]);
+        }
+        input.extend(&[0, 1, Value::MAX]);
+        for _ in 1..depth {
+            input.extend(std::iter::repeat(1).take(width));
+        }
+        input
+    }
+
+    #[test]
+    fn value_saturates() {
+        let input = repeated_references(3, 100);
+        let (node, remainder) = Node::try_parse(&input).unwrap();
+        assert!(remainder.is_empty());
+        assert_eq!(node.value(), Value::MAX as u64 * 100 * 100);
+
+        // each level multiplies the value by 100; computed naively, this would never finish
+        let input = repeated_references(40, 100);
+        let (node, _) = Node::try_parse(&input).unwrap();
+        assert_eq!(node.value(), u64::MAX);
+    }
+
+    #[test]
+    fn too_deep() {
+        let input = repeated_references(MAX_DEPTH, 1);
+        assert!(Node::try_parse(&input).is_ok());
+        let input = repeated_references(MAX_DEPTH + 1, 1);
+        assert!(matches!(Node::try_parse(&input), Err(Error::TooDeep)));
+    }
+
+    #[test]
+    fn truncated_metadata() {
+        let input = parse_input("0 3 1 2").unwrap();
+        assert!(matches!(
+            Node::try_parse(&input),
+            Err(Error::NotEnoughInput {
+                path,
+                missing: Missing::Metadata { expected: 3, found: 2 },
+                location: Location { value: 0, byte: None },
+            }) if path == NodePath::default()
+        ));
+    }
+
+    #[test]
+    fn truncated_child() {
+        // the root's third child is missing its last metadata entry
+        let input = "3 1  0 1 7  1 1 0 0 2  0 2 4";
+        let err = part1_from_str(input).unwrap_err();
+        assert!(matches!(
+            &err,
+            Error::NotEnoughInput {
+                path,
+                missing: Missing::Metadata { expected: 2, found: 1 },
+                location: Location { value: 10, byte: Some(23) },
+            } if path == &NodePath(vec![2])
+        ));
+        assert_eq!(
+            err.to_string(),
+            "not enough input for child 2 of root, which begins at value 10 (byte 23): \
+             expected 2 metadata entries, found 1"
+        );
+
+        let values = parse_input(input).unwrap();
+        let (partial, err) = Node::parse_partial(&values);
+        assert!(err.is_some());
+        assert_eq!(partial.children.len(), 3);
+        assert_eq!(partial.children[0].metadata, &[7]);
+        assert_eq!(partial.children[1].children.len(), 1);
+        assert_eq!(partial.children[1].metadata, &[2]);
+        assert_eq!(partial.children[2].metadata, &[4]);
+        assert!(partial.metadata.is_empty());
+    }
+
+    #[test]
+    fn truncated_header() {
+        let values = parse_input("1 1 1 0 0").unwrap();
+        let (partial, err) = Node::parse_partial(&values);
+        assert!(matches!(
+            err,
+            Some(Error::NotEnoughInput {
+                path,
+                missing: Missing::Header { found: 1 },
+                location: Location { value: 4, byte: None },
+            }) if path == NodePath(vec![0, 0])
+        ));
+        assert_eq!(partial.children[0].children, vec![Node::default()]);
+        assert!(diagnose_from_str("1 1 1 0 0").unwrap().ends_with(
+            "child 0 of child 0 of root, which begins at value 4 (byte 8): \
+                        expected a header of 2 values, found 1"
+        ));
+    }
+
+    #[test]
+    fn partial_complete() {
+        let values = parse_input(EXAMPLE).unwrap();
+        let (partial, err) = Node::parse_partial(&values);
+        assert!(err.is_none());
+        assert_eq!(partial, Node::try_parse(&values).unwrap().0);
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day08-crlf", &format!("{}\n", EXAMPLE)).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(part1_from_str(&input).unwrap(), "sum of metadata: 138");
+    }
 }
@@ -0,0 +1,39 @@
+//! Compare the circle storages by playing the same game with each.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day09::{ArrayCircle, MarbleStorage, SlabStorage, State};
+use std::collections::VecDeque;
+
+const PLAYERS: usize = 430;
+
+fn play<C: MarbleStorage>(last_marble: u32) -> Option<(usize, u32)> {
+    let mut state = State::<C>::with_storage(PLAYERS, last_marble);
+    state.run();
+    state.winner()
+}
+
+fn bench_storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("storage");
+    group.sample_size(20);
+    for &last_marble in &[71_588, 715_880] {
+        group.bench_with_input(
+            BenchmarkId::new("VecDeque", last_marble),
+            &last_marble,
+            |b, &last_marble| b.iter(|| play::<VecDeque<u32>>(last_marble)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("SlabStorage", last_marble),
+            &last_marble,
+            |b, &last_marble| b.iter(|| play::<SlabStorage>(last_marble)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("ArrayCircle", last_marble),
+            &last_marble,
+            |b, &last_marble| b.iter(|| play::<ArrayCircle>(last_marble)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_storage);
+criterion_main!(benches);
@@ -0,0 +1,502 @@
+use std::ops::{Index, IndexMut};
+
+/// Identifies a single node within a [`Circle`].
+///
+/// Ids remain valid until their node is removed. Using an id after its node has been
+/// removed either panics or, if the slot has since been reused, refers to the new node.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeId(usize);
+
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    prev: usize,
+    next: usize,
+}
+
+/// A circular doubly-linked list, stored in a slab.
+///
+/// Positions within the circle are plain [`NodeId`]s, so any number of independent
+/// pointers can move around the same circle. Mutation goes through `&mut Circle`;
+/// read-only traversal can use any number of borrowed [`Cursor`]s at once.
+///
+/// Moving clockwise follows `next`; counter-clockwise follows `prev`.
+///
+/// The circle also tracks a head node, which is where whole-circle operations such as
+/// [`iter`](Circle::iter) begin. The head is the first node inserted; if it is removed,
+/// the node clockwise of it becomes the new head.
+///
+/// With the `serde` feature, a circle serializes as the sequence of its values beginning
+/// at the head. Node ids are not preserved across serialization.
+#[derive(Debug)]
+pub struct Circle<T> {
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+    head: Option<usize>,
+    len: usize,
+}
+
+impl<T> Default for Circle<T> {
+    fn default() -> Self {
+        Circle {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: None,
+            len: 0,
+        }
+    }
+}
+
+impl<T> Circle<T> {
+    /// Create a circle containing a single value.
+    pub fn new(value: T) -> (Self, NodeId) {
+        Self::with_capacity(value, 1)
+    }
+
+    /// Create a circle containing a single value, with space for `capacity` values.
+    pub fn with_capacity(value: T, capacity: usize) -> (Self, NodeId) {
+        let mut circle = Circle {
+            nodes: Vec::with_capacity(capacity.max(1)),
+            ..Circle::default()
+        };
+        let id = circle.push_back(value);
+        (circle, id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of bytes allocated for nodes, including free slots.
+    pub fn memory_footprint(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Option<Node<T>>>()
+            + self.free.capacity() * std::mem::size_of::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The head of the circle, or `None` if it is empty.
+    pub fn head(&self) -> Option<NodeId> {
+        self.head.map(NodeId)
+    }
+
+    fn node(&self, id: NodeId) -> &Node<T> {
+        self.nodes[id.0].as_ref().expect("node was removed")
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        self.nodes[id.0].as_mut().expect("node was removed")
+    }
+
+    /// The node clockwise of `id`.
+    pub fn next(&self, id: NodeId) -> NodeId {
+        NodeId(self.node(id).next)
+    }
+
+    /// The node counter-clockwise of `id`.
+    pub fn prev(&self, id: NodeId) -> NodeId {
+        NodeId(self.node(id).prev)
+    }
+
+    /// The node `steps` positions from `id`: clockwise if positive, counter-clockwise if negative.
+    pub fn step(&self, mut id: NodeId, steps: isize) -> NodeId {
+        for _ in 0..steps.abs() {
+            id = if steps > 0 {
+                self.next(id)
+            } else {
+                self.prev(id)
+            };
+        }
+        id
+    }
+
+    /// Store a node in a free slot, returning its index.
+    fn alloc(&mut self, node: Node<T>) -> usize {
+        match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Insert `value` immediately clockwise of `id`, returning the new node's id.
+    pub fn insert_after(&mut self, id: NodeId, value: T) -> NodeId {
+        let prev = id.0;
+        let next = self.node(id).next;
+        let new = self.alloc(Node { value, prev, next });
+        self.node_mut(NodeId(prev)).next = new;
+        self.node_mut(NodeId(next)).prev = new;
+        self.len += 1;
+        NodeId(new)
+    }
+
+    /// Insert `value` immediately counter-clockwise of the head, so it is last in iteration order.
+    ///
+    /// If the circle is empty, the new node becomes the head.
+    pub fn push_back(&mut self, value: T) -> NodeId {
+        match self.head {
+            Some(head) => self.insert_before(NodeId(head), value),
+            None => {
+                let new = self.alloc(Node {
+                    value,
+                    prev: 0,
+                    next: 0,
+                });
+                let node = self.node_mut(NodeId(new));
+                node.prev = new;
+                node.next = new;
+                self.head = Some(new);
+                self.len = 1;
+                NodeId(new)
+            }
+        }
+    }
+
+    /// Insert `value` immediately counter-clockwise of `id`, returning the new node's id.
+    pub fn insert_before(&mut self, id: NodeId, value: T) -> NodeId {
+        let prev = self.prev(id);
+        self.insert_after(prev, value)
+    }
+
+    /// Remove the node `id`, returning its value and the id of the node which was clockwise of it.
+    ///
+    /// The returned id is `None` if the circle is now empty.
+    pub fn remove(&mut self, id: NodeId) -> (T, Option<NodeId>) {
+        let Node { value, prev, next } = self.nodes[id.0].take().expect("node was removed");
+        self.free.push(id.0);
+        self.len -= 1;
+        if self.len == 0 {
+            self.head = None;
+            return (value, None);
+        }
+        if self.head == Some(id.0) {
+            self.head = Some(next);
+        }
+        self.node_mut(NodeId(prev)).next = next;
+        self.node_mut(NodeId(next)).prev = prev;
+        (value, Some(NodeId(next)))
+    }
+
+    /// A read-only cursor positioned at `id`.
+    pub fn cursor(&self, id: NodeId) -> Cursor<'_, T> {
+        // validate eagerly so a bad id fails here instead of on first use
+        self.node(id);
+        Cursor { circle: self, id }
+    }
+
+    /// Iterate clockwise over every value, beginning at the head.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = &T> {
+        let len = if self.head.is_some() { self.len } else { 0 };
+        let mut id = self.head.map(NodeId);
+        (0..len).map(move |_| {
+            let current = id.expect("circle is not empty");
+            id = Some(self.next(current));
+            &self[current]
+        })
+    }
+
+    /// Remove every value, yielding them clockwise from the head.
+    ///
+    /// The circle is empty afterwards, even if the iterator is not consumed.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> {
+        let mut circle = std::mem::take(self);
+        let mut id = circle.head.map(NodeId);
+        std::iter::from_fn(move || {
+            let (value, next) = circle.remove(id?);
+            id = next;
+            Some(value)
+        })
+    }
+
+    /// Remove every value for which `keep` returns `false`, visiting clockwise from the head.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let mut id = match self.head {
+            Some(head) => NodeId(head),
+            None => return,
+        };
+        for _ in 0..self.len {
+            let next = self.next(id);
+            if !keep(&self[id]) {
+                self.remove(id);
+            }
+            id = next;
+        }
+    }
+
+    /// Iterate clockwise over every value, beginning at `id`.
+    pub fn iter_from(&self, id: NodeId) -> impl '_ + Iterator<Item = &T> {
+        let mut cursor = self.cursor(id);
+        (0..self.len).map(move |_| {
+            let value = cursor.value();
+            cursor.move_next();
+            value
+        })
+    }
+}
+
+impl<T> Index<NodeId> for Circle<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        &self.node(id).value
+    }
+}
+
+impl<T> IndexMut<NodeId> for Circle<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut Self::Output {
+        &mut self.node_mut(id).value
+    }
+}
+
+impl<T> From<Vec<T>> for Circle<T> {
+    /// Build a circle whose head is the first element of the vector.
+    fn from(values: Vec<T>) -> Self {
+        let mut circle = Circle {
+            nodes: Vec::with_capacity(values.len()),
+            ..Circle::default()
+        };
+        for value in values {
+            circle.push_back(value);
+        }
+        circle
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Circle<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Circle<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Circle::from)
+    }
+}
+
+/// A read-only position within a [`Circle`].
+///
+/// Cursors borrow the circle immutably, so many of them can traverse it independently.
+#[derive(Debug)]
+pub struct Cursor<'a, T> {
+    circle: &'a Circle<T>,
+    id: NodeId,
+}
+
+impl<'a, T> Clone for Cursor<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Cursor<'a, T> {}
+
+impl<'a, T> Cursor<'a, T> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn value(&self) -> &'a T {
+        &self.circle[self.id]
+    }
+
+    pub fn move_next(&mut self) {
+        self.id = self.circle.next(self.id);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.id = self.circle.prev(self.id);
+    }
+
+    pub fn step(&mut self, steps: isize) {
+        self.id = self.circle.step(self.id, steps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values<T: Clone>(circle: &Circle<T>, from: NodeId) -> Vec<T> {
+        circle.iter_from(from).cloned().collect()
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let (mut circle, zero) = Circle::new(0);
+        let one = circle.insert_after(zero, 1);
+        let two = circle.insert_after(one, 2);
+        circle.insert_before(zero, 3);
+        assert_eq!(values(&circle, zero), vec![0, 1, 2, 3]);
+
+        assert_eq!(circle.remove(one), (1, Some(two)));
+        assert_eq!(values(&circle, zero), vec![0, 2, 3]);
+
+        // the freed slot is reused
+        let four = circle.insert_after(two, 4);
+        assert_eq!(four, one);
+        assert_eq!(values(&circle, zero), vec![0, 2, 4, 3]);
+    }
+
+    #[test]
+    fn remove_last() {
+        let (mut circle, zero) = Circle::new('a');
+        assert_eq!(circle.remove(zero), ('a', None));
+        assert!(circle.is_empty());
+    }
+
+    #[test]
+    fn independent_pointers() {
+        let (mut circle, first) = Circle::new(0);
+        let mut last = first;
+        for value in 1..10 {
+            last = circle.insert_after(last, value);
+        }
+
+        // a tortoise and a hare, moving independently through the same circle
+        let mut slow = circle.cursor(first);
+        let mut fast = circle.cursor(first);
+        for _ in 0..7 {
+            slow.move_next();
+            fast.step(2);
+        }
+        assert_eq!((*slow.value(), *fast.value()), (7, 4));
+        slow.move_prev();
+        assert_eq!(*slow.value(), 6);
+
+        // ids held by separate pointers survive mutation elsewhere in the circle
+        let (slow, fast) = (slow.id(), fast.id());
+        circle.insert_after(slow, 60);
+        circle[fast] *= 10;
+        assert_eq!(circle.step(slow, 1), circle.prev(circle.step(slow, 2)));
+        assert_eq!(circle[circle.next(slow)], 60);
+        assert_eq!(circle[fast], 40);
+    }
+
+    #[test]
+    fn marble_game() {
+        // the circle can play the game as well as the `VecDeque` representation
+        let players = 9;
+        let (mut circle, mut current) = Circle::new(0);
+        let mut scores = vec![0; players];
+        for marble in 1..=25 {
+            if marble % 23 == 0 {
+                let removed = circle.step(current, -7);
+                let (value, next) = circle.remove(removed);
+                scores[marble as usize % players] += marble + value;
+                current = next.unwrap();
+            } else {
+                current = circle.insert_after(circle.next(current), marble);
+            }
+        }
+        assert_eq!(scores.iter().max(), Some(&32));
+        assert_eq!(scores[5], 32);
+    }
+
+    fn collect<T: Clone>(circle: &Circle<T>) -> Vec<T> {
+        circle.iter().cloned().collect()
+    }
+
+    #[test]
+    fn from_vec() {
+        let circle = Circle::from(vec![1, 2, 3]);
+        assert_eq!(circle.len(), 3);
+        assert_eq!(collect(&circle), vec![1, 2, 3]);
+        let head = circle.head().unwrap();
+        assert_eq!(circle[circle.prev(head)], 3);
+
+        let empty = Circle::<u8>::from(Vec::new());
+        assert!(empty.is_empty());
+        assert_eq!(empty.head(), None);
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    #[test]
+    fn remove_head() {
+        let mut circle = Circle::from(vec![1, 2, 3]);
+        let head = circle.head().unwrap();
+        let next = circle.next(head);
+        assert_eq!(circle.remove(head), (1, Some(next)));
+        assert_eq!(circle.head(), Some(next));
+        assert_eq!(collect(&circle), vec![2, 3]);
+    }
+
+    #[test]
+    fn remove_tail() {
+        let mut circle = Circle::from(vec![1, 2, 3]);
+        let head = circle.head().unwrap();
+        let tail = circle.prev(head);
+        // removing the tail wraps around to the head
+        assert_eq!(circle.remove(tail), (3, Some(head)));
+        assert_eq!(circle.head(), Some(head));
+        assert_eq!(circle.next(circle.next(head)), head);
+        assert_eq!(collect(&circle), vec![1, 2]);
+    }
+
+    #[test]
+    fn empty_and_refill() {
+        let mut circle = Circle::from(vec![1, 2]);
+        let head = circle.head().unwrap();
+        let (_, next) = circle.remove(head);
+        assert_eq!(circle.remove(next.unwrap()), (2, None));
+        assert_eq!(circle.head(), None);
+
+        let id = circle.push_back(3);
+        assert_eq!(circle.head(), Some(id));
+        assert_eq!((circle.next(id), circle.prev(id)), (id, id));
+        circle.push_back(4);
+        assert_eq!(collect(&circle), vec![3, 4]);
+    }
+
+    #[test]
+    fn drain() {
+        let mut circle = Circle::from(vec!['a', 'b', 'c']);
+        assert_eq!(circle.drain().collect::<String>(), "abc");
+        assert!(circle.is_empty());
+
+        let mut circle = Circle::from(vec!['a', 'b', 'c']);
+        circle.drain().next();
+        assert!(circle.is_empty());
+        assert_eq!(circle.head(), None);
+    }
+
+    #[test]
+    fn retain() {
+        let mut circle = Circle::from((0..10).collect::<Vec<_>>());
+        circle.retain(|value| value % 3 == 1);
+        assert_eq!(collect(&circle), vec![1, 4, 7]);
+
+        // dropping both the head and the tail
+        let mut circle = Circle::from(vec![0, 1, 2, 0]);
+        circle.retain(|&value| value != 0);
+        assert_eq!(collect(&circle), vec![1, 2]);
+        assert_eq!(circle[circle.head().unwrap()], 1);
+
+        circle.retain(|_| false);
+        assert!(circle.is_empty());
+        assert_eq!(circle.head(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut circle = Circle::from(vec![1, 2, 3]);
+        let head = circle.head().unwrap();
+        circle.remove(head);
+        circle.push_back(4);
+
+        let json = serde_json::to_string(&circle).unwrap();
+        assert_eq!(json, "[2,3,4]");
+        let circle: Circle<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(collect(&circle), vec![2, 3, 4]);
+    }
+}
@@ -1,5 +1,12 @@
-use aoclib::parse;
-use std::{collections::VecDeque, path::Path};
+pub mod circle;
+pub mod storage;
+
+use rayon::prelude::*;
+use std::{fmt, ops::Range, path::Path};
+use util::parse_str;
+
+pub use circle::{Circle, Cursor, NodeId};
+pub use storage::{peak_circle_len, ArrayCircle, MarbleStorage, Preallocation, SlabStorage};
 
 #[derive(Debug, parse_display::FromStr, parse_display::Display, Clone, Copy)]
 #[display("{players} players; last marble is worth {last_marble} points")]
@@ -8,13 +15,43 @@ struct Rules {
     last_marble: u32,
 }
 
+/// How a game is scored.
+///
+/// The [`Default`] rules are the puzzle's: each marble which is a multiple of 23 is kept
+/// and scores along with the marble 7 positions counter-clockwise; every other marble is
+/// placed 1 position clockwise of the current marble.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRules {
+    /// Marbles which are multiples of this score instead of being placed.
+    pub divisor: u32,
+    /// How far counter-clockwise of the current marble a scoring marble's partner is.
+    pub back_steps: usize,
+    /// How far clockwise of the current marble a marble is placed.
+    pub forward_steps: usize,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules {
+            divisor: 23,
+            back_steps: 7,
+            forward_steps: 1,
+        }
+    }
+}
+
+/// A game in progress, generic over how the circle of marbles is stored.
+///
+/// The default [`ArrayCircle`] is the fastest; [`SlabStorage`] and `VecDeque<u32>`
+/// are kept for comparison.
 #[derive(Debug)]
-pub struct State {
+pub struct State<C = ArrayCircle> {
     last_marble: u32,
     next_marble: u32,
     next_player: usize,
     scores: Vec<u32>,
-    circle: VecDeque<u32>,
+    circle: C,
+    rules: GameRules,
 }
 
 impl From<Rules> for State {
@@ -30,20 +67,54 @@ impl From<Rules> for State {
 
 impl State {
     pub fn new(players: usize, last_marble: u32) -> State {
-        // preload the first two steps, which are confusing anyway.
-        let mut circle = VecDeque::with_capacity(last_marble as usize);
-        circle.push_back(0);
-        circle.push_back(1);
+        State::with_storage(players, last_marble)
+    }
+}
+
+impl<C: MarbleStorage> State<C> {
+    /// Create a game whose circle is stored in `C`.
+    pub fn with_storage(players: usize, last_marble: u32) -> State<C> {
+        State::with_preallocation(players, last_marble, Preallocation::default())
+    }
 
+    /// Create a game whose circle is stored in `C`, reserving space according to `preallocation`.
+    pub fn with_preallocation(
+        players: usize,
+        last_marble: u32,
+        preallocation: Preallocation,
+    ) -> State<C> {
+        // preload the first two steps, which are confusing anyway.
         State {
             last_marble,
             next_marble: 2,
-            next_player: 2,
+            next_player: 2 % players.max(1),
             scores: vec![0; players],
-            circle,
+            circle: C::with_preallocation(last_marble, preallocation),
+            rules: GameRules::default(),
         }
     }
 
+    /// Play by `rules` instead of the puzzle's.
+    ///
+    /// Space is still reserved as the puzzle's rules would need; the circle grows if necessary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rules.divisor` is less than 2, which could empty the circle.
+    pub fn with_rules(mut self, rules: GameRules) -> Self {
+        assert!(rules.divisor >= 2, "divisor must be at least 2");
+        self.rules = rules;
+        self
+    }
+
+    /// An estimate of the bytes used by this game: the state itself, plus what it has
+    /// allocated on the heap.
+    pub fn memory_footprint(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.scores.capacity() * std::mem::size_of::<u32>()
+            + self.circle.memory_footprint()
+    }
+
     fn step(&mut self) {
         if self.next_marble > self.last_marble {
             return;
@@ -54,13 +125,11 @@ impl State {
         let player = self.next_player;
         self.next_player = (self.next_player + 1) % self.scores.len();
 
-        if marble % 23 == 0 {
+        if marble % self.rules.divisor == 0 {
             self.scores[player] += marble;
-            self.circle.rotate_left(7);
-            self.scores[player] += self.circle.pop_back().unwrap();
+            self.scores[player] += self.circle.remove_scoring(self.rules.back_steps);
         } else {
-            self.circle.rotate_right(2);
-            self.circle.push_back(marble);
+            self.circle.place(marble, self.rules.forward_steps);
         }
     }
 
@@ -70,6 +139,11 @@ impl State {
         }
     }
 
+    /// The marbles in the circle in clockwise order, beginning with the smallest.
+    pub fn circle_snapshot(&self) -> Vec<u32> {
+        self.circle.marbles()
+    }
+
     pub fn winner(&self) -> Option<(usize, u32)> {
         if self.next_marble <= self.last_marble {
             return None;
@@ -84,27 +158,121 @@ impl State {
     }
 }
 
+/// Displays like a line of the puzzle's worked example: the player who placed the most
+/// recent marble, then the circle from marble 0 with the current marble in parentheses.
+///
+/// ```
+/// # use day09::State;
+/// let state = State::new(9, 25);
+/// assert_eq!(state.to_string(), "[1]  0 (1)");
+/// ```
+impl<C: MarbleStorage> fmt::Display for State<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let last_played = self.next_marble - 1;
+        // marble 1 is placed by the first player
+        write!(
+            f,
+            "[{}]",
+            (last_played as usize - 1) % self.scores.len() + 1
+        )?;
+
+        // each marble gets a column wide enough for the largest marble plus a space;
+        // the parentheses around the current marble spill into the neighboring columns
+        let width = (last_played.to_string().len() + 1).max(3);
+        let current = self.circle.current();
+        let mut after_current = false;
+        for marble in self.circle.marbles() {
+            let column = if marble == current {
+                format!("({}", marble)
+            } else {
+                marble.to_string()
+            };
+            let mut column = format!("{:>width$}", column, width = width);
+            if after_current {
+                column.replace_range(..1, ")");
+            }
+            f.write_str(&column)?;
+            after_current = marble == current;
+        }
+        if after_current {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Play a complete game for each `last_marble` in `range`, stepping by `step`.
+///
+/// Games are independent, so they're played in parallel. Results are returned in order
+/// of `last_marble` as `(last_marble, winning_player, winning_score)`.
+///
+/// # Panics
+///
+/// Panics if `players` or `step` is zero.
+pub fn sweep(players: usize, range: Range<u32>, step: usize) -> Vec<(u32, usize, u64)> {
+    assert_ne!(players, 0, "at least one player is required");
+    let last_marbles: Vec<_> = range.step_by(step).collect();
+    last_marbles
+        .into_par_iter()
+        .map(|last_marble| {
+            let mut state = State::new(players, last_marble);
+            state.run();
+            let (player, score) = state.winner().expect("game has completed");
+            (last_marble, player, score as u64)
+        })
+        .collect()
+}
+
 pub fn part1(input: &Path) -> Result<(), Error> {
-    for rules in parse::<Rules>(input)? {
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for rules in parse_str::<Rules>(input) {
         let mut state: State = rules.into();
         state.run();
         let (_player, winning_score) = state.winner().ok_or(Error::NoSolution)?;
 
-        println!("{} => winning score: {}", rules, winning_score);
+        lines.push(format!("{} => winning score: {}", rules, winning_score));
     }
-    Ok(())
+    Ok(lines.join("\n"))
+}
+
+/// Solve part 1, playing each game by `rules` instead of the puzzle's.
+///
+/// # Panics
+///
+/// Panics if `rules.divisor` is less than 2.
+pub fn part1_with_rules(input: &str, rules: GameRules) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for game in parse_str::<Rules>(input) {
+        let mut state = State::from(game).with_rules(rules);
+        state.run();
+        let (_player, winning_score) = state.winner().ok_or(Error::NoSolution)?;
+
+        lines.push(format!("{} => winning score: {}", game, winning_score));
+    }
+    Ok(lines.join("\n"))
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    for mut rules in parse::<Rules>(input)? {
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for mut rules in parse_str::<Rules>(input) {
         rules.last_marble *= 100;
         let mut state: State = rules.into();
         state.run();
         let (_player, winning_score) = state.winner().ok_or(Error::NoSolution)?;
 
-        println!("{} => winning score: {}", rules, winning_score);
+        lines.push(format!("{} => winning score: {}", rules, winning_score));
     }
-    Ok(())
+    Ok(lines.join("\n"))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -160,4 +328,203 @@ mod tests {
         s.run();
         assert_eq!(37305, s.winner().unwrap().1);
     }
+
+    fn winner<C: MarbleStorage>(players: usize, last_marble: u32) -> (usize, u32) {
+        let mut s = State::<C>::with_storage(players, last_marble);
+        s.run();
+        s.winner().unwrap()
+    }
+
+    #[test]
+    fn storages_agree() {
+        use std::collections::VecDeque;
+
+        let games = [
+            (9, 25),
+            (10, 1618),
+            (13, 7999),
+            (17, 1104),
+            (21, 6111),
+            (30, 5807),
+        ];
+        for &(players, last_marble) in &games {
+            let expect = winner::<VecDeque<u32>>(players, last_marble);
+            assert_eq!(winner::<SlabStorage>(players, last_marble), expect);
+            assert_eq!(winner::<ArrayCircle>(players, last_marble), expect);
+        }
+    }
+
+    #[test]
+    fn display_matches_worked_example() {
+        let mut s = State::new(9, 25);
+        let mut lines = vec![s.to_string()];
+        while s.next_marble <= s.last_marble {
+            s.step();
+            lines.push(s.to_string());
+        }
+
+        assert_eq!(lines.len(), 25);
+        assert_eq!(lines[0], "[1]  0 (1)");
+        assert_eq!(lines[1], "[2]  0 (2) 1");
+        assert_eq!(lines[2], "[3]  0  2  1 (3)");
+        assert_eq!(
+            lines[21],
+            "[4]  0 16  8 17  4 18  9 19  2 20 10 21  5(22)11  1 12  6 13  3 14  7 15"
+        );
+        assert_eq!(
+            lines[22],
+            "[5]  0 16  8 17  4 18(19) 2 20 10 21  5 22 11  1 12  6 13  3 14  7 15"
+        );
+        assert_eq!(
+            lines[24],
+            "[7]  0 16  8 17  4 18 19  2 24 20(25)10 21  5 22 11  1 12  6 13  3 14  7 15"
+        );
+    }
+
+    #[test]
+    fn snapshots_agree() {
+        use std::collections::VecDeque;
+
+        let mut array = State::<ArrayCircle>::with_storage(9, 100);
+        let mut slab = State::<SlabStorage>::with_storage(9, 100);
+        let mut deque = State::<VecDeque<u32>>::with_storage(9, 100);
+        for _ in 2..=100 {
+            array.step();
+            slab.step();
+            deque.step();
+            assert_eq!(slab.circle_snapshot(), array.circle_snapshot());
+            assert_eq!(deque.circle_snapshot(), array.circle_snapshot());
+            assert_eq!(slab.to_string(), array.to_string());
+            assert_eq!(deque.to_string(), array.to_string());
+        }
+        assert_eq!(array.circle_snapshot().len(), 101 - 2 * 4);
+    }
+
+    /// Run a game to completion, counting the steps at which its memory footprint changed.
+    fn reallocations<C: MarbleStorage>(mut state: State<C>) -> usize {
+        let mut footprint = state.memory_footprint();
+        let mut reallocations = 0;
+        while state.next_marble <= state.last_marble {
+            state.step();
+            let current = state.memory_footprint();
+            if current != footprint {
+                reallocations += 1;
+                footprint = current;
+            }
+        }
+        reallocations
+    }
+
+    #[test]
+    fn preallocation() {
+        use std::collections::VecDeque;
+
+        // the second example, with part 2's multiplier
+        let (players, last_marble) = (10, 1618 * 100);
+        assert!(reallocations(State::<ArrayCircle>::with_storage(players, last_marble)) <= 1);
+        assert!(reallocations(State::<SlabStorage>::with_storage(players, last_marble)) <= 1);
+        assert!(reallocations(State::<VecDeque<u32>>::with_storage(players, last_marble)) <= 1);
+
+        let grown =
+            State::<VecDeque<u32>>::with_preallocation(players, last_marble, Preallocation::None);
+        assert!(reallocations(grown) > 1);
+
+        // the default reserves less than the number of marbles, since some are removed
+        let deque = State::<VecDeque<u32>>::with_storage(players, last_marble);
+        let peak = peak_circle_len(last_marble);
+        assert!(peak < last_marble as usize);
+        assert!(deque.circle.capacity() >= peak);
+
+        // growing on demand gives the same result as reserving everything up front
+        let mut grown =
+            State::<ArrayCircle>::with_preallocation(9, 1618, Preallocation::Marbles(100));
+        grown.run();
+        assert_eq!(grown.winner(), Some(winner::<ArrayCircle>(9, 1618)));
+    }
+
+    #[test]
+    fn tiny_games() {
+        // games which end before the circle is exercised at all
+        for last_marble in 0..3 {
+            assert_eq!(winner::<ArrayCircle>(2, last_marble), (1, 0));
+        }
+    }
+
+    #[test]
+    fn sweep_matches_individual_games() {
+        let swept = sweep(10, 1..1619, 101);
+        assert_eq!(swept.len(), 17);
+        for (last_marble, player, score) in swept {
+            let mut s = State::new(10, last_marble);
+            s.run();
+            assert_eq!((player, score as u32), s.winner().unwrap());
+        }
+    }
+
+    #[test]
+    fn variant_rules() {
+        let rules = GameRules {
+            divisor: 5,
+            back_steps: 2,
+            forward_steps: 1,
+        };
+        let mut s = State::new(3, 7).with_rules(rules);
+        s.run();
+        // marble 5 scores along with marble 3
+        assert_eq!(s.winner(), Some((2, 8)));
+        assert_eq!(s.circle_snapshot(), vec![0, 4, 6, 2, 7, 1]);
+    }
+
+    #[test]
+    fn storages_agree_on_variants() {
+        use std::collections::VecDeque;
+
+        fn play<C: MarbleStorage>(rules: GameRules) -> (Option<(usize, u32)>, Vec<u32>) {
+            let mut s = State::<C>::with_storage(2, 500).with_rules(rules);
+            s.run();
+            (s.winner(), s.circle_snapshot())
+        }
+
+        for &(divisor, back_steps, forward_steps) in &[
+            (2, 0, 0),
+            (2, 3, 5),
+            (3, 1, 2),
+            (10, 12, 0),
+            (23, 7, 1),
+            (40, 30, 4),
+        ] {
+            let rules = GameRules {
+                divisor,
+                back_steps,
+                forward_steps,
+            };
+            let expect = play::<VecDeque<u32>>(rules);
+            assert_eq!(play::<SlabStorage>(rules), expect, "{:?}", rules);
+            assert_eq!(play::<ArrayCircle>(rules), expect, "{:?}", rules);
+        }
+    }
+
+    #[test]
+    fn default_rules() {
+        let mut s = State::new(10, 1618).with_rules(GameRules::default());
+        s.run();
+        assert_eq!(8317, s.winner().unwrap().1);
+    }
+
+    #[test]
+    fn sweep_example() {
+        assert_eq!(sweep(9, 25..26, 1), vec![(25, 5, 32)]);
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file =
+            util::CrlfInputFile::new("day09-crlf", "9 players; last marble is worth 25 points\n")
+                .unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part1_from_str(&input).unwrap(),
+            "9 players; last marble is worth 25 points => winning score: 32"
+        );
+    }
 }
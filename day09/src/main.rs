@@ -1,16 +1,16 @@
 use aoclib::{config::Config, website::get_input};
-use day09::{part1, part2};
+use day09::{part1_with_rules, part2_from_str, GameRules};
 
 use color_eyre::eyre::Result;
-use structopt::StructOpt;
 use std::path::PathBuf;
+use structopt::StructOpt;
 
 const YEAR: u32 = 2018;
 const DAY: u8 = 9;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day09 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +21,51 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// in part 1, marbles which are multiples of this score instead of being placed
+    #[structopt(long, default_value = "23")]
+    divisor: u32,
+
+    /// in part 1, how far counter-clockwise a scoring marble's partner is
+    #[structopt(long, default_value = "7")]
+    back_steps: usize,
+
+    /// in part 1, how far clockwise each marble is placed
+    #[structopt(long, default_value = "1")]
+    forward_steps: usize,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        let rules = GameRules {
+            divisor: args.divisor,
+            back_steps: args.back_steps,
+            forward_steps: args.forward_steps,
+        };
+        println!("{}", part1_with_rules(&input, rules)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        println!("{}", part2_from_str(&input)?);
     }
     Ok(())
 }
@@ -0,0 +1,311 @@
+//! Interchangeable storage for the circle of marbles.
+//!
+//! A game only ever places a marble a short way clockwise of the current one or removes a
+//! marble a short way counter-clockwise of it, so any structure which supports those two
+//! operations can drive a [`State`](crate::State).
+
+use crate::circle::{Circle, NodeId};
+use std::{collections::VecDeque, mem::size_of};
+
+/// How much space a circle reserves before the game begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preallocation {
+    /// Reserve nothing beyond the first two marbles; grow as the game goes.
+    None,
+    /// Reserve exactly as much as the game will need, given its last marble.
+    ///
+    /// Storage which holds only the marbles in the circle needs [`peak_circle_len`];
+    /// storage indexed by marble needs a slot for every marble.
+    Peak,
+    /// Reserve space for this many marbles. The circle still grows if it needs more.
+    Marbles(usize),
+}
+
+impl Default for Preallocation {
+    fn default() -> Self {
+        Preallocation::Peak
+    }
+}
+
+impl Preallocation {
+    /// The number of marbles to reserve space for, given the space `peak` the game needs.
+    fn marbles(self, peak: usize) -> usize {
+        match self {
+            Preallocation::None => 2,
+            Preallocation::Peak => peak,
+            Preallocation::Marbles(marbles) => marbles.max(2),
+        }
+    }
+}
+
+/// The greatest number of marbles in the circle at once, in a game ending with `last_marble`.
+///
+/// Every marble up to `last_marble` is placed, except that each multiple of 23 instead
+/// removes another marble, so the circle shrinks by one at each multiple of 23 and grows
+/// by one otherwise. It's largest just after the last marble, or just before it if that's
+/// a multiple of 23.
+pub fn peak_circle_len(last_marble: u32) -> usize {
+    let len_after = |marble: u32| marble as usize + 1 - 2 * (marble / 23) as usize;
+    let len = len_after(last_marble.max(1));
+    if last_marble > 1 && last_marble % 23 == 0 {
+        len.max(len_after(last_marble - 1))
+    } else {
+        len
+    }
+}
+
+/// The operations a game needs from its circle of marbles.
+pub trait MarbleStorage: Sized {
+    /// Create a circle containing marbles 0 and 1, with marble 1 current.
+    ///
+    /// No marble greater than `last_marble` will be placed. Space is reserved for all of them.
+    fn new(last_marble: u32) -> Self {
+        Self::with_preallocation(last_marble, Preallocation::default())
+    }
+
+    /// Create a circle containing marbles 0 and 1, with marble 1 current, reserving space
+    /// according to `preallocation`.
+    ///
+    /// No marble greater than `last_marble` will be placed.
+    fn with_preallocation(last_marble: u32, preallocation: Preallocation) -> Self;
+
+    /// Place `marble` between the marbles `forward_steps` and `forward_steps + 1` positions
+    /// clockwise of the current marble. It becomes the current marble.
+    ///
+    /// The puzzle always steps forward 1.
+    fn place(&mut self, marble: u32, forward_steps: usize);
+
+    /// Remove and return the marble `back_steps` positions counter-clockwise of the current
+    /// marble. The marble clockwise of it becomes the current marble.
+    ///
+    /// The puzzle always steps back 7. The circle must hold at least two marbles.
+    fn remove_scoring(&mut self, back_steps: usize) -> u32;
+
+    /// The current marble.
+    fn current(&self) -> u32;
+
+    /// The marbles in clockwise order, beginning with the smallest.
+    ///
+    /// That's marble 0, unless a variant game has removed it.
+    fn marbles(&self) -> Vec<u32>;
+
+    /// The number of bytes allocated on the heap.
+    fn memory_footprint(&self) -> usize;
+}
+
+/// Rotate marbles listed in clockwise order so that the smallest comes first.
+fn starting_at_smallest(mut marbles: Vec<u32>) -> Vec<u32> {
+    let smallest = marbles
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, &marble)| marble)
+        .map(|(idx, _)| idx)
+        .unwrap_or_default();
+    marbles.rotate_left(smallest);
+    marbles
+}
+
+/// The current marble is at the back, and clockwise is toward the front.
+impl MarbleStorage for VecDeque<u32> {
+    fn with_preallocation(last_marble: u32, preallocation: Preallocation) -> Self {
+        let capacity = preallocation.marbles(peak_circle_len(last_marble));
+        let mut circle = VecDeque::with_capacity(capacity);
+        circle.push_back(0);
+        circle.push_back(1);
+        circle
+    }
+
+    fn place(&mut self, marble: u32, forward_steps: usize) {
+        // rotating by the whole length changes nothing
+        self.rotate_right((forward_steps + 1) % self.len());
+        self.push_back(marble);
+    }
+
+    fn remove_scoring(&mut self, back_steps: usize) -> u32 {
+        self.rotate_left(back_steps % self.len());
+        self.pop_back().expect("circle is never empty")
+    }
+
+    fn current(&self) -> u32 {
+        *self.back().expect("circle is never empty")
+    }
+
+    fn marbles(&self) -> Vec<u32> {
+        starting_at_smallest(self.iter().rev().copied().collect())
+    }
+
+    fn memory_footprint(&self) -> usize {
+        self.capacity() * size_of::<u32>()
+    }
+}
+
+/// A [`Circle`] together with the id of the current marble.
+#[derive(Debug)]
+pub struct SlabStorage {
+    circle: Circle<u32>,
+    current: NodeId,
+}
+
+/// Removed marbles' slots are reused, so the slab never holds more than the circle's peak.
+impl MarbleStorage for SlabStorage {
+    fn with_preallocation(last_marble: u32, preallocation: Preallocation) -> Self {
+        let capacity = preallocation.marbles(peak_circle_len(last_marble));
+        let (mut circle, zero) = Circle::with_capacity(0, capacity);
+        let current = circle.insert_after(zero, 1);
+        SlabStorage { circle, current }
+    }
+
+    fn place(&mut self, marble: u32, forward_steps: usize) {
+        let before = self.circle.step(self.current, forward_steps as isize);
+        self.current = self.circle.insert_after(before, marble);
+    }
+
+    fn remove_scoring(&mut self, back_steps: usize) -> u32 {
+        let target = self.circle.step(self.current, -(back_steps as isize));
+        let (marble, clockwise) = self.circle.remove(target);
+        self.current = clockwise.expect("circle is never empty");
+        marble
+    }
+
+    fn current(&self) -> u32 {
+        self.circle[self.current]
+    }
+
+    fn marbles(&self) -> Vec<u32> {
+        starting_at_smallest(self.circle.iter_from(self.current).copied().collect())
+    }
+
+    fn memory_footprint(&self) -> usize {
+        self.circle.memory_footprint()
+    }
+}
+
+/// A doubly-linked circle stored as two flat arrays of links, indexed by marble.
+///
+/// Every marble is placed at most once, so each one can own a fixed slot. By default, the
+/// links are allocated up front for every marble up to `last_marble`, and nothing is
+/// allocated or freed while the game runs. Links are `u32`, half the size of a `usize` index.
+#[derive(Debug, Clone)]
+pub struct ArrayCircle {
+    next: Vec<u32>,
+    prev: Vec<u32>,
+    current: u32,
+}
+
+/// Slots are indexed by marble, so the game needs one for every marble up to the last,
+/// including those which are removed.
+impl MarbleStorage for ArrayCircle {
+    fn with_preallocation(last_marble: u32, preallocation: Preallocation) -> Self {
+        let slots = preallocation.marbles(last_marble.max(1) as usize + 1);
+        let mut next = vec![0; slots];
+        let mut prev = vec![0; slots];
+        next[0] = 1;
+        prev[0] = 1;
+        next[1] = 0;
+        prev[1] = 0;
+        ArrayCircle {
+            next,
+            prev,
+            current: 1,
+        }
+    }
+
+    fn place(&mut self, marble: u32, forward_steps: usize) {
+        if marble as usize >= self.next.len() {
+            // `resize` grows the capacity geometrically, so this rarely reallocates
+            self.next.resize(marble as usize + 1, 0);
+            self.prev.resize(marble as usize + 1, 0);
+        }
+        let mut before = self.current;
+        for _ in 0..forward_steps {
+            before = self.next[before as usize];
+        }
+        let after = self.next[before as usize];
+        self.next[before as usize] = marble;
+        self.prev[marble as usize] = before;
+        self.next[marble as usize] = after;
+        self.prev[after as usize] = marble;
+        self.current = marble;
+    }
+
+    fn remove_scoring(&mut self, back_steps: usize) -> u32 {
+        let mut target = self.current;
+        for _ in 0..back_steps {
+            target = self.prev[target as usize];
+        }
+        let before = self.prev[target as usize];
+        let after = self.next[target as usize];
+        self.next[before as usize] = after;
+        self.prev[after as usize] = before;
+        self.current = after;
+        target
+    }
+
+    fn current(&self) -> u32 {
+        self.current
+    }
+
+    fn marbles(&self) -> Vec<u32> {
+        let mut marbles = vec![self.current];
+        let mut marble = self.next[self.current as usize];
+        while marble != self.current {
+            marbles.push(marble);
+            marble = self.next[marble as usize];
+        }
+        starting_at_smallest(marbles)
+    }
+
+    fn memory_footprint(&self) -> usize {
+        (self.next.capacity() + self.prev.capacity()) * size_of::<u32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_circle_example() {
+        // the state of the circle after marble 22 and after marble 23 in the example
+        let mut circle = ArrayCircle::new(25);
+        for marble in 2..=22 {
+            circle.place(marble, 1);
+        }
+        assert_eq!(
+            circle.marbles(),
+            vec![0, 16, 8, 17, 4, 18, 9, 19, 2, 20, 10, 21, 5, 22, 11, 1, 12, 6, 13, 3, 14, 7, 15]
+        );
+        assert_eq!(circle.remove_scoring(7), 9);
+        assert_eq!(circle.current, 19);
+    }
+
+    #[test]
+    fn peak_lengths() {
+        // simulate the circle's length directly
+        let mut len = 2;
+        let mut peak = 2;
+        for marble in 2..=2_000 {
+            if marble % 23 == 0 {
+                len -= 1;
+            } else {
+                len += 1;
+            }
+            peak = peak.max(len);
+            assert_eq!(peak_circle_len(marble), peak, "last marble {}", marble);
+        }
+        assert_eq!(peak_circle_len(0), 2);
+        assert_eq!(peak_circle_len(1), 2);
+    }
+
+    #[test]
+    fn array_circle_grows() {
+        let mut circle = ArrayCircle::with_preallocation(25, Preallocation::None);
+        let mut reference = ArrayCircle::new(25);
+        for marble in 2..=22 {
+            circle.place(marble, 1);
+            reference.place(marble, 1);
+        }
+        assert_eq!(circle.marbles(), reference.marbles());
+        assert_eq!(circle.remove_scoring(7), reference.remove_scoring(7));
+    }
+}
@@ -0,0 +1,60 @@
+//! Compare sequential, parallel, and fused ticks over large sets of lights.
+
+use aoclib::geometry::Point;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use day10::{bounds, par_bounds, par_tick, tick, tick_and_bounds, Light};
+
+/// A deterministic field of `n` lights, scattered over a few thousand pixels.
+fn lights(n: usize) -> Vec<Light> {
+    let mut seed = 0x2018_u64;
+    let mut next = move |modulus: u64| {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        ((seed >> 33) % modulus) as i32
+    };
+    (0..n)
+        .map(|_| {
+            let position = Point::new(next(4000) - 2000, next(4000) - 2000);
+            let velocity = Point::new(next(7) - 3, next(7) - 3);
+            Light::new(position, velocity)
+        })
+        .collect()
+}
+
+fn bench_tick_then_bounds(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick_then_bounds");
+    group.sample_size(20);
+    for &n in &[100_000, 1_000_000, 4_000_000] {
+        let input = lights(n);
+        group.bench_with_input(BenchmarkId::new("sequential", n), &input, |b, input| {
+            b.iter_batched_ref(
+                || input.clone(),
+                |lights| {
+                    tick(lights);
+                    bounds(lights)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", n), &input, |b, input| {
+            b.iter_batched_ref(
+                || input.clone(),
+                |lights| {
+                    par_tick(lights);
+                    par_bounds(lights)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("fused", n), &input, |b, input| {
+            b.iter_batched_ref(
+                || input.clone(),
+                |lights| tick_and_bounds(lights),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_tick_then_bounds);
+criterion_main!(benches);
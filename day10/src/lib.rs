@@ -1,10 +1,9 @@
-use aoclib::{
-    geometry::{tile::Bool, Map, Point},
-    parse,
-};
+use aoclib::geometry::{tile::Bool, Map, Point};
 use lazy_static::lazy_static;
+use rayon::prelude::*;
 use regex::Regex;
-use std::{num::ParseIntError, path::Path, str::FromStr};
+use std::{num::ParseIntError, ops::Range, path::Path, str::FromStr};
+use util::parse_str;
 
 lazy_static! {
     static ref POINT_RE: Regex = Regex::new(r"<\s?(?P<x>-?\d+), \s?(?P<y>-?\d+)>").unwrap();
@@ -28,7 +27,7 @@ fn parse_point(s: &str) -> Result<Point, Error> {
 }
 
 #[derive(Clone, Copy, Debug)]
-struct Light {
+pub struct Light {
     position: Point,
     velocity: Point,
 }
@@ -53,48 +52,239 @@ impl FromStr for Light {
     }
 }
 
-/// Compute the `(min, max)` bounds enclosing the given points.
-fn bounds(points: &[Light]) -> (Point, Point) {
-    let mut min = Point::new(i32::MAX, i32::MAX);
-    let mut max = Point::new(i32::MIN, i32::MIN);
-    for point in points {
-        min.x = min.x.min(point.position.x);
-        min.y = min.y.min(point.position.y);
-        max.x = max.x.max(point.position.x);
-        max.y = max.y.max(point.position.y);
+impl Light {
+    pub fn new(position: Point, velocity: Point) -> Self {
+        Light { position, velocity }
+    }
+}
+
+/// Parse the input into independent light fields, separated by blank lines.
+///
+/// Each field is parsed as with [`parse_str`]: lines which fail to parse are reported and skipped.
+pub fn light_groups(input: &str) -> Vec<Vec<Light>> {
+    let mut groups = Vec::new();
+    let mut group = String::new();
+    for line in input.lines().chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            group.push_str(line);
+            group.push('\n');
+        } else if !group.is_empty() {
+            groups.push(parse_str(&group).collect::<Vec<Light>>());
+            group.clear();
+        }
     }
+    groups.retain(|group| !group.is_empty());
+    groups
+}
 
+/// The bounds enclosing no points, from which any others can be grown.
+fn empty_bounds() -> (Point, Point) {
+    (
+        Point::new(i32::MAX, i32::MAX),
+        Point::new(i32::MIN, i32::MIN),
+    )
+}
+
+/// Grow `bounds` to enclose `position`.
+fn extend_bounds((mut min, mut max): (Point, Point), position: Point) -> (Point, Point) {
+    min.x = min.x.min(position.x);
+    min.y = min.y.min(position.y);
+    max.x = max.x.max(position.x);
+    max.y = max.y.max(position.y);
     (min, max)
 }
 
+/// Combine two bounds into the bounds enclosing both.
+fn merge_bounds(a: (Point, Point), (min, max): (Point, Point)) -> (Point, Point) {
+    extend_bounds(extend_bounds(a, min), max)
+}
+
+/// Compute the `(min, max)` bounds enclosing the given points.
+///
+/// With no points, `min` is greater than `max`.
+pub fn bounds(points: &[Light]) -> (Point, Point) {
+    points.iter().fold(empty_bounds(), |bounds, light| {
+        extend_bounds(bounds, light.position)
+    })
+}
+
+/// Compute the `(min, max)` bounds enclosing the given points, in parallel.
+pub fn par_bounds(points: &[Light]) -> (Point, Point) {
+    points
+        .par_iter()
+        .fold(empty_bounds, |bounds, light| {
+            extend_bounds(bounds, light.position)
+        })
+        .reduce(empty_bounds, merge_bounds)
+}
+
+/// Compute the `(width, height)` spanned by the given bounds.
+///
+/// This is computed in `i64`: the span of two `i32` coordinates can exceed `i32::MAX`.
+fn span((min, max): (Point, Point)) -> (u64, u64) {
+    let width = (max.x as i64 - min.x as i64) as u64;
+    let height = (max.y as i64 - min.y as i64) as u64;
+    (width, height)
+}
+
 /// Compute the bounding area of the given points.
+#[cfg(test)]
 fn area(points: &[Light]) -> u64 {
-    let (min, max) = bounds(points);
-    debug_assert!(max.x >= min.x);
-    debug_assert!(max.y >= min.y);
-    let width = (max.x - min.x) as u64;
-    let height = (max.y - min.y) as u64;
-    width * height
+    let (width, height) = span(bounds(points));
+    width.saturating_mul(height)
 }
 
-// advance the state of the lights
-fn tick(lights: &mut [Light]) {
+/// Compute the bounding area of the lights at time `t`, without constructing their states.
+///
+/// Positions are computed in `i64`, so they can't overflow for any `t` we search. Areas
+/// too large for a `u64` saturate.
+fn area_at(lights: &[Light], t: i32) -> u64 {
+    let (mut min_x, mut min_y) = (i64::MAX, i64::MAX);
+    let (mut max_x, mut max_y) = (i64::MIN, i64::MIN);
+    for light in lights {
+        let x = light.position.x as i64 + light.velocity.x as i64 * t as i64;
+        let y = light.position.y as i64 + light.velocity.y as i64 * t as i64;
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    let width = max_x.saturating_sub(min_x) as u64;
+    let height = max_y.saturating_sub(min_y) as u64;
+    width.saturating_mul(height)
+}
+
+/// Advance the state of the lights by one second.
+pub fn tick(lights: &mut [Light]) {
     for light in lights.iter_mut() {
         light.position += light.velocity;
     }
 }
 
+/// Advance the state of the lights by one second, in parallel.
+pub fn par_tick(lights: &mut [Light]) {
+    lights
+        .par_iter_mut()
+        .for_each(|light| light.position += light.velocity);
+}
+
+/// Advance the state of the lights by one second, in parallel, and return their new bounds.
+///
+/// This is equivalent to [`par_tick`] followed by [`par_bounds`], but it visits each light
+/// only once, which matters when there are too many lights to fit in cache.
+pub fn tick_and_bounds(lights: &mut [Light]) -> (Point, Point) {
+    lights
+        .par_iter_mut()
+        .fold(empty_bounds, |bounds, light| {
+            light.position += light.velocity;
+            extend_bounds(bounds, light.position)
+        })
+        .reduce(empty_bounds, merge_bounds)
+}
+
+/// Compute the state of the lights at time `t`, relative to their initial positions.
+///
+/// This takes time proportional to the number of lights, regardless of `t`.
+///
+/// Fails with [`Error::Overflow`] if any light's position at `t` doesn't fit in an `i32`.
+pub fn state_at(lights: &[Light], t: i32) -> Result<Vec<Light>, Error> {
+    lights
+        .iter()
+        .map(|&light| {
+            let position = |p: i32, v: i32| {
+                v.checked_mul(t)
+                    .and_then(|dp| p.checked_add(dp))
+                    .ok_or(Error::Overflow { t })
+            };
+            Ok(Light {
+                position: Point::new(
+                    position(light.position.x, light.velocity.x)?,
+                    position(light.position.y, light.velocity.y)?,
+                ),
+                ..light
+            })
+        })
+        .collect()
+}
+
+/// Compute the area of the bounding box of the lights at each time in `t_range`.
+pub fn bounds_over_time(lights: &[Light], t_range: Range<i32>) -> Vec<(i32, u64)> {
+    t_range.map(|t| (t, area_at(lights, t))).collect()
+}
+
+/// Render the bounding-box area at each time in `t_range` as CSV, with a header row.
+///
+/// Each light field in the input is numbered from 0 in the `group` column.
+pub fn bounds_csv_from_str(input: &str, t_range: Range<i32>) -> Result<String, Error> {
+    let groups = light_groups(input);
+    if groups.is_empty() {
+        return Err(Error::NoSolution);
+    }
+    let mut lines = vec!["group,t,area".to_string()];
+    for (group, lights) in groups.iter().enumerate() {
+        for (t, area) in bounds_over_time(lights, t_range.clone()) {
+            lines.push(format!("{},{},{}", group, t, area));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Upper bound on the time searched by [`find_min_area_analytic`].
+///
+/// Past this, positions of plausibly-sized inputs could overflow.
+const MAX_TIME: i32 = 1 << 20;
+
+/// Find the time at which the bounding area of the lights is minimized, without simulating.
+///
+/// The bounding area is unimodal in `t`: it shrinks while the lights converge, then grows.
+/// We double an upper bound until the area starts growing, then ternary-search below it.
+/// Each probe computes positions directly with [`area_at`], so this takes `O(log T)` probes
+/// instead of `O(T)` ticks.
+///
+/// Returns the lights at that time, and the time.
+fn find_min_area_analytic(lights: &[Light]) -> Result<(Vec<Light>, usize), Error> {
+    let area = |t| area_at(lights, t);
+
+    let mut hi = 1;
+    while hi < MAX_TIME && area(hi * 2) < area(hi) {
+        hi *= 2;
+    }
+    let mut lo = 0;
+    let mut hi = (hi * 2).min(MAX_TIME);
+
+    while hi - lo > 2 {
+        let m1 = lo + (hi - lo) / 3;
+        let m2 = hi - (hi - lo) / 3;
+        if area(m1) < area(m2) {
+            hi = m2 - 1;
+        } else {
+            lo = m1 + 1;
+        }
+    }
+
+    let t = (lo..=hi)
+        .min_by_key(|&t| area(t))
+        .expect("range is never empty");
+    Ok((state_at(lights, t)?, t as usize))
+}
+
+/// Find the time at which the bounding area is minimized by ticking the simulation forward.
+///
+/// This is the reference against which [`find_min_area_analytic`] is tested.
+#[cfg(test)]
 fn find_min_area(mut lights: Vec<Light>) -> (Vec<Light>, usize) {
+    let area_of = |bounds| {
+        let (width, height) = span(bounds);
+        width.saturating_mul(height)
+    };
     let mut count = 0;
 
     let mut prev_area = area(&lights);
-    tick(&mut lights);
-    let mut current_area = area(&lights);
+    let mut current_area = area_of(tick_and_bounds(&mut lights));
 
     while current_area <= prev_area {
         prev_area = current_area;
-        tick(&mut lights);
-        current_area = area(&lights);
+        current_area = area_of(tick_and_bounds(&mut lights));
         count += 1;
     }
 
@@ -105,9 +295,20 @@ fn find_min_area(mut lights: Vec<Light>) -> (Vec<Light>, usize) {
     (lights, count)
 }
 
-fn to_map(mut lights: Vec<Light>) -> Map<Bool> {
+/// The largest message, in pixels, which we're willing to render.
+///
+/// Real messages are a few hundred pixels. Lights which never converge can leave a minimum
+/// bounding box far too large to allocate.
+const MAX_MESSAGE_AREA: u64 = 1 << 20;
+
+fn to_map(mut lights: Vec<Light>) -> Result<Map<Bool>, Error> {
     // adjust the lights such that the minimum corner is at `(0, 0)`
     let (min, max) = bounds(&lights);
+    let (width, height) = span((min, max));
+    let (width, height) = (width + 1, height + 1);
+    if width.saturating_mul(height) > MAX_MESSAGE_AREA {
+        return Err(Error::MessageTooLarge { width, height });
+    }
     for light in lights.iter_mut() {
         light.position -= min;
     }
@@ -128,24 +329,46 @@ fn to_map(mut lights: Vec<Light>) -> Map<Bool> {
     // map's origin is in bottom left, but AoC coords assume origin is in top left
     map = map.flip_vertical();
 
-    map
+    Ok(map)
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let lights: Vec<Light> = parse(input)?.collect();
-    let (min_area_lights, _) = find_min_area(lights);
-    let map = to_map(min_area_lights);
-    println!("{}", map);
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let groups = light_groups(input);
+    if groups.is_empty() {
+        return Err(Error::NoSolution);
+    }
+    let mut messages = Vec::with_capacity(groups.len());
+    for lights in groups {
+        let (min_area_lights, _) = find_min_area_analytic(&lights)?;
+        let message = to_map(min_area_lights)?.to_string();
+        messages.push(message.trim_end().to_string());
+    }
+    Ok(messages.join("\n\n"))
+}
+
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let lights: Vec<Light> = parse(input)?.collect();
-    let (_, time_to_answer) = find_min_area(lights);
-    println!("time to answer: {}", time_to_answer);
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let groups = light_groups(input);
+    if groups.is_empty() {
+        return Err(Error::NoSolution);
+    }
+    let mut lines = Vec::with_capacity(groups.len());
+    for lights in groups {
+        let (_, time_to_answer) = find_min_area_analytic(&lights)?;
+        lines.push(format!("time to answer: {}", time_to_answer));
+    }
+    Ok(lines.join("\n"))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -156,4 +379,222 @@ pub enum Error {
     ParseIntError(#[from] ParseIntError),
     #[error("No solution found")]
     NoSolution,
+    #[error("a light's position overflows at t={t}")]
+    Overflow { t: i32 },
+    #[error("message is too large to render: {width}x{height}")]
+    MessageTooLarge { width: u64, height: u64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+position=< 9,  1> velocity=< 0,  2>
+position=< 7,  0> velocity=<-1,  0>
+position=< 3, -2> velocity=<-1,  1>
+position=< 6, 10> velocity=<-2, -1>
+position=< 2, -4> velocity=< 2,  2>
+position=<-6, 10> velocity=< 2, -2>
+position=< 1,  8> velocity=< 1, -1>
+position=< 1,  7> velocity=< 1,  0>
+position=<-3, 11> velocity=< 1, -2>
+position=< 7,  6> velocity=<-1, -1>
+position=<-2,  3> velocity=< 1,  0>
+position=<-4,  3> velocity=< 2,  0>
+position=<10, -3> velocity=<-1,  1>
+position=< 5, 11> velocity=< 1, -2>
+position=< 4,  7> velocity=< 0, -1>
+position=< 8, -2> velocity=< 0,  1>
+position=<15,  0> velocity=<-2,  0>
+position=< 1,  6> velocity=< 1,  0>
+position=< 8,  9> velocity=< 0, -1>
+position=< 3,  3> velocity=<-1,  1>
+position=< 0,  5> velocity=< 0, -1>
+position=<-2,  2> velocity=< 2,  0>
+position=< 5, -2> velocity=< 1,  2>
+position=< 1,  4> velocity=< 2,  1>
+position=<-2,  7> velocity=< 2, -2>
+position=< 3,  6> velocity=<-1, -1>
+position=< 5,  0> velocity=< 1,  0>
+position=<-6,  0> velocity=< 2,  0>
+position=< 5,  9> velocity=< 1, -2>
+position=<14,  7> velocity=<-2,  0>
+position=<-3,  6> velocity=< 2, -1>
+";
+
+    fn lights() -> Vec<Light> {
+        parse_str(EXAMPLE).collect()
+    }
+
+    #[test]
+    fn message_snapshot() {
+        insta::assert_snapshot!(part1_from_str(EXAMPLE).unwrap(), @r###"
+        #...#..###
+        #...#...#.
+        #...#...#.
+        #####...#.
+        #...#...#.
+        #...#...#.
+        #...#...#.
+        #...#..###
+        "###);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2_from_str(EXAMPLE).unwrap(), "time to answer: 3");
+    }
+
+    #[test]
+    fn state_at_matches_ticking() {
+        let initial = lights();
+        let mut ticked = initial.clone();
+        for t in 0..5 {
+            let direct = state_at(&initial, t).unwrap();
+            assert!(direct
+                .iter()
+                .zip(&ticked)
+                .all(|(a, b)| a.position == b.position));
+            tick(&mut ticked);
+        }
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        // enough lights that rayon actually splits the work
+        let many: Vec<Light> = state_at(&lights(), -50)
+            .unwrap()
+            .into_iter()
+            .cycle()
+            .take(100_000)
+            .enumerate()
+            .map(|(idx, light)| {
+                let offset = idx as i32 % 1000;
+                Light::new(light.position + Point::new(offset, -offset), light.velocity)
+            })
+            .collect();
+        let mut sequential = many.clone();
+        let mut parallel = many.clone();
+        let mut fused = many;
+        for _ in 0..3 {
+            tick(&mut sequential);
+            par_tick(&mut parallel);
+            let fused_bounds = tick_and_bounds(&mut fused);
+            assert_eq!(par_bounds(&parallel), bounds(&sequential));
+            assert_eq!(fused_bounds, bounds(&sequential));
+        }
+        assert!(sequential
+            .iter()
+            .zip(&parallel)
+            .zip(&fused)
+            .all(|((a, b), c)| a.position == b.position && b.position == c.position));
+
+        assert_eq!(par_bounds(&[]), bounds(&[]));
+        assert_eq!(tick_and_bounds(&mut []), empty_bounds());
+    }
+
+    #[test]
+    fn analytic_matches_simulation() {
+        let (simulated, simulated_t) = find_min_area(lights());
+        let (analytic, analytic_t) = find_min_area_analytic(&lights()).unwrap();
+        assert_eq!(simulated_t, 3);
+        assert_eq!(analytic_t, simulated_t);
+        assert!(simulated
+            .iter()
+            .zip(&analytic)
+            .all(|(a, b)| a.position == b.position));
+    }
+
+    #[test]
+    fn analytic_distant_convergence() {
+        // the example, rewound by 10,000 seconds
+        let rewound = state_at(&lights(), -10_000).unwrap();
+        let (_, t) = find_min_area_analytic(&rewound).unwrap();
+        assert_eq!(t, 10_003);
+    }
+
+    #[test]
+    fn bounds_unimodal() {
+        let areas = bounds_over_time(&lights(), 0..7);
+        assert_eq!(areas.len(), 7);
+        let (min_t, _) = *areas.iter().min_by_key(|(_, area)| *area).unwrap();
+        assert_eq!(min_t, 3);
+        assert!(areas[..=3].windows(2).all(|w| w[0].1 > w[1].1));
+        assert!(areas[3..].windows(2).all(|w| w[0].1 < w[1].1));
+    }
+
+    #[test]
+    fn bounds_csv() {
+        let csv = bounds_csv_from_str(EXAMPLE, 3..5).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("group,t,area"));
+        // at t=3 the message spans 10 columns and 8 rows
+        assert_eq!(lines.next(), Some("0,3,63"));
+        assert_eq!(lines.count(), 1);
+    }
+
+    #[test]
+    fn extreme_positions() {
+        // these diverge immediately, so the message is at t=0, and far too large
+        let input = "position=<-2000000000, 0> velocity=<-1000, 0>
+position=< 2000000000, 1> velocity=< 1000, 0>";
+        assert_eq!(part2_from_str(input).unwrap(), "time to answer: 0");
+        assert!(matches!(
+            part1_from_str(input),
+            Err(Error::MessageTooLarge {
+                width: 4_000_000_001,
+                height: 2
+            })
+        ));
+
+        let lights: Vec<Light> = parse_str(input).collect();
+        assert!(matches!(
+            state_at(&lights, 1_000_000),
+            Err(Error::Overflow { t: 1_000_000 })
+        ));
+        assert_eq!(area_at(&lights, 1_000_000), 6_000_000_000);
+    }
+
+    /// The example followed by a copy rewound by 5 seconds, as a second light field.
+    fn two_groups() -> String {
+        let mut input = EXAMPLE.to_string();
+        input.push('\n');
+        for light in state_at(&lights(), -5).unwrap() {
+            input.push_str(&format!(
+                "position=<{}, {}> velocity=<{}, {}>\n",
+                light.position.x, light.position.y, light.velocity.x, light.velocity.y
+            ));
+        }
+        input
+    }
+
+    #[test]
+    fn groups_split_on_blank_lines() {
+        let groups = light_groups(&two_groups());
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.len() == lights().len()));
+        assert_eq!(light_groups(EXAMPLE).len(), 1);
+        assert!(light_groups("\n  \n").is_empty());
+    }
+
+    #[test]
+    fn groups_solved_independently() {
+        assert_eq!(
+            part2_from_str(&two_groups()).unwrap(),
+            "time to answer: 3\ntime to answer: 8"
+        );
+
+        let single = part1_from_str(EXAMPLE).unwrap();
+        let both = part1_from_str(&two_groups()).unwrap();
+        assert_eq!(both, format!("{}\n\n{}", single, single));
+        assert!(matches!(part1_from_str(""), Err(Error::NoSolution)));
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day10-crlf", EXAMPLE).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(part2_from_str(&input).unwrap(), "time to answer: 3");
+    }
 }
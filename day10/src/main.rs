@@ -1,16 +1,16 @@
 use aoclib::{config::Config, website::get_input};
-use day10::{part1, part2};
+use day10::{bounds_csv_from_str, part1_from_str, part2_from_str};
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use structopt::StructOpt;
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf};
 
 const YEAR: u32 = 2018;
 const DAY: u8 = 10;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day10 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +21,50 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// instead of solving, dump the bounding-box area at each time in `START..END` as CSV
+    #[structopt(long, value_name = "START..END", parse(try_from_str = parse_range))]
+    bounds_csv: Option<Range<i32>>,
+}
+
+fn parse_range(s: &str) -> Result<Range<i32>> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| eyre!("expected a range like `0..20000`"))?;
+    Ok(start.parse()?..end.parse()?)
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
+
+    if let Some(ref t_range) = args.bounds_csv {
+        println!("{}", bounds_csv_from_str(&input, t_range.clone())?);
+        return Ok(());
+    }
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_from_str(&input)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        println!("{}", part2_from_str(&input)?);
     }
     Ok(())
 }
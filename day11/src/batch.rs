@@ -0,0 +1,114 @@
+//! Vectorized computations over whole grids, using `ndarray`.
+//!
+//! Arrays are indexed `[y, x]`, or `[serial, y, x]` when covering several grids.
+
+use crate::{FuelGrid, Map, EDGE_SIZE};
+use aoclib::geometry::Point;
+use ndarray::{s, Array, Array2, Array3, Axis};
+
+/// Power levels of every cell, for each serial.
+fn power_levels(serials: &[i32]) -> Array3<i32> {
+    // the puzzle's coordinates are 1-indexed
+    let coords = Array::from_iter(1..=EDGE_SIZE as i32);
+    let rack_id = (&coords + 10)
+        .into_shape((1, 1, EDGE_SIZE))
+        .expect("shape preserves length");
+    let y = coords
+        .into_shape((1, EDGE_SIZE, 1))
+        .expect("shape preserves length");
+    let serials = Array::from(serials.to_vec())
+        .into_shape((serials.len(), 1, 1))
+        .expect("shape preserves length");
+
+    let mut power = &(&(&rack_id * &y) + &serials) * &rack_id;
+    power.mapv_inplace(|power| power / 100 % 10 - 5);
+    power
+}
+
+pub(crate) fn fuel_grids(serials: &[i32]) -> Vec<FuelGrid> {
+    let levels = power_levels(serials);
+    serials
+        .iter()
+        .zip(levels.outer_iter())
+        .map(|(&serial, levels)| {
+            FuelGrid::with_map(
+                serial,
+                Map::procedural(EDGE_SIZE, EDGE_SIZE, |cell| {
+                    levels[[cell.y as usize, cell.x as usize]]
+                }),
+            )
+        })
+        .collect()
+}
+
+/// A summed-area table of the map, with a border of zeros on its low edges.
+///
+/// `table[[y, x]]` is the total power of every cell whose coordinates are below `(x, y)`.
+pub(crate) fn summed_area_table(map: &Map) -> Array2<i32> {
+    let mut table = Array2::zeros((EDGE_SIZE + 1, EDGE_SIZE + 1));
+    table
+        .slice_mut(s![1.., 1..])
+        .assign(&Array2::from_shape_fn((EDGE_SIZE, EDGE_SIZE), |(y, x)| {
+            map[(x, y)]
+        }));
+    table.accumulate_axis_inplace(Axis(0), |&prev, current| *current += prev);
+    table.accumulate_axis_inplace(Axis(1), |&prev, current| *current += prev);
+    table
+}
+
+/// The total power of every square of the given edge size, as `(origin, edge_size, total_power)`.
+pub(crate) fn square_powers(table: &Array2<i32>, edge_size: usize) -> Vec<(Point, usize, i32)> {
+    let n = EDGE_SIZE + 1 - edge_size;
+    let powers = &(&(&table.slice(s![edge_size.., edge_size..])
+        - &table.slice(s![..n, edge_size..]))
+        - &table.slice(s![edge_size.., ..n]))
+        + &table.slice(s![..n, ..n]);
+    powers
+        .iter()
+        .enumerate()
+        .map(|(idx, &total_power)| {
+            let origin = Point::new((idx % n) as i32, (idx / n) as i32);
+            (origin, edge_size, total_power)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn batch_matches_scalar() {
+        let serials = [18, 42, 7_347];
+        for (batched, serial) in FuelGrid::batch(&serials).into_iter().zip(&serials) {
+            let scalar = FuelGrid::new(*serial);
+            assert_eq!(batched.serial, *serial);
+            assert!(batched.iter().eq(scalar.iter()), "serial {}", serial);
+        }
+    }
+
+    #[test]
+    fn top_k_squares_backends_agree() {
+        let grid = FuelGrid::new(18);
+        for sizes in [3..=3, 1..=5, 14..=18].iter() {
+            assert_eq!(
+                grid.top_k_squares_batch(5, sizes.clone()),
+                grid.top_k_squares_incremental(5, sizes.clone()),
+            );
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn square_powers_match_reference(serial in 0..10_000, edge_size in 1..=EDGE_SIZE, nth in any::<prop::sample::Index>()) {
+            let grid = FuelGrid::new(serial);
+            let table = summed_area_table(&grid);
+            let positions = EDGE_SIZE - edge_size + 1;
+            let (origin, _, total_power) = square_powers(&table, edge_size)[nth.index(positions * positions)];
+            prop_assert_eq!(total_power, grid.reference_power(origin, edge_size));
+        }
+    }
+}
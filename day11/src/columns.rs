@@ -0,0 +1,42 @@
+//! Cumulative power down each column of a grid, computed one column at a time.
+
+use crate::EDGE_SIZE;
+use once_cell::sync::OnceCell;
+
+pub(crate) struct ColumnPrefixes {
+    columns: Vec<OnceCell<Vec<i32>>>,
+}
+
+impl ColumnPrefixes {
+    pub fn new() -> Self {
+        ColumnPrefixes {
+            columns: (0..EDGE_SIZE).map(|_| OnceCell::new()).collect(),
+        }
+    }
+
+    /// The prefix sums of column `x`: element `y` is the total power of the cells in the
+    /// column whose y coordinate is less than `y`, so there are `EDGE_SIZE + 1` of them.
+    ///
+    /// On first use, the column is computed from `power_at(y)`.
+    pub fn column(&self, x: usize, power_at: impl Fn(usize) -> i32) -> &[i32] {
+        self.columns[x].get_or_init(|| {
+            let mut prefix = Vec::with_capacity(EDGE_SIZE + 1);
+            let mut total = 0;
+            prefix.push(total);
+            for y in 0..EDGE_SIZE {
+                total += power_at(y);
+                prefix.push(total);
+            }
+            prefix
+        })
+    }
+
+    /// The number of columns computed so far.
+    #[cfg(test)]
+    pub fn computed(&self) -> usize {
+        self.columns
+            .iter()
+            .filter(|column| column.get().is_some())
+            .count()
+    }
+}
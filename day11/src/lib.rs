@@ -1,15 +1,22 @@
-use aoclib::{
-    geometry::{Direction, Point},
-    parse,
-};
+#[cfg(feature = "ndarray")]
+mod batch;
+mod columns;
+
+use aoclib::geometry::{Direction, Point};
+use columns::ColumnPrefixes;
+use once_cell::sync::OnceCell;
 use rayon::prelude::*;
 use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    convert::TryFrom,
     iter,
     num::ParseIntError,
-    ops::{Deref, Index},
+    ops::{Deref, Index, RangeInclusive},
     path::Path,
     str::FromStr,
 };
+use util::parse_str;
 
 const EDGE_SIZE: usize = 300;
 
@@ -19,9 +26,7 @@ fn power_level(serial: i32, cell: Point) -> i32 {
     debug_assert!(cell.x < EDGE_SIZE as i32);
     debug_assert!(cell.y < EDGE_SIZE as i32);
 
-    // map initialization is 0-indexed, but the coordinate system
-    // assumed by the problem statement is 1-indexed
-    let cell = Point::new(cell.x + 1, cell.y + 1);
+    let cell = FuelGrid::puzzle_coordinates(cell);
 
     let rack_id = cell.x + 10;
     let mut power = rack_id * cell.y;
@@ -33,9 +38,20 @@ fn power_level(serial: i32, cell: Point) -> i32 {
     power
 }
 
-struct FuelGrid {
+/// Whether `cell`, in map coordinates, lies on the grid.
+fn on_grid(cell: Point) -> bool {
+    (0..EDGE_SIZE as i32).contains(&cell.x) && (0..EDGE_SIZE as i32).contains(&cell.y)
+}
+
+/// The fuel grid for a single serial number.
+///
+/// Power levels are computed lazily. Queries of single cells and of regions compute only
+/// what they need; anything which needs the whole map, such as indexing or searching for
+/// the most powerful squares, computes it all at once on first use.
+pub struct FuelGrid {
     serial: i32,
-    map: Map,
+    map: OnceCell<Map>,
+    columns: ColumnPrefixes,
 }
 
 impl FromStr for FuelGrid {
@@ -51,7 +67,7 @@ impl Deref for FuelGrid {
     type Target = Map;
 
     fn deref(&self) -> &Self::Target {
-        &self.map
+        self.map()
     }
 }
 
@@ -62,15 +78,101 @@ where
     type Output = <Map as Index<I>>::Output;
 
     fn index(&self, index: I) -> &Self::Output {
-        self.map.index(index)
+        self.map().index(index)
     }
 }
 
 impl FuelGrid {
-    fn new(serial: i32) -> Self {
+    pub fn new(serial: i32) -> Self {
         FuelGrid {
             serial,
-            map: Map::procedural(EDGE_SIZE, EDGE_SIZE, |cell| power_level(serial, cell)),
+            map: OnceCell::new(),
+            columns: ColumnPrefixes::new(),
+        }
+    }
+
+    /// Construct a grid whose map has already been computed.
+    #[cfg(feature = "ndarray")]
+    fn with_map(serial: i32, map: Map) -> Self {
+        let grid = FuelGrid::new(serial);
+        let _ = grid.map.set(map);
+        grid
+    }
+
+    /// Convert a point in map coordinates to the puzzle's coordinates.
+    ///
+    /// Map coordinates are 0-indexed, with y increasing upward as the map is displayed. The
+    /// puzzle's coordinates are 1-indexed, with y increasing downward. Both measure y from the
+    /// same edge of the grid, so only the indexing differs: the top-left cell of the puzzle,
+    /// `1,1`, is `(0, 0)` on the map, which displays it at the bottom left.
+    pub fn puzzle_coordinates(map_point: Point) -> Point {
+        map_point + Point::new(1, 1)
+    }
+
+    /// Convert a point in the puzzle's coordinates to map coordinates.
+    ///
+    /// This is the inverse of [`puzzle_coordinates`](Self::puzzle_coordinates).
+    pub fn map_coordinates(puzzle_point: Point) -> Point {
+        puzzle_point - Point::new(1, 1)
+    }
+
+    /// The power levels of every cell, computing them if they haven't been already.
+    fn map(&self) -> &Map {
+        self.map.get_or_init(|| {
+            Map::procedural(EDGE_SIZE, EDGE_SIZE, |cell| power_level(self.serial, cell))
+        })
+    }
+
+    /// The power level of the cell at `point`, in map coordinates, or `None` if it's off
+    /// the grid.
+    ///
+    /// This never computes the whole map.
+    pub fn power_at(&self, point: Point) -> Option<i32> {
+        if !on_grid(point) {
+            return None;
+        }
+        Some(match self.map.get() {
+            Some(map) => map[point],
+            None => power_level(self.serial, point),
+        })
+    }
+
+    /// Total power of the `width` by `height` rectangle whose lowest corner is at `origin`,
+    /// in map coordinates, or `None` if the rectangle doesn't lie entirely on the grid.
+    ///
+    /// This computes only the columns the rectangle covers, each once: after that, any
+    /// query over the same columns takes time proportional to `width`.
+    pub fn region_power(&self, origin: Point, width: usize, height: usize) -> Option<i32> {
+        let x = usize::try_from(origin.x).ok()?;
+        let y = usize::try_from(origin.y).ok()?;
+        if x + width > EDGE_SIZE || y + height > EDGE_SIZE {
+            return None;
+        }
+        Some(
+            (x..x + width)
+                .map(|x| {
+                    let column = self.columns.column(x, |y| {
+                        self.power_at(Point::new(x as i32, y as i32))
+                            .expect("column is on the grid")
+                    });
+                    column[y + height] - column[y]
+                })
+                .sum(),
+        )
+    }
+
+    /// Compute the grids for many serials at once.
+    ///
+    /// With the `ndarray` feature, every power level of every grid is computed in a single
+    /// vectorized pass; otherwise this is equivalent to calling [`new`](Self::new) repeatedly.
+    pub fn batch(serials: &[i32]) -> Vec<Self> {
+        #[cfg(feature = "ndarray")]
+        {
+            batch::fuel_grids(serials)
+        }
+        #[cfg(not(feature = "ndarray"))]
+        {
+            serials.iter().copied().map(FuelGrid::new).collect()
         }
     }
 
@@ -126,12 +228,137 @@ impl FuelGrid {
             .map(|cell| cell.expect("known to be Some from check above"))
     }
 
-    /// Iterate over all fuel cells of all edge sizes.
-    fn fuel_cells_all_sizes(&self) -> impl ParallelIterator<Item = FuelCell> {
-        (1..=EDGE_SIZE)
+    /// Find the `k` most powerful squares whose edge sizes are in `sizes`.
+    ///
+    /// Returns `(origin, edge_size, total_power)`, most powerful first. Origins are in map
+    /// coordinates; see [`puzzle_coordinates`](Self::puzzle_coordinates).
+    ///
+    /// Ties are broken in favor of smaller squares, then by lower origin.
+    ///
+    /// With the `ndarray` feature, squares are summed from a summed-area table; otherwise
+    /// each square's power is updated incrementally from its neighbor's.
+    pub fn top_k_squares(
+        &self,
+        k: usize,
+        sizes: RangeInclusive<usize>,
+    ) -> Vec<(Point, usize, i32)> {
+        #[cfg(feature = "ndarray")]
+        {
+            self.top_k_squares_batch(k, sizes)
+        }
+        #[cfg(not(feature = "ndarray"))]
+        {
+            self.top_k_squares_incremental(k, sizes)
+        }
+    }
+
+    #[cfg(any(test, not(feature = "ndarray")))]
+    fn top_k_squares_incremental(
+        &self,
+        k: usize,
+        sizes: RangeInclusive<usize>,
+    ) -> Vec<(Point, usize, i32)> {
+        let squares = clamp_sizes(sizes)
+            .into_par_iter()
+            .flat_map_iter(move |edge_size| {
+                self.fuel_cells(edge_size)
+                    .map(|cell| (cell.origin, cell.edge_size, cell.total_power))
+            });
+        top_k(k, squares)
+    }
+
+    #[cfg(feature = "ndarray")]
+    fn top_k_squares_batch(
+        &self,
+        k: usize,
+        sizes: RangeInclusive<usize>,
+    ) -> Vec<(Point, usize, i32)> {
+        let table = batch::summed_area_table(self.map());
+        let squares = clamp_sizes(sizes)
             .into_par_iter()
-            .flat_map_iter(move |edge_size| self.fuel_cells(edge_size))
+            .flat_map_iter(|edge_size| batch::square_powers(&table, edge_size));
+        top_k(k, squares)
+    }
+
+    /// Total power of the square at `origin`, computed by direct summation.
+    ///
+    /// This is `O(edge_size^2)`, so it's far too slow for solving the puzzle; it exists as
+    /// a reference against which to check the incremental computations.
+    #[cfg(any(test, feature = "reference"))]
+    pub fn reference_power(&self, origin: Point, edge_size: usize) -> i32 {
+        let mut total_power = 0;
+        for dy in 0..edge_size as i32 {
+            for dx in 0..edge_size as i32 {
+                total_power += power_level(self.serial, origin + Point::new(dx, dy));
+            }
+        }
+        total_power
     }
+
+    /// The most powerful square of the given edge size, computed by direct summation.
+    ///
+    /// Returns `(origin, total_power)` in map coordinates. Ties are broken by lower origin.
+    #[cfg(any(test, feature = "reference"))]
+    pub fn reference_max_square(&self, edge_size: usize) -> Option<(Point, i32)> {
+        let last = (EDGE_SIZE as i32) - edge_size as i32;
+        (0..=last)
+            .flat_map(|y| (0..=last).map(move |x| Point::new(x, y)))
+            .map(|origin| (self.reference_power(origin, edge_size), Reverse(origin)))
+            .max()
+            .map(|(total_power, Reverse(origin))| (origin, total_power))
+    }
+}
+
+/// Restrict a range of edge sizes to those which fit on the grid.
+fn clamp_sizes(sizes: RangeInclusive<usize>) -> RangeInclusive<usize> {
+    (*sizes.start()).max(1)..=(*sizes.end()).min(EDGE_SIZE)
+}
+
+/// Select the `k` most powerful of `squares`, given as `(origin, edge_size, total_power)`.
+///
+/// Ties are broken in favor of smaller squares, then by lower origin.
+fn top_k(
+    k: usize,
+    squares: impl ParallelIterator<Item = (Point, usize, i32)>,
+) -> Vec<(Point, usize, i32)> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // a min-heap of the best squares seen so far, so the worst of them is always on top
+    let push_bounded = move |heap: &mut BinaryHeap<Reverse<_>>, key| {
+        heap.push(Reverse(key));
+        if heap.len() > k {
+            heap.pop();
+        }
+    };
+
+    let best = squares
+        .fold(
+            BinaryHeap::new,
+            move |mut heap, (origin, edge_size, total_power)| {
+                push_bounded(
+                    &mut heap,
+                    (total_power, Reverse(edge_size), Reverse(origin)),
+                );
+                heap
+            },
+        )
+        .reduce(BinaryHeap::new, move |mut heap, other| {
+            for Reverse(key) in other {
+                push_bounded(&mut heap, key);
+            }
+            heap
+        });
+
+    best.into_sorted_vec()
+        .into_iter()
+        .map(
+            |Reverse((total_power, Reverse(edge_size), Reverse(origin)))| {
+                (origin, edge_size, total_power)
+            },
+        )
+        .collect()
 }
 
 #[derive(Clone, Copy)]
@@ -216,35 +443,80 @@ impl<'a> FuelCell<'a> {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    for fuel_grid in parse::<FuelGrid>(input)? {
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+/// Describe a square's origin in the puzzle's coordinates, followed by its `edge_size` if any.
+///
+/// When `verbose`, also give it in map coordinates, for comparison against solutions which
+/// use another convention.
+fn describe_origin(origin: Point, edge_size: Option<usize>, verbose: bool) -> String {
+    let coords = FuelGrid::puzzle_coordinates(origin);
+    let size = edge_size
+        .map(|edge_size| format!(",{}", edge_size))
+        .unwrap_or_default();
+    let mut out = format!("{},{}{}", coords.x, coords.y, size);
+    if verbose {
+        out.push_str(&format!(
+            " (puzzle: 1-indexed, y down; map: {},{}{}, 0-indexed, y up)",
+            origin.x, origin.y, size
+        ));
+    }
+    out
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with(input, false)
+}
+
+/// Find the most powerful 3x3 square for each serial.
+///
+/// When `verbose`, give each origin in map coordinates as well as the puzzle's.
+pub fn part1_with(input: &str, verbose: bool) -> Result<String, Error> {
+    let serials: Vec<i32> = parse_str(input).collect();
+    let mut lines = Vec::new();
+    for fuel_grid in FuelGrid::batch(&serials) {
         let max_power_cell = fuel_grid
             .fuel_cells(3)
             .max_by_key(|cell| cell.total_power)
             .expect("fuel grid is never empty");
-        // offset by 1 because AoC expects 1-indexing for this problem
-        let coords = max_power_cell.origin + Point::new(1, 1);
-        println!(
-            "for serial {}: origin of max power cell: {},{}",
-            fuel_grid.serial, coords.x, coords.y
-        );
+        lines.push(format!(
+            "for serial {}: origin of max power cell: {}",
+            fuel_grid.serial,
+            describe_origin(max_power_cell.origin, None, verbose)
+        ));
     }
-    Ok(())
+    Ok(lines.join("\n"))
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    for fuel_grid in parse::<FuelGrid>(input)? {
-        let max_power_cell = fuel_grid
-            .fuel_cells_all_sizes()
-            .max_by_key(|cell| cell.total_power)
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with(input, false)
+}
+
+/// Find the most powerful square of any size for each serial.
+///
+/// When `verbose`, give each origin in map coordinates as well as the puzzle's.
+pub fn part2_with(input: &str, verbose: bool) -> Result<String, Error> {
+    let serials: Vec<i32> = parse_str(input).collect();
+    let mut lines = Vec::new();
+    for fuel_grid in FuelGrid::batch(&serials) {
+        let (origin, edge_size, _total_power) = fuel_grid
+            .top_k_squares(1, 1..=EDGE_SIZE)
+            .pop()
             .expect("fuel grid is never empty");
-        // offset by 1 because AoC expects 1-indexing for this problem
-        let coords = max_power_cell.origin + Point::new(1, 1);
-        println!(
-            "for serial {}: origin of max power cell (all sizes): {},{},{}",
-            fuel_grid.serial, coords.x, coords.y, max_power_cell.edge_size,
-        );
+        lines.push(format!(
+            "for serial {}: origin of max power cell (all sizes): {}",
+            fuel_grid.serial,
+            describe_origin(origin, Some(edge_size), verbose)
+        ));
     }
-    Ok(())
+    Ok(lines.join("\n"))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -260,13 +532,14 @@ pub enum Error {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
 
     fn check_power_level(serial: i32, coords: (usize, usize), expect: i32) {
         assert_ne!(coords.0, 0);
         assert_ne!(coords.1, 0);
 
-        // decrement the x and y coords by 1, because that's how the map will do it
-        let coords = Point::new(coords.0 as i32 - 1, coords.1 as i32 - 1);
+        let coords = FuelGrid::map_coordinates(Point::new(coords.0 as i32, coords.1 as i32));
         assert_eq!(power_level(serial, coords), expect);
     }
 
@@ -289,4 +562,185 @@ mod tests {
     fn example_3() {
         check_power_level(71, (101, 153), 4);
     }
+
+    #[test]
+    fn first_cell_covers_exactly_edge_size() {
+        let grid = FuelGrid::new(18);
+        for &edge_size in &[1, 3, 16, EDGE_SIZE] {
+            let cell = grid.first_cell(edge_size);
+            assert_eq!(
+                cell.total_power,
+                grid.reference_power(Point::new(0, 0), edge_size)
+            );
+        }
+    }
+
+    #[test]
+    fn part1_example() {
+        let grid = FuelGrid::new(18);
+        let best = grid
+            .fuel_cells(3)
+            .max_by_key(|cell| cell.total_power)
+            .unwrap();
+        assert_eq!(
+            FuelGrid::puzzle_coordinates(best.origin),
+            Point::new(33, 45)
+        );
+        assert_eq!(best.total_power, 29);
+        assert_eq!(
+            part1_from_str("18").unwrap(),
+            "for serial 18: origin of max power cell: 33,45"
+        );
+        assert_eq!(
+            part1_with("18", true).unwrap(),
+            "for serial 18: origin of max power cell: 33,45 (puzzle: 1-indexed, y down; map: 32,44, 0-indexed, y up)"
+        );
+    }
+
+    #[test]
+    fn coordinate_conventions() {
+        let puzzle = Point::new(1, 1);
+        assert_eq!(FuelGrid::map_coordinates(puzzle), Point::new(0, 0));
+        assert_eq!(
+            FuelGrid::puzzle_coordinates(FuelGrid::map_coordinates(Point::new(122, 79))),
+            Point::new(122, 79)
+        );
+        assert_eq!(
+            describe_origin(Point::new(89, 268), Some(16), true),
+            "90,269,16 (puzzle: 1-indexed, y down; map: 89,268,16, 0-indexed, y up)"
+        );
+        assert_eq!(
+            describe_origin(Point::new(89, 268), Some(16), false),
+            "90,269,16"
+        );
+    }
+
+    #[test]
+    fn top_k_squares_examples() {
+        for &(serial, x, y, power) in &[(18, 33, 45, 29), (42, 21, 61, 30)] {
+            let top = FuelGrid::new(serial).top_k_squares(1, 3..=3);
+            let origin = FuelGrid::map_coordinates(Point::new(x, y));
+            assert_eq!(top, vec![(origin, 3, power)]);
+        }
+    }
+
+    #[test]
+    fn top_k_squares_sorted() {
+        let top = FuelGrid::new(18).top_k_squares(10, 2..=4);
+        assert_eq!(top.len(), 10);
+        assert!(top.windows(2).all(|window| window[0].2 >= window[1].2));
+        assert!(top
+            .iter()
+            .all(|&(_, edge_size, _)| (2..=4).contains(&edge_size)));
+    }
+
+    #[test]
+    fn lazy_queries() {
+        let grid = FuelGrid::new(57);
+        assert_eq!(grid.power_at(Point::new(121, 78)), Some(-5));
+        assert_eq!(grid.power_at(Point::new(-1, 0)), None);
+        assert_eq!(grid.power_at(Point::new(0, EDGE_SIZE as i32)), None);
+
+        let origin = Point::new(10, 20);
+        assert_eq!(
+            grid.region_power(origin, 3, 3),
+            Some(grid.reference_power(origin, 3))
+        );
+        assert_eq!(grid.region_power(origin, 0, 7), Some(0));
+        assert_eq!(grid.region_power(Point::new(298, 0), 3, 1), None);
+        assert_eq!(grid.region_power(Point::new(0, -1), 1, 1), None);
+        assert_eq!(grid.columns.computed(), 3);
+        assert!(
+            grid.map.get().is_none(),
+            "partial queries computed the whole map"
+        );
+
+        // once the map exists, queries agree with it
+        let whole = grid.first_cell(EDGE_SIZE).total_power;
+        assert!(grid.map.get().is_some());
+        assert_eq!(
+            grid.region_power(Point::new(0, 0), EDGE_SIZE, EDGE_SIZE),
+            Some(whole)
+        );
+        assert_eq!(grid.power_at(Point::new(121, 78)), Some(-5));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn fuel_cells_cover_grid(serial in 0..10_000, edge_size in 1..=EDGE_SIZE) {
+            let grid = FuelGrid::new(serial);
+            let positions = EDGE_SIZE - edge_size + 1;
+            let mut seen = HashSet::new();
+            for cell in grid.fuel_cells(edge_size) {
+                prop_assert!(grid.in_bounds(cell.origin));
+                prop_assert!(seen.insert(cell.origin), "visited {:?} twice", cell.origin);
+            }
+            prop_assert_eq!(seen.len(), positions * positions);
+        }
+
+        #[test]
+        fn fuel_cells_match_reference(serial in 0..10_000, edge_size in 1..=6_usize) {
+            let grid = FuelGrid::new(serial);
+            for cell in grid.fuel_cells(edge_size) {
+                prop_assert_eq!(
+                    cell.total_power,
+                    grid.reference_power(cell.origin, edge_size),
+                    "origin {:?}",
+                    cell.origin
+                );
+            }
+        }
+
+        #[test]
+        fn large_squares_match_reference(serial in 0..10_000, edge_size in 1..=EDGE_SIZE, nth in any::<prop::sample::Index>()) {
+            let grid = FuelGrid::new(serial);
+            let positions = EDGE_SIZE - edge_size + 1;
+            let cell = grid
+                .fuel_cells(edge_size)
+                .nth(nth.index(positions * positions))
+                .expect("index is in range");
+            prop_assert_eq!(cell.total_power, grid.reference_power(cell.origin, edge_size));
+        }
+
+        #[test]
+        fn region_power_matches_reference(
+            serial in 0..10_000,
+            x in 0..EDGE_SIZE,
+            y in 0..EDGE_SIZE,
+            width in 0..=EDGE_SIZE,
+            height in 0..=EDGE_SIZE,
+        ) {
+            let grid = FuelGrid::new(serial);
+            let origin = Point::new(x as i32, y as i32);
+            let expect = (x + width <= EDGE_SIZE && y + height <= EDGE_SIZE).then(|| {
+                (0..height as i32)
+                    .flat_map(|dy| (0..width as i32).map(move |dx| Point::new(dx, dy)))
+                    .map(|offset| power_level(serial, origin + offset))
+                    .sum::<i32>()
+            });
+            prop_assert_eq!(grid.region_power(origin, width, height), expect);
+        }
+
+        #[test]
+        fn top_square_matches_reference(serial in 0..10_000, edge_size in 1..=5_usize) {
+            let grid = FuelGrid::new(serial);
+            let (origin, total_power) = grid.reference_max_square(edge_size).unwrap();
+            prop_assert_eq!(
+                grid.top_k_squares(1, edge_size..=edge_size),
+                vec![(origin, edge_size, total_power)]
+            );
+        }
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day11-crlf", "18\n").unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part1_from_str(&input).unwrap(),
+            "for serial 18: origin of max power cell: 33,45"
+        );
+    }
 }
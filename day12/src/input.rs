@@ -14,7 +14,7 @@
 use crate::{encode_as_u8::EncodeAsU8, Error, Rules};
 use bitvec::vec::BitVec;
 use pest_consume::{match_nodes, Parser};
-use std::path::Path;
+use std::str::FromStr;
 
 type Node<'i> = pest_consume::Node<'i, Rule, ()>;
 type ParseResult<T> = Result<T, pest_consume::Error<Rule>>;
@@ -37,12 +37,18 @@ impl InputParser {
         }
     }
 
-    fn state(input: Node) -> ParseResult<BitVec> {
+    fn pots(input: Node) -> ParseResult<BitVec> {
         Ok(match_nodes!(input.into_children();
             [pot(pots)..] => pots.collect(),
         ))
     }
 
+    fn state(input: Node) -> ParseResult<BitVec> {
+        Ok(match_nodes!(input.into_children();
+            [pots(pots)] => pots,
+        ))
+    }
+
     fn rule(input: Node) -> ParseResult<([bool; 5], bool)> {
         Ok(match_nodes!(input.into_children();
             [pot(p0), pot(p1), pot(p2), pot(p3), pot(p4), pot(p5)] => {
@@ -53,15 +59,7 @@ impl InputParser {
 
     fn rules(input: Node) -> ParseResult<Rules> {
         Ok(match_nodes!(input.into_children();
-            [rule(rules)..] => {
-                let mut rules_list = Rules::default();
-
-                for (idx, val) in rules {
-                    rules_list[idx.as_u8() as usize] = val;
-                }
-
-                rules_list
-            },
+            [rule(rules)..] => rules_from(rules),
         ))
     }
 
@@ -70,21 +68,69 @@ impl InputParser {
             [state(initial), rules(rules), EOI(_eoi)] => Input{ initial, rules },
         ))
     }
+
+    fn pattern(input: Node) -> ParseResult<BitVec> {
+        Ok(match_nodes!(input.into_children();
+            [pots(pots), EOI(_eoi)] => pots,
+        ))
+    }
+
+    fn rule_list(input: Node) -> ParseResult<Rules> {
+        Ok(match_nodes!(input.into_children();
+            [rule(rules).., EOI(_eoi)] => rules_from(rules),
+        ))
+    }
 }
 
-/// Representation of the day's input.
+/// Collect parsed rules into a ruleset. Patterns which aren't mentioned produce empty pots.
+fn rules_from(rules: impl Iterator<Item = ([bool; 5], bool)>) -> Rules {
+    let mut rules_list = Rules::default();
+
+    for (idx, val) in rules {
+        rules_list[idx.as_u8() as usize] = val;
+    }
+
+    rules_list
+}
+
+impl InputParser {
+    /// Parse a pattern of pots like `#..#.#`.
+    pub(crate) fn parse_pattern(s: &str) -> Result<BitVec, Error> {
+        let pattern = InputParser::parse(Rule::pattern, s)?.single()?;
+        InputParser::pattern(pattern).map_err(Into::into)
+    }
+
+    /// Parse a list of rules like `..#.. => #`, one per line.
+    pub(crate) fn parse_rule_list(s: &str) -> Result<Rules, Error> {
+        let rule_list = InputParser::parse(Rule::rule_list, s)?.single()?;
+        InputParser::rule_list(rule_list).map_err(Into::into)
+    }
+}
+
+/// Representation of the day's input: an initial state followed by a list of rules.
+///
+/// ```text
+/// initial state: #..#.#..##......###...###
+///
+/// ...## => #
+/// ..#.. => #
+/// ```
 #[derive(Debug)]
 pub struct Input {
+    /// Pots of the initial state. The first pot has index 0.
     pub initial: BitVec,
     pub rules: Rules,
 }
 
-impl Input {
-    pub fn load_file(path: &Path) -> Result<Self, Error> {
-        let input_data = std::fs::read_to_string(path)?;
-        Self::new(&input_data)
+impl FromStr for Input {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
     }
+}
 
+impl Input {
     pub fn new(input_data: &str) -> Result<Self, Error> {
         let inputs = InputParser::parse(Rule::file, &input_data)?;
         let input = inputs.single()?;
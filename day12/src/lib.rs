@@ -1,18 +1,42 @@
 mod encode_as_u8;
 mod input;
+mod pot_state;
+mod rules;
+mod sparse_state;
 
 use bitvec::prelude::*;
 use encode_as_u8::EncodeAsU8;
+pub use input::Input;
+pub use pot_state::PotState;
+pub use rules::{InferenceError, Rules, RulesMask};
+pub use sparse_state::SparseState;
 use std::{
-    ops::{Deref, Index},
+    ops::{Deref, Index, Range},
     path::Path,
+    str::FromStr,
 };
 
-// each array of 5 bits corresponds to a single number in the range `0..32`,
-// so we can encode the complete ruleset as an array of 32 bools.
-pub type Rules = [bool; 32];
-
-struct State {
+/// A generation of pots.
+///
+/// Pots are indexed relative to the first pot of the initial state, which has index 0.
+/// Each generation can spread plants by up to two pots in either direction, so successor
+/// states grow by two pots on each side; indices to the left of the initial state are
+/// negative. Pots outside a state's bounds are empty.
+///
+/// States parse from a pattern of pots, where `#` is a plant and `.` is empty:
+///
+/// ```
+/// # use day12::{Rules, State};
+/// let rules: Rules = "..#.. => #".parse().unwrap();
+/// let state: State = "#...#".parse().unwrap();
+/// assert_eq!(state.pot_sum(), 4);
+///
+/// let next = state.successor(&rules);
+/// assert_eq!(next.bounds(), (-2, 6));
+/// assert!(next.get(0) && next.get(4));
+/// assert!(!next.get(-1) && !next.get(100));
+/// ```
+pub struct State {
     pots: BitVec,
     zero_offset: isize,
 }
@@ -34,8 +58,17 @@ impl Index<isize> for State {
     }
 }
 
+impl FromStr for State {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        input::InputParser::parse_pattern(s).map(State::from_initial)
+    }
+}
+
 impl State {
-    fn from_initial(initial: BitVec) -> Self {
+    /// Construct an initial state, whose first pot has index 0.
+    pub fn from_initial(initial: BitVec) -> Self {
         Self {
             pots: initial,
             zero_offset: 0,
@@ -48,6 +81,32 @@ impl State {
         self.pots.set((self.zero_offset + index) as usize, value);
     }
 
+    /// Inclusive `(low, high)` bounds of the pot indices tracked by this state.
+    pub fn bounds(&self) -> (isize, isize) {
+        let low = -self.zero_offset;
+        (low, low + self.len() as isize - 1)
+    }
+
+    /// Get the value of a pot. Pots outside the bounds of this state are empty.
+    pub fn get(&self, index: isize) -> bool {
+        let (low, high) = self.bounds();
+        (low..=high).contains(&index) && self[index]
+    }
+
+    /// Encode the window of length 5 centered on `index` as a value.
+    ///
+    /// Unlike `windows_enumerated`, this works for any index, treating out-of-bounds pots as empty.
+    fn window(&self, index: isize) -> u8 {
+        [
+            self.get(index - 2),
+            self.get(index - 1),
+            self.get(index),
+            self.get(index + 1),
+            self.get(index + 2),
+        ]
+        .as_u8()
+    }
+
     /// Iterate over all pots and relevant indices in the state.
     fn iter_enumerated(&self) -> impl '_ + Iterator<Item = (isize, bool)> {
         std::iter::successors(Some(-self.zero_offset), |&offset| Some(offset + 1))
@@ -60,10 +119,10 @@ impl State {
     ///
     /// The index returned is the offset of the center item in the window.
     ///
-    /// Note that this produces two more items than `self.len()`.
+    /// Note that this produces four more items than `self.len()`.
     /// Normally, windows produces `self.len() - windows - 1` items, and `windows`
     /// here is five. However, this iteration includes windows overhanging
-    /// each side by up to 3 values.
+    /// each side by up to 4 values.
     ///
     /// # Panics
     ///
@@ -77,13 +136,18 @@ impl State {
         let self_len = self.len();
         let zero_offset = self.zero_offset;
 
+        let left_overhang_4 = [self.pots[0]].as_u8();
         let left_overhang_3 = [self.pots[0], self.pots[1]].as_u8();
         let left_overhang_2 = [self.pots[0], self.pots[1], self.pots[2]].as_u8();
         let left_overhang_1 = [self.pots[0], self.pots[1], self.pots[2], self.pots[3]].as_u8();
-        let left_overhangs =
-            std::array::IntoIter::new([left_overhang_3, left_overhang_2, left_overhang_1])
-                .enumerate()
-                .map(move |(idx, val)| (idx as isize - zero_offset - 1, val));
+        let left_overhangs = std::array::IntoIter::new([
+            left_overhang_4,
+            left_overhang_3,
+            left_overhang_2,
+            left_overhang_1,
+        ])
+        .enumerate()
+        .map(move |(idx, val)| (idx as isize - zero_offset - 2, val));
 
         let right_overhang_1 = [
             self.pots[self.len() - 4],
@@ -109,10 +173,15 @@ impl State {
             false,
         ]
         .as_u8();
-        let right_overhangs =
-            std::array::IntoIter::new([right_overhang_1, right_overhang_2, right_overhang_3])
-                .enumerate()
-                .map(move |(idx, val)| ((idx + self_len) as isize - zero_offset - 2, val));
+        let right_overhang_4 = [self.pots[self.len() - 1], false, false, false, false].as_u8();
+        let right_overhangs = std::array::IntoIter::new([
+            right_overhang_1,
+            right_overhang_2,
+            right_overhang_3,
+            right_overhang_4,
+        ])
+        .enumerate()
+        .map(move |(idx, val)| ((idx + self_len) as isize - zero_offset - 2, val));
 
         let iteration = self
             .windows(5)
@@ -122,10 +191,11 @@ impl State {
         left_overhangs.chain(iteration).chain(right_overhangs)
     }
 
-    fn successor(&self, rules: &Rules) -> State {
+    /// Compute the next generation.
+    pub fn successor(&self, rules: &Rules) -> State {
         let mut succ = State {
-            pots: bitvec![0; self.pots.len() + 2],
-            zero_offset: self.zero_offset + 1,
+            pots: bitvec![0; self.pots.len() + 4],
+            zero_offset: self.zero_offset + 2,
         };
 
         for (idx, val) in self.windows_enumerated() {
@@ -135,60 +205,264 @@ impl State {
         succ
     }
 
-    fn pot_sum(&self) -> isize {
+    /// Compute the next generation, operating on whole words of pots at a time.
+    ///
+    /// This produces exactly the same state as [`successor`](Self::successor). For each word
+    /// of the successor, it gathers five words holding every pot's window neighbors, then ORs
+    /// together one AND term per pattern which produces a plant, so 64 pots are decided in a
+    /// handful of operations per rule. Unlike `successor`, it works on states of any length.
+    pub fn successor_fast(&self, rules: &RulesMask) -> State {
+        const BITS: isize = usize::BITS as isize;
+
+        let len = self.pots.len();
+        let mut words = self.pots.as_raw_slice().to_vec();
+        // bits past the end of a bitvec are unspecified
+        if len % BITS as usize != 0 {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (len % BITS as usize)) - 1;
+            }
+        }
+
+        // the 64 pots starting at `start`; pots outside the state are empty
+        let pots_at = |start: isize| -> usize {
+            let word = start.div_euclid(BITS);
+            let shift = start.rem_euclid(BITS);
+            let get = |idx: isize| {
+                if idx >= 0 {
+                    words.get(idx as usize).copied().unwrap_or_default()
+                } else {
+                    0
+                }
+            };
+            if shift == 0 {
+                get(word)
+            } else {
+                (get(word) >> shift) | (get(word + 1) << (BITS - shift))
+            }
+        };
+
+        // successor pot `i` is centered on pot `i - 2` of this state,
+        // so its window covers pots `i - 4 ..= i`.
+        let succ_len = len + 4;
+        let n_words = (succ_len + BITS as usize - 1) / BITS as usize;
+        let mut succ_words = Vec::with_capacity(n_words);
+        for word in 0..n_words as isize {
+            let start = word * BITS;
+            // neighbors[0] is the leftmost pot of each window: the most significant bit of the pattern
+            let neighbors = [
+                pots_at(start - 4),
+                pots_at(start - 3),
+                pots_at(start - 2),
+                pots_at(start - 1),
+                pots_at(start),
+            ];
+            let mut out = 0;
+            for pattern in rules.patterns() {
+                let mut term = !0;
+                for (position, &neighbor) in neighbors.iter().enumerate() {
+                    if pattern & (0b10000 >> position) != 0 {
+                        term &= neighbor;
+                    } else {
+                        term &= !neighbor;
+                    }
+                }
+                out |= term;
+            }
+            succ_words.push(out);
+        }
+
+        let mut pots = BitVec::from_vec(succ_words);
+        pots.truncate(succ_len);
+        State {
+            pots,
+            zero_offset: self.zero_offset + 2,
+        }
+    }
+
+    /// Sum the indices of all pots which contain plants.
+    pub fn pot_sum(&self) -> isize {
         self.iter_enumerated()
             .filter_map(|(idx, has_plant)| has_plant.then(move || idx))
             .sum()
     }
 
-    fn into_iter(self, rules: &Rules) -> impl '_ + Iterator<Item = State> {
-        std::iter::successors(Some(self), move |state| Some(state.successor(rules)))
+    /// Sum the indices of the pots in `range` which contain plants.
+    pub fn pot_sum_range(&self, range: Range<isize>) -> isize {
+        self.iter_enumerated()
+            .filter_map(|(idx, has_plant)| (has_plant && range.contains(&idx)).then(move || idx))
+            .sum()
+    }
+
+    /// The number of pots which contain plants.
+    pub fn live_count(&self) -> usize {
+        self.count_ones()
+    }
+
+    fn into_iter(self, rules: &Rules) -> impl Iterator<Item = State> {
+        let rules = RulesMask::from(rules);
+        std::iter::successors(Some(self), move |state| Some(state.successor_fast(&rules)))
     }
 }
 
+/// The default number of generations to simulate while waiting for a steady state.
+pub const DEFAULT_GENERATION_CAP: usize = 1_000;
+
+/// If the number of plants increases this many generations in a row, assume it always will.
+pub const GROWTH_WINDOW: usize = 100;
+
+/// Summary statistics of a single generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationRecord {
+    pub generation: usize,
+    pub pot_sum: isize,
+    pub live_count: usize,
+}
+
 /// Keep calculating successors until the system settles down into a steady state, as indicated
 /// by the difference remaining constant twice in a row.
 ///
 /// Returns `(generation, state, diff of sums)`.
-fn advance_until_steady_state(state: State, rules: &Rules) -> (usize, State, isize) {
+///
+/// Fails with [`Error::NoSteadyState`] after `generation_cap` generations, or as soon as
+/// the number of plants has increased for `growth_window` consecutive generations.
+fn advance_until_steady_state<S: PotState>(
+    state: S,
+    rules: &Rules,
+    generation_cap: usize,
+    growth_window: usize,
+) -> Result<(usize, S, isize), Error> {
+    advance_until_steady_state_recording(state, rules, generation_cap, growth_window, |_| {})
+}
+
+/// Like [`advance_until_steady_state`], but report every generation examined, starting
+/// with the initial state.
+fn advance_until_steady_state_recording<S: PotState>(
+    state: S,
+    rules: &Rules,
+    generation_cap: usize,
+    growth_window: usize,
+    mut on_generation: impl FnMut(GenerationRecord),
+) -> Result<(usize, S, isize), Error> {
     let mut old_sum = 0;
     let mut older_sum = 0;
+    let mut old_count = 0;
+    let mut growing_for = 0;
 
-    for (generation, state) in state.into_iter(rules).enumerate() {
+    let generations = std::iter::successors(Some(state), |state| Some(state.successor(rules)));
+    for (generation, state) in generations.enumerate().take(generation_cap + 1) {
         let sum = state.pot_sum();
+        let count = state.live_count();
+        on_generation(GenerationRecord {
+            generation,
+            pot_sum: sum,
+            live_count: count,
+        });
+
         let older_diff = old_sum - older_sum;
         let diff = sum - old_sum;
         if diff == older_diff {
-            return (generation, state, diff);
+            return Ok((generation, state, diff));
         }
 
+        if generation > 0 && count > old_count {
+            growing_for += 1;
+            if growing_for >= growth_window {
+                return Err(Error::NoSteadyState {
+                    generations: generation,
+                });
+            }
+        } else {
+            growing_for = 0;
+        }
+        old_count = count;
+
         older_sum = old_sum;
         old_sum = sum;
     }
 
-    unreachable!("state is known to stabilize within 1000 iterations")
+    Err(Error::NoSteadyState {
+        generations: generation_cap,
+    })
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let input::Input { rules, initial } = input::Input::load_file(input)?;
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let Input { rules, initial } = input.parse()?;
     let state = State::from_initial(initial);
     let state = state.into_iter(&rules).nth(20).unwrap();
     let pot_sum: isize = state.pot_sum();
-    println!("pot sum after 20 generations: {}", pot_sum);
-    Ok(())
+    Ok(format!("pot sum after 20 generations: {}", pot_sum))
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let input::Input { rules, initial } = input::Input::load_file(input)?;
-    let state = State::from_initial(initial);
-    let (generation, state, diff) = advance_until_steady_state(state, &rules);
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with_generation_cap(input, DEFAULT_GENERATION_CAP)
+}
+
+/// Solve part 2, simulating at most `generation_cap` generations while waiting for a steady state.
+///
+/// Fails with [`Error::NoSteadyState`] if the pot sum never settles into a constant difference.
+pub fn part2_with_generation_cap(input: &str, generation_cap: usize) -> Result<String, Error> {
+    solve_part2::<State>(input, generation_cap)
+}
+
+/// Solve part 2, storing each generation as a [`SparseState`].
+pub fn part2_sparse_from_str(input: &str) -> Result<String, Error> {
+    solve_part2::<SparseState>(input, DEFAULT_GENERATION_CAP)
+}
+
+fn solve_part2<S: PotState>(input: &str, generation_cap: usize) -> Result<String, Error> {
+    let Input { rules, initial } = input.parse()?;
+    let state = S::from_initial(initial);
+    let (generation, state, diff) =
+        advance_until_steady_state(state, &rules, generation_cap, GROWTH_WINDOW)?;
 
     const TARGET_GENERATION: usize = 50_000_000_000;
 
-    let total = state.pot_sum() as usize + (diff as usize * (TARGET_GENERATION - generation));
-    println!("pot sum after {} generations: {}", TARGET_GENERATION, total);
+    // plants can drift left, so the sum and its difference may be negative
+    let total = (TARGET_GENERATION as i64)
+        .checked_sub(generation as i64)
+        .and_then(|remaining| (diff as i64).checked_mul(remaining))
+        .and_then(|growth| growth.checked_add(state.pot_sum() as i64))
+        .ok_or(Error::Overflow)?;
+    Ok(format!(
+        "pot sum after {} generations: {}",
+        TARGET_GENERATION, total
+    ))
+}
 
-    Ok(())
+/// Render each generation's pot sum and live count as CSV, with a header row.
+///
+/// Generations are recorded until a steady state is found, as in part 2. If none is,
+/// the generations examined before giving up are still rendered.
+pub fn time_series_csv_from_str(input: &str) -> Result<String, Error> {
+    let Input { rules, initial } = input.parse()?;
+    let state = State::from_initial(initial);
+    let mut lines = vec!["generation,pot_sum,live_count".to_string()];
+    let outcome = advance_until_steady_state_recording(
+        state,
+        &rules,
+        DEFAULT_GENERATION_CAP,
+        GROWTH_WINDOW,
+        |record| {
+            lines.push(format!(
+                "{},{},{}",
+                record.generation, record.pot_sum, record.live_count
+            ))
+        },
+    );
+    match outcome {
+        Ok(_) | Err(Error::NoSteadyState { .. }) => Ok(lines.join("\n")),
+        Err(err) => Err(err),
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -199,12 +473,34 @@ pub enum Error {
     Parse(#[from] pest::error::Error<input::Rule>),
     #[error("No solution found")]
     NoSolution,
+    #[error("no steady state found after {generations} generations")]
+    NoSteadyState { generations: usize },
+    #[error("pot sum overflows")]
+    Overflow,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    pub(crate) const EXAMPLE: &str = "initial state: #..#.#..##......###...###
+
+...## => #
+..#.. => #
+.#... => #
+.#.#. => #
+.#.## => #
+.##.. => #
+.#### => #
+#.#.# => #
+#.### => #
+##.#. => #
+##.## => #
+###.. => #
+###.# => #
+####. => #
+";
+
     #[test]
     fn test_windows_enumerated_indices() {
         for n_pots in 5..=10 {
@@ -217,11 +513,11 @@ mod tests {
 
                 assert!(values.into_iter().all(|value| value == 0));
 
-                assert_eq!(indices.len(), pots.len() + 2);
-                assert_eq!(indices[0], -1 - state.zero_offset);
+                assert_eq!(indices.len(), pots.len() + 4);
+                assert_eq!(indices[0], -2 - state.zero_offset);
                 assert_eq!(
                     *indices.last().unwrap(),
-                    pots.len() as isize - state.zero_offset,
+                    pots.len() as isize + 1 - state.zero_offset,
                 );
 
                 assert!(indices.windows(2).all(|window| window[1] == window[0] + 1));
@@ -229,21 +525,206 @@ mod tests {
         }
     }
 
+    /// Every pattern of `len` pots, for each length in `lengths`.
+    fn all_states(lengths: std::ops::RangeInclusive<usize>) -> impl Iterator<Item = State> {
+        lengths.flat_map(|len| {
+            (0..1_u32 << len).map(move |bits| {
+                State::from_initial((0..len).map(|idx| bits & (1 << idx) != 0).collect())
+            })
+        })
+    }
+
+    fn assert_same_successor(state: &State, rules: &Rules) {
+        let slow = state.successor(rules);
+        let fast = state.successor_fast(&RulesMask::from(rules));
+        assert_eq!(fast.zero_offset, slow.zero_offset);
+        assert_eq!(
+            fast.pots, slow.pots,
+            "from {:?} with {:?}",
+            state.pots, rules
+        );
+    }
+
+    fn rules_from_mask(mask: u32) -> Rules {
+        let mut rules = Rules::default();
+        for (pattern, rule) in rules.iter_mut().enumerate() {
+            *rule = mask & (1 << pattern) != 0;
+        }
+        rules
+    }
+
+    #[test]
+    fn successor_fast_single_patterns() {
+        // each rule on its own, against every state of up to 10 pots
+        for pattern in 0..32 {
+            let rules = rules_from_mask(1 << pattern);
+            for state in all_states(5..=10) {
+                assert_same_successor(&state, &rules);
+            }
+        }
+    }
+
+    #[test]
+    fn successor_fast_combined_patterns() {
+        let masks = [0, !0, 0xaaaa_aaaa, 0x5555_5555, 0x1234_5678, 0xfffe_fffe];
+        for &mask in &masks {
+            let rules = rules_from_mask(mask);
+            for state in all_states(5..=10) {
+                assert_same_successor(&state, &rules);
+            }
+        }
+    }
+
+    #[test]
+    fn successor_fast_across_words() {
+        // a deterministic pseudo-random sequence of long states, spanning several words
+        let mut seed = 0x2018_u64;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            seed >> 33
+        };
+        for len in (60..=200).step_by(7) {
+            let rules = rules_from_mask(next() as u32);
+            let state = State::from_initial((0..len).map(|_| next() & 1 == 1).collect());
+            assert_same_successor(&state, &rules);
+
+            // offset states must agree as well
+            let mut slow = state.successor(&rules);
+            let mut fast = state.successor_fast(&RulesMask::from(&rules));
+            for _ in 0..5 {
+                slow = slow.successor(&rules);
+                fast = fast.successor_fast(&RulesMask::from(&rules));
+            }
+            assert_eq!(fast.zero_offset, slow.zero_offset);
+            assert_eq!(fast.pots, slow.pots);
+        }
+    }
+
+    /// Every plant survives, and spreads one pot to the right each generation.
+    fn rightward_growth() -> Rules {
+        rules_from_mask((0..32).fold(0, |mask, pattern| {
+            if pattern & 0b01100 != 0 {
+                mask | 1 << pattern
+            } else {
+                mask
+            }
+        }))
+    }
+
+    #[test]
+    fn unbounded_growth_detected() {
+        let state: State = "..#".parse().unwrap();
+        assert!(matches!(
+            advance_until_steady_state(state, &rightward_growth(), DEFAULT_GENERATION_CAP, 10),
+            Err(Error::NoSteadyState { generations: 10 })
+        ));
+    }
+
+    #[test]
+    fn generation_cap() {
+        let state: State = "..#".parse().unwrap();
+        assert!(matches!(
+            advance_until_steady_state(state, &rightward_growth(), 25, GROWTH_WINDOW),
+            Err(Error::NoSteadyState { generations: 25 })
+        ));
+
+        // a lone plant which simply persists is steady right away
+        let state: State = "..#".parse().unwrap();
+        let rules: Rules = "..#.. => #".parse().unwrap();
+        let (_, state, diff) =
+            advance_until_steady_state(state, &rules, DEFAULT_GENERATION_CAP, GROWTH_WINDOW)
+                .unwrap();
+        assert_eq!(diff, 0);
+        assert_eq!(state.pot_sum(), 2);
+    }
+
+    #[test]
+    fn leftward_drift() {
+        // a lone plant which moves two pots left each generation
+        let input = "initial state: .#\n\n....# => #\n";
+        assert_eq!(
+            part2_from_str(input).unwrap(),
+            "pot sum after 50000000000 generations: -99999999999"
+        );
+    }
+
+    #[test]
+    fn sparse_part2() {
+        let input = "initial state: .#\n\n....# => #\n";
+        assert_eq!(
+            part2_sparse_from_str(input).unwrap(),
+            part2_from_str(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn windowed_queries() {
+        let state: State = "#..##.#".parse().unwrap();
+        assert_eq!(state.live_count(), 4);
+        assert_eq!(state.pot_sum_range(0..7), state.pot_sum());
+        assert_eq!(state.pot_sum_range(1..4), 3);
+        assert_eq!(state.pot_sum_range(-10..1), 0);
+        assert_eq!(state.pot_sum_range(4..100), 4 + 6);
+        assert_eq!(state.pot_sum_range(5..5), 0);
+    }
+
+    #[test]
+    fn time_series() {
+        let state: State = "..#".parse().unwrap();
+        let mut records = Vec::new();
+        let result = advance_until_steady_state_recording(
+            state,
+            &rightward_growth(),
+            5,
+            GROWTH_WINDOW,
+            |record| records.push(record),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::NoSteadyState { generations: 5 })
+        ));
+        assert_eq!(records.len(), 6);
+        for (generation, record) in records.iter().enumerate() {
+            // the plants at pots 2 ..= 2 + generation
+            let live_count = generation + 1;
+            let pot_sum = (2..=2 + generation as isize).sum();
+            assert_eq!(
+                *record,
+                GenerationRecord {
+                    generation,
+                    pot_sum,
+                    live_count
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn successor_fast_short_states() {
+        let rules = rules_from_mask(1 << 0b00100 | 1 << 0b00010);
+        let state: State = "#".parse().unwrap();
+        let next = state.successor_fast(&RulesMask::from(&rules));
+        assert_eq!(next.bounds(), (-2, 2));
+        assert!(next.get(-1) && next.get(0) && !next.get(1));
+    }
+
     #[test]
     fn test_windows_enumerated_values() {
         use std::array::IntoIter;
 
         for n_pots in 5..=10 {
             let pots: BitVec = IntoIter::new([true, false]).cycle().take(n_pots).collect();
-            let expect: Vec<_> = IntoIter::new([0b0010, 0b00101, 0b01010])
+            let expect: Vec<_> = IntoIter::new([0b00001, 0b0010, 0b00101, 0b01010])
                 .chain(IntoIter::new([0b10101, 0b01010]).cycle().take(n_pots - 4))
-                .chain(IntoIter::new([0b01010, 0b10100, 0b01000]).map(|v| {
-                    if n_pots % 2 == 0 {
-                        (v << 1) & 0b11111
-                    } else {
-                        v
-                    }
-                }))
+                .chain(
+                    IntoIter::new([0b01010, 0b10100, 0b01000, 0b10000]).map(|v| {
+                        if n_pots % 2 == 0 {
+                            (v << 1) & 0b11111
+                        } else {
+                            v
+                        }
+                    }),
+                )
                 .collect();
 
             for offset in 0..pots.len() {
@@ -260,4 +741,14 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day12-crlf", EXAMPLE).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part1_from_str(&input).unwrap(),
+            "pot sum after 20 generations: 325"
+        );
+    }
 }
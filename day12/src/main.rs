@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day12::{part1, part2};
+use day12::{part1_from_str, part2_from_str, part2_sparse_from_str, time_series_csv_from_str};
 
 use color_eyre::eyre::Result;
 use std::path::PathBuf;
@@ -10,7 +10,7 @@ const DAY: u8 = 12;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day12 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +21,51 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// in part 2, store each generation as runs of plants rather than a pot per bit
+    #[structopt(long)]
+    sparse: bool,
+
+    /// instead of solving, dump each generation's pot sum and live count as CSV
+    #[structopt(long)]
+    time_series: bool,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
+
+    if args.time_series {
+        println!("{}", time_series_csv_from_str(&input)?);
+        return Ok(());
+    }
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_from_str(&input)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        if args.sparse {
+            println!("{}", part2_sparse_from_str(&input)?);
+        } else {
+            println!("{}", part2_from_str(&input)?);
+        }
     }
     Ok(())
 }
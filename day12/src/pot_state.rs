@@ -0,0 +1,52 @@
+use crate::{Rules, RulesMask, State};
+use bitvec::prelude::*;
+
+/// A generation of pots, however it's stored.
+///
+/// Every representation tracks the same pots as [`State`] and computes the same successors,
+/// so they're interchangeable.
+pub trait PotState: Sized {
+    /// Construct an initial state, whose first pot has index 0.
+    fn from_initial(initial: BitVec) -> Self;
+
+    /// Inclusive `(low, high)` bounds of the pot indices tracked by this state.
+    fn bounds(&self) -> (isize, isize);
+
+    /// Get the value of a pot. Pots outside the bounds of this state are empty.
+    fn get(&self, index: isize) -> bool;
+
+    /// Compute the next generation.
+    fn successor(&self, rules: &Rules) -> Self;
+
+    /// Sum the indices of all pots which contain plants.
+    fn pot_sum(&self) -> isize;
+
+    /// The number of pots which contain plants.
+    fn live_count(&self) -> usize;
+}
+
+impl PotState for State {
+    fn from_initial(initial: BitVec) -> Self {
+        State::from_initial(initial)
+    }
+
+    fn bounds(&self) -> (isize, isize) {
+        State::bounds(self)
+    }
+
+    fn get(&self, index: isize) -> bool {
+        State::get(self, index)
+    }
+
+    fn successor(&self, rules: &Rules) -> Self {
+        self.successor_fast(&RulesMask::from(rules))
+    }
+
+    fn pot_sum(&self) -> isize {
+        State::pot_sum(self)
+    }
+
+    fn live_count(&self) -> usize {
+        State::live_count(self)
+    }
+}
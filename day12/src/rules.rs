@@ -0,0 +1,224 @@
+use crate::{input::InputParser, Error, State};
+use std::{
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
+
+/// Rules determining whether a pot contains a plant in the next generation.
+///
+/// Each window of 5 pots corresponds to a single number in the range `0..32`,
+/// so we can encode the complete ruleset as an array of 32 bools. The leftmost pot
+/// of the window is the most significant bit, so `##...` is rule `0b11000`.
+///
+/// Rules parse from lines in the puzzle's format. Patterns which aren't listed
+/// produce empty pots.
+///
+/// ```
+/// # use day12::Rules;
+/// let rules: Rules = "..#.. => #\n##... => .".parse().unwrap();
+/// assert!(rules[0b00100]);
+/// assert!(!rules[0b11000]);
+/// assert!(!rules[0b11111]);
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules([bool; 32]);
+
+impl From<[bool; 32]> for Rules {
+    fn from(rules: [bool; 32]) -> Self {
+        Rules(rules)
+    }
+}
+
+impl FromStr for Rules {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InputParser::parse_rule_list(s)
+    }
+}
+
+impl Deref for Rules {
+    type Target = [bool; 32];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Rules {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Rules {
+    /// Infer the ruleset which produced a sequence of consecutive generations.
+    ///
+    /// Every pot in each generation is determined by the window of five pots centered on it
+    /// in the previous generation, so each pair of adjacent states constrains the rules for
+    /// every window which appears in the earlier state. Pots outside a state's bounds are empty.
+    ///
+    /// Fails if the states contradict each other, or if some pattern never appears, so
+    /// its rule can't be determined.
+    pub fn infer(states: &[State]) -> Result<Rules, InferenceError> {
+        if states.len() < 2 {
+            return Err(InferenceError::TooFewStates);
+        }
+
+        let mut observed = [None; 32];
+        for (generation, pair) in states.windows(2).enumerate() {
+            let (prev, next) = (&pair[0], &pair[1]);
+
+            // a plant can spread at most two pots beyond the extent of the previous generation;
+            // anything beyond that is covered by the all-empty window.
+            let (prev_low, prev_high) = prev.bounds();
+            let (next_low, next_high) = next.bounds();
+            let low = (prev_low - 2).min(next_low);
+            let high = (prev_high + 2).max(next_high);
+
+            for index in low..=high {
+                let pattern = prev.window(index);
+                let has_plant = next.get(index);
+                match observed[pattern as usize] {
+                    None => observed[pattern as usize] = Some(has_plant),
+                    Some(expect) if expect == has_plant => {}
+                    Some(_) => {
+                        return Err(InferenceError::Inconsistent {
+                            generation: generation + 1,
+                            index,
+                            pattern,
+                        })
+                    }
+                }
+            }
+        }
+
+        let unobserved: Vec<u8> = observed
+            .iter()
+            .enumerate()
+            .filter_map(|(pattern, rule)| rule.is_none().then(move || pattern as u8))
+            .collect();
+        if !unobserved.is_empty() {
+            return Err(InferenceError::Ambiguous(unobserved));
+        }
+
+        let mut rules = Rules::default();
+        for (rule, observation) in rules.iter_mut().zip(observed.iter()) {
+            *rule = observation.expect("all patterns observed");
+        }
+        Ok(rules)
+    }
+}
+
+/// Rules packed into a bitmask: bit `p` is set when pattern `p` produces a plant.
+///
+/// This is the form [`State::successor_fast`] consumes.
+///
+/// ```
+/// # use day12::{Rules, RulesMask};
+/// let rules: Rules = "..#.. => #\n#.... => #".parse().unwrap();
+/// assert_eq!(RulesMask::from(&rules).bits(), 1 << 0b00100 | 1 << 0b10000);
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RulesMask(u32);
+
+impl From<&Rules> for RulesMask {
+    fn from(rules: &Rules) -> Self {
+        let mut mask = 0;
+        for (pattern, &has_plant) in rules.iter().enumerate() {
+            if has_plant {
+                mask |= 1 << pattern;
+            }
+        }
+        RulesMask(mask)
+    }
+}
+
+impl From<u32> for RulesMask {
+    fn from(mask: u32) -> Self {
+        RulesMask(mask)
+    }
+}
+
+impl RulesMask {
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether `pattern` produces a plant.
+    pub fn produces_plant(self, pattern: u8) -> bool {
+        self.0 & (1 << pattern) != 0
+    }
+
+    /// Iterate over the patterns which produce plants.
+    pub(crate) fn patterns(self) -> impl Iterator<Item = u8> {
+        (0..32).filter(move |&pattern| self.produces_plant(pattern))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InferenceError {
+    #[error("at least two consecutive states are required to infer rules")]
+    TooFewStates,
+    #[error("generation {generation} contradicts earlier rules for pattern {pattern:#07b} at pot {index}")]
+    Inconsistent {
+        generation: usize,
+        index: isize,
+        pattern: u8,
+    },
+    #[error("rules are ambiguous: patterns never observed: {0:?}")]
+    Ambiguous(Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::Input, tests::EXAMPLE};
+
+    fn state(pots: &str) -> State {
+        pots.parse().unwrap()
+    }
+
+    #[test]
+    fn infer_from_de_bruijn_sequence() {
+        let Input { rules, .. } = Input::new(EXAMPLE).unwrap();
+        // every 5-pot pattern appears exactly once in this sequence
+        let initial = state(".....#...##..#.#..###.#.##.#####....");
+        let next = initial.successor(&rules);
+        assert_eq!(Rules::infer(&[initial, next]).unwrap(), rules);
+    }
+
+    #[test]
+    fn infer_ambiguous() {
+        let Input { rules, initial } = Input::new(EXAMPLE).unwrap();
+        let initial = State::from_initial(initial);
+        let next = initial.successor(&rules);
+        assert!(matches!(
+            Rules::infer(&[initial, next]),
+            Err(InferenceError::Ambiguous(_)),
+        ));
+    }
+
+    #[test]
+    fn infer_inconsistent() {
+        // `..#..` is observed both to survive and to die
+        let states = vec![
+            state("..#.."),
+            state("..#.."),
+            state("..#.."),
+            state("....."),
+        ];
+        assert!(matches!(
+            Rules::infer(&states),
+            Err(InferenceError::Inconsistent { generation: 3, .. }),
+        ));
+    }
+
+    #[test]
+    fn infer_too_few_states() {
+        assert_eq!(
+            Rules::infer(&[state("#####")]),
+            Err(InferenceError::TooFewStates)
+        );
+    }
+}
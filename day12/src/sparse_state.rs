@@ -0,0 +1,226 @@
+use crate::{encode_as_u8::EncodeAsU8, input, Error, PotState, Rules, State};
+use bitvec::prelude::*;
+use std::{ops::Range, str::FromStr};
+
+/// A generation of pots, stored as the runs of consecutive plants.
+///
+/// This tracks the same pots as [`State`] and computes identical successors, but it takes
+/// space in proportion to the number of runs rather than the number of pots, and computes
+/// successors a run at a time. It suits sparse patterns spread over large ranges.
+///
+/// ```
+/// # use day12::{PotState, Rules, SparseState};
+/// let rules: Rules = "..#.. => #".parse().unwrap();
+/// let state: SparseState = "#...#".parse().unwrap();
+/// let next = state.successor(&rules);
+/// assert_eq!(next.bounds(), (-2, 6));
+/// assert_eq!(next.runs(), &[0..1, 4..5]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseState {
+    /// Sorted, disjoint, and never adjacent.
+    runs: Vec<Range<isize>>,
+    low: isize,
+    high: isize,
+}
+
+impl FromStr for SparseState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        input::InputParser::parse_pattern(s).map(SparseState::from_initial)
+    }
+}
+
+impl From<&State> for SparseState {
+    fn from(state: &State) -> Self {
+        let (low, high) = state.bounds();
+        SparseState::from_pots(low, high, state.iter_enumerated())
+    }
+}
+
+impl SparseState {
+    fn from_pots(low: isize, high: isize, pots: impl Iterator<Item = (isize, bool)>) -> Self {
+        let mut state = SparseState {
+            runs: Vec::new(),
+            low,
+            high,
+        };
+        for (index, has_plant) in pots {
+            if has_plant {
+                state.push_run(index..index + 1);
+            }
+        }
+        state
+    }
+
+    /// Append a run of plants beyond all existing runs, merging it with the last if they touch.
+    fn push_run(&mut self, run: Range<isize>) {
+        match self.runs.last_mut() {
+            Some(last) if last.end == run.start => last.end = run.end,
+            _ => self.runs.push(run),
+        }
+    }
+
+    /// The half-open ranges of pot indices which contain plants, in ascending order.
+    pub fn runs(&self) -> &[Range<isize>] {
+        &self.runs
+    }
+
+    /// The first index after `index` at which a run starts or ends.
+    fn next_boundary(&self, index: isize) -> Option<isize> {
+        let idx = self.runs.partition_point(|run| run.end <= index);
+        self.runs.get(idx).map(|run| {
+            if run.start > index {
+                run.start
+            } else {
+                run.end
+            }
+        })
+    }
+
+    /// Encode the window of length 5 centered on `index` as a value.
+    fn window(&self, index: isize) -> u8 {
+        [
+            self.get(index - 2),
+            self.get(index - 1),
+            self.get(index),
+            self.get(index + 1),
+            self.get(index + 2),
+        ]
+        .as_u8()
+    }
+}
+
+impl PotState for SparseState {
+    fn from_initial(initial: BitVec) -> Self {
+        let high = initial.len() as isize - 1;
+        SparseState::from_pots(
+            0,
+            high,
+            initial
+                .iter()
+                .by_val()
+                .enumerate()
+                .map(|(idx, has_plant)| (idx as isize, has_plant)),
+        )
+    }
+
+    fn bounds(&self) -> (isize, isize) {
+        (self.low, self.high)
+    }
+
+    fn get(&self, index: isize) -> bool {
+        let idx = self.runs.partition_point(|run| run.end <= index);
+        self.runs
+            .get(idx)
+            .map(|run| run.start <= index)
+            .unwrap_or_default()
+    }
+
+    fn successor(&self, rules: &Rules) -> Self {
+        let mut succ = SparseState {
+            runs: Vec::new(),
+            low: self.low - 2,
+            high: self.high + 2,
+        };
+
+        let mut index = succ.low;
+        while index <= succ.high {
+            let window = self.window(index);
+            // a window of all plants or no plants stays that way until it reaches a boundary
+            let end = if window == 0 || window == 0b11111 {
+                self.next_boundary(index + 2)
+                    .map_or(succ.high + 1, |boundary| boundary - 2)
+                    .min(succ.high + 1)
+            } else {
+                index + 1
+            };
+            if rules[window as usize] {
+                succ.push_run(index..end);
+            }
+            index = end;
+        }
+
+        succ
+    }
+
+    fn pot_sum(&self) -> isize {
+        self.runs
+            .iter()
+            .map(|run| (run.start + run.end - 1) * (run.end - run.start) / 2)
+            .sum()
+    }
+
+    fn live_count(&self) -> usize {
+        self.runs.iter().map(|run| run.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules_from_mask(mask: u32) -> Rules {
+        let mut rules = Rules::default();
+        for (pattern, rule) in rules.iter_mut().enumerate() {
+            *rule = mask & (1 << pattern) != 0;
+        }
+        rules
+    }
+
+    fn assert_same(sparse: &SparseState, dense: &State) {
+        let (low, high) = dense.bounds();
+        assert_eq!(sparse.bounds(), (low, high));
+        assert_eq!(sparse, &SparseState::from(dense));
+        for index in low - 3..=high + 3 {
+            assert_eq!(
+                PotState::get(sparse, index),
+                dense.get(index),
+                "pot {}",
+                index
+            );
+        }
+        assert_eq!(PotState::pot_sum(sparse), dense.pot_sum());
+        assert_eq!(PotState::live_count(sparse), dense.live_count());
+    }
+
+    #[test]
+    fn matches_dense() {
+        // a deterministic pseudo-random sequence of rules and initial states
+        let mut seed = 0x2018_u64;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            seed >> 33
+        };
+        let mut masks = vec![0, !0, 1, 1 << 31, 0x5555_5555, 0x1234_5678];
+        masks.extend((0..20).map(|_| next() as u32));
+        for mask in masks {
+            let rules = rules_from_mask(mask);
+            for len in [0, 1, 3, 8, 40].iter().copied() {
+                let initial: BitVec = (0..len).map(|_| next() % 3 == 0).collect();
+                let mut dense = State::from_initial(initial.clone());
+                let mut sparse = SparseState::from_initial(initial);
+                assert_same(&sparse, &dense);
+                for _ in 0..30 {
+                    dense = PotState::successor(&dense, &rules);
+                    sparse = sparse.successor(&rules);
+                    assert_same(&sparse, &dense);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn runs_stay_few() {
+        // two plants drifting left, one pot per generation
+        let rules: Rules = "...#. => #".parse().unwrap();
+        let mut state: SparseState = "#...#".parse().unwrap();
+        for _ in 0..10_000 {
+            state = state.successor(&rules);
+        }
+        assert_eq!(state.bounds(), (-20_000, 20_004));
+        assert_eq!(state.runs(), &[-10_000..-9_999, -9_996..-9_995]);
+        assert_eq!(state.pot_sum(), -19_996);
+    }
+}
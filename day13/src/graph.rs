@@ -0,0 +1,379 @@
+use crate::{Error, Track};
+use aoclib::geometry::{Direction, Point};
+use std::{collections::HashMap, str::FromStr};
+
+type TrackMap = aoclib::geometry::Map<Track>;
+
+/// The kinds of track at which a cart can change direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Curve,
+    Intersection,
+}
+
+impl NodeKind {
+    fn name(self) -> &'static str {
+        match self {
+            NodeKind::Curve => "curve",
+            NodeKind::Intersection => "intersection",
+        }
+    }
+}
+
+/// A curve or intersection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Node {
+    /// With the origin at the top left.
+    pub position: Point,
+    pub kind: NodeKind,
+}
+
+/// A straight run of track between two nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    /// Index into [`TrackGraph::nodes`].
+    pub from: usize,
+    /// The direction in which the run leaves `from`.
+    pub from_exit: Direction,
+    /// Index into [`TrackGraph::nodes`].
+    pub to: usize,
+    /// The direction in which the run leaves `to`, heading back toward `from`.
+    pub to_exit: Direction,
+    /// The number of ticks a cart takes to get from one end to the other.
+    pub length: usize,
+}
+
+/// The track as a graph: curves and intersections are nodes, and the straight runs
+/// between them are edges.
+///
+/// Nodes are listed in reading order. Each edge is listed once, from its end which
+/// comes first in reading order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Ways to write a [`TrackGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz's DOT language.
+    Dot,
+    Json,
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(Error::UnknownGraphFormat(s.to_string())),
+        }
+    }
+}
+
+impl TrackGraph {
+    /// Derive the graph of a track from which the carts have been removed.
+    pub(crate) fn new(track: &TrackMap) -> TrackGraph {
+        let height = track.height() as i32;
+        let mut nodes = Vec::new();
+        let mut exits = Vec::new();
+        let mut indices = HashMap::new();
+        for row in 0..height {
+            for x in 0..track.width() as i32 {
+                let point = Point::new(x, height - 1 - row);
+                let kind = match track[point] {
+                    Track::SlashCurve | Track::BackslashCurve => NodeKind::Curve,
+                    Track::Cross => NodeKind::Intersection,
+                    _ => continue,
+                };
+                indices.insert(point, nodes.len());
+                nodes.push(Node {
+                    position: Point::new(x, row),
+                    kind,
+                });
+                exits.push(node_exits(track, point));
+            }
+        }
+
+        let mut edges = Vec::new();
+        for (&point, &from) in &indices {
+            for &from_exit in &exits[from] {
+                let (end, to_exit, length) = match follow(track, point, from_exit) {
+                    Some(run) => run,
+                    None => continue,
+                };
+                let to = indices[&end];
+                // each run is found from both ends; a run whose far end doesn't lead back
+                // into it isn't really connected
+                if exits[to].contains(&to_exit)
+                    && (from, from_exit.deltas()) < (to, to_exit.deltas())
+                {
+                    edges.push(Edge {
+                        from,
+                        from_exit,
+                        to,
+                        to_exit,
+                        length,
+                    });
+                }
+            }
+        }
+        edges.sort_unstable_by_key(|edge| (edge.from, edge.to, edge.from_exit.deltas()));
+
+        TrackGraph { nodes, edges }
+    }
+
+    /// Render the graph in Graphviz's DOT language.
+    ///
+    /// Nodes are labeled with their positions, and edges with their lengths.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("graph track {\n");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let shape = match node.kind {
+                NodeKind::Curve => "ellipse",
+                NodeKind::Intersection => "box",
+            };
+            out.push_str(&format!(
+                "    n{} [label=\"{},{}\", shape={}];\n",
+                idx, node.position.x, node.position.y, shape
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    n{} -- n{} [label=\"{}\"];\n",
+                edge.from, edge.to, edge.length
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the graph as JSON: an object with `nodes` and `edges` arrays.
+    ///
+    /// Edges refer to nodes by their index in `nodes`.
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    "    {{\"x\": {}, \"y\": {}, \"kind\": \"{}\"}}",
+                    node.position.x,
+                    node.position.y,
+                    node.kind.name()
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                format!(
+                    "    {{\"from\": {}, \"from_exit\": \"{}\", \"to\": {}, \"to_exit\": \"{}\", \"length\": {}}}",
+                    edge.from,
+                    direction_name(edge.from_exit),
+                    edge.to,
+                    direction_name(edge.to_exit),
+                    edge.length
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+
+    /// Render the graph in `format`.
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Json => self.to_json(),
+        }
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// The directions in which a cart can leave the node at `point`.
+fn node_exits(track: &TrackMap, point: Point) -> Vec<Direction> {
+    use Direction::*;
+    match track[point] {
+        Track::Cross => Direction::iter().collect(),
+        Track::SlashCurve => curve_exits(track, point, [[Right, Down], [Left, Up]]),
+        Track::BackslashCurve => curve_exits(track, point, [[Left, Down], [Right, Up]]),
+        _ => Vec::new(),
+    }
+}
+
+/// A curve joins one of two pairs of its neighbors.
+///
+/// Curves can sit beside one another without being connected, so prefer the pair whose
+/// neighbors both lead back into the curve, and then the pair with fewer curves among
+/// them. Any remaining tie goes to the first pair.
+fn curve_exits(track: &TrackMap, point: Point, pairs: [[Direction; 2]; 2]) -> Vec<Direction> {
+    let neighbor = |direction: Direction| {
+        let mut neighbor = point;
+        neighbor += direction;
+        track.in_bounds(neighbor).then(|| track[neighbor])
+    };
+    let score = |pair: &[Direction; 2]| {
+        pair.iter()
+            .map(|&direction| match neighbor(direction) {
+                Some(Track::SlashCurve | Track::BackslashCurve) => Some(0),
+                Some(tile) if tile.connects(direction.reverse()) => Some(1),
+                _ => None,
+            })
+            .sum::<Option<usize>>()
+    };
+    let (first, second) = (score(&pairs[0]), score(&pairs[1]));
+    match (first, second) {
+        (None, None) => Vec::new(),
+        _ if first >= second => pairs[0].to_vec(),
+        _ => pairs[1].to_vec(),
+    }
+}
+
+/// Follow the straight run leaving `point` in `direction` to the next node.
+///
+/// Return the node, the direction in which the run leaves it, and the run's length,
+/// or `None` if the run ends without reaching a node.
+fn follow(
+    track: &TrackMap,
+    point: Point,
+    direction: Direction,
+) -> Option<(Point, Direction, usize)> {
+    let mut position = point;
+    let mut length = 0;
+    loop {
+        position += direction;
+        length += 1;
+        if !track.in_bounds(position) {
+            return None;
+        }
+        match (track[position], direction) {
+            (Track::Horizontal, Direction::Left | Direction::Right)
+            | (Track::Vertical, Direction::Up | Direction::Down) => {}
+            (Track::SlashCurve | Track::BackslashCurve | Track::Cross, _) => {
+                return Some((position, direction.reverse(), length))
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Map;
+
+    const EXAMPLE_PART1: &str = r"
+/->-\........
+|   |  /----\
+| /-+--+-\  |
+| | |  | v  |
+\-+-/  \-+--/
+  \------/...
+";
+
+    fn graph(input: &str) -> TrackGraph {
+        let mut map: Map = input.parse().unwrap();
+        map.extract_carts();
+        TrackGraph::new(&map.track)
+    }
+
+    #[test]
+    fn example_graph() {
+        let graph = graph(&EXAMPLE_PART1[1..]);
+        assert_eq!(graph.nodes.len(), 16);
+        assert_eq!(
+            graph
+                .nodes
+                .iter()
+                .filter(|node| node.kind == NodeKind::Intersection)
+                .count(),
+            4
+        );
+        // curves have two edges and intersections four
+        assert_eq!(graph.edges.len(), 20);
+        assert_eq!(
+            graph.edges[0],
+            Edge {
+                from: 0,
+                from_exit: Direction::Right,
+                to: 1,
+                to_exit: Direction::Left,
+                length: 4,
+            }
+        );
+
+        // every straight piece of track lies on exactly one edge
+        let straights = EXAMPLE_PART1
+            .chars()
+            .filter(|c| "-|<>^v".contains(*c))
+            .count();
+        let total: usize = graph.edges.iter().map(|edge| edge.length).sum();
+        assert_eq!(total, straights + graph.edges.len());
+    }
+
+    #[test]
+    fn adjacent_curves() {
+        let graph = graph(
+            r"/-\/-\
+| || |
+\-/\-/
+",
+        );
+        assert_eq!(graph.nodes.len(), 8);
+        assert_eq!(graph.edges.len(), 8);
+        // the loops touch, but aren't connected
+        assert!(graph
+            .edges
+            .iter()
+            .all(|edge| (graph.nodes[edge.from].position.x < 3)
+                == (graph.nodes[edge.to].position.x < 3)));
+    }
+
+    #[test]
+    fn formats() {
+        let graph = graph(
+            r"/--\
+|  |
+\--/
+",
+        );
+        assert_eq!(
+            graph.to_dot(),
+            r#"graph track {
+    n0 [label="0,0", shape=ellipse];
+    n1 [label="3,0", shape=ellipse];
+    n2 [label="0,2", shape=ellipse];
+    n3 [label="3,2", shape=ellipse];
+    n0 -- n1 [label="3"];
+    n0 -- n2 [label="2"];
+    n1 -- n3 [label="2"];
+    n2 -- n3 [label="3"];
+}
+"#
+        );
+        let json = graph.to_json();
+        assert!(
+            json.starts_with("{\n  \"nodes\": [\n    {\"x\": 0, \"y\": 0, \"kind\": \"curve\"},\n")
+        );
+        assert!(json.contains(
+            "{\"from\": 0, \"from_exit\": \"right\", \"to\": 1, \"to_exit\": \"left\", \"length\": 3}"
+        ));
+        assert!(json.ends_with("]\n}\n"));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{Collision, Track};
+use aoclib::geometry::{tile::DisplayWidth, Point};
+use std::fmt;
+
+type TrackMap = aoclib::geometry::Map<Track>;
+
+#[derive(Debug, Clone, Copy)]
+enum HeatTile {
+    Track(Track),
+    Collisions(usize),
+}
+
+impl Default for HeatTile {
+    fn default() -> Self {
+        HeatTile::Track(Track::Empty)
+    }
+}
+
+/// Collision counts are drawn as single digits, or `*` for ten or more.
+impl fmt::Display for HeatTile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            HeatTile::Track(track) => write!(f, "{}", track),
+            HeatTile::Collisions(count) if count < 10 => write!(f, "{}", count),
+            HeatTile::Collisions(_) => f.write_str("*"),
+        }
+    }
+}
+
+impl DisplayWidth for HeatTile {
+    const DISPLAY_WIDTH: usize = 1;
+}
+
+/// The track, with the number of collisions at each point where any occurred.
+pub struct Heatmap {
+    map: aoclib::geometry::Map<HeatTile>,
+    collisions: Vec<Collision>,
+}
+
+impl Heatmap {
+    pub(crate) fn new(track: &TrackMap, collisions: Vec<Collision>) -> Heatmap {
+        let (width, height) = (track.width(), track.height());
+        let mut map = aoclib::geometry::Map::new(width, height);
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let point = Point::new(x, y);
+                map[point] = HeatTile::Track(track[point]);
+            }
+        }
+
+        for collision in &collisions {
+            let mut point = collision.position;
+            point.y = height as i32 - 1 - point.y;
+            let tile = &mut map[point];
+            *tile = match *tile {
+                HeatTile::Collisions(count) => HeatTile::Collisions(count + 1),
+                HeatTile::Track(_) => HeatTile::Collisions(1),
+            };
+        }
+        Heatmap { map, collisions }
+    }
+
+    /// Convert between the map's coordinates and those with the origin at the top left.
+    fn flip_y(&self, mut point: Point) -> Point {
+        point.y = self.map.height() as i32 - 1 - point.y;
+        point
+    }
+
+    /// Every collision, in the order they occurred.
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// The number of collisions at `position`, with the origin at the top left.
+    pub fn count_at(&self, position: Point) -> usize {
+        let point = self.flip_y(position);
+        if !self.map.in_bounds(point) {
+            return 0;
+        }
+        match self.map[point] {
+            HeatTile::Collisions(count) => count,
+            HeatTile::Track(_) => 0,
+        }
+    }
+
+    /// The greatest number of collisions at any single point.
+    pub fn max_count(&self) -> usize {
+        self.map
+            .iter()
+            .map(|tile| match *tile {
+                HeatTile::Collisions(count) => count,
+                HeatTile::Track(_) => 0,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Render the heatmap as an RGB image with one pixel per map tile.
+    ///
+    /// Track is grey; collisions are red, brighter where they're more frequent.
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, path: &std::path::Path) -> Result<(), crate::Error> {
+        let (width, height) = (self.map.width(), self.map.height());
+        let max_count = self.max_count().max(1);
+        let mut data = Vec::with_capacity(width * height * 3);
+        for row in 0..height as i32 {
+            for x in 0..width as i32 {
+                let point = self.flip_y(Point::new(x, row));
+                let pixel = match self.map[point] {
+                    HeatTile::Track(Track::Empty) => [0, 0, 0],
+                    HeatTile::Track(_) => [96, 96, 96],
+                    HeatTile::Collisions(count) => [(128 + 127 * count / max_count) as u8, 0, 0],
+                };
+                data.extend_from_slice(&pixel);
+            }
+        }
+
+        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Heatmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collision_heatmap, collision_history, DEFAULT_TICK_LIMIT};
+
+    const EXAMPLE_PART1: &str = r"
+/->-\........
+|   |  /----\
+| /-+--+-\  |
+| | |  | v  |
+\-+-/  \-+--/
+  \------/...
+";
+
+    #[test]
+    fn single_collision() {
+        let history = collision_history(&EXAMPLE_PART1[1..], DEFAULT_TICK_LIMIT).unwrap();
+        assert_eq!(
+            history,
+            vec![Collision {
+                tick: 14,
+                position: Point::new(7, 3)
+            }]
+        );
+
+        let heatmap = collision_heatmap(&EXAMPLE_PART1[1..], DEFAULT_TICK_LIMIT).unwrap();
+        assert_eq!(heatmap.count_at(Point::new(7, 3)), 1);
+        assert_eq!(heatmap.count_at(Point::new(9, 3)), 0);
+        assert_eq!(heatmap.max_count(), 1);
+        let rendered = heatmap.to_string();
+        let row = rendered.lines().nth(3).unwrap();
+        assert_eq!(row.trim_end(), "| | |  1 |  |");
+    }
+
+    const EXAMPLE_PART2: &str = "
+/>-<\\..
+|   |..
+| /<+-\\
+| | | v
+\\>+</ |
+  |   ^
+  \\<->/
+";
+
+    #[test]
+    fn every_collision_counted() {
+        let heatmap = collision_heatmap(&EXAMPLE_PART2[1..], DEFAULT_TICK_LIMIT).unwrap();
+        // nine carts: four crashes leave one survivor
+        assert_eq!(heatmap.collisions().len(), 4);
+        assert!(heatmap
+            .collisions()
+            .iter()
+            .all(|collision| collision.tick <= 3));
+        let total: usize = (0..7)
+            .flat_map(|y| (0..7).map(move |x| Point::new(x, y)))
+            .map(|point| heatmap.count_at(point))
+            .sum();
+        assert_eq!(total, 4);
+    }
+
+    // two carts, each circling its own loop forever
+    const SEPARATE_LOOPS: &str = r"
+/>-\./<-\
+|..|.|..|
+\--/.\--/
+";
+
+    #[test]
+    fn cycles_end_the_history() {
+        let history = collision_history(&SEPARATE_LOOPS[1..], DEFAULT_TICK_LIMIT).unwrap();
+        assert!(history.is_empty());
+    }
+}
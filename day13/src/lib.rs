@@ -1,5 +1,16 @@
+mod graph;
+mod heatmap;
+mod signals;
+
 use aoclib::geometry::{tile::DisplayWidth, Direction, Point};
-use std::{cmp::Ordering, fmt, path::Path, str::FromStr};
+pub use graph::{Edge, GraphFormat, Node, NodeKind, TrackGraph};
+pub use heatmap::Heatmap;
+use rayon::prelude::*;
+use signals::{JunctionState, Schedule};
+use std::{cmp::Ordering, collections::HashMap, fmt, path::Path, str::FromStr};
+
+/// The default maximum number of ticks to simulate while searching for the last cart.
+pub const DEFAULT_TICK_LIMIT: usize = 1_000_000;
 
 #[derive(Debug, Clone, Copy)]
 enum Track {
@@ -49,6 +60,21 @@ impl fmt::Display for Track {
     }
 }
 
+impl Track {
+    /// Whether a cart on this track could leave it in `direction`.
+    ///
+    /// Curves are treated as connecting in every direction: which pair of edges a curve
+    /// joins depends on its neighbors, and overestimating connectivity is harmless.
+    fn connects(self, direction: Direction) -> bool {
+        match self {
+            Track::Empty => false,
+            Track::Horizontal => matches!(direction, Direction::Left | Direction::Right),
+            Track::Vertical => matches!(direction, Direction::Up | Direction::Down),
+            Track::SlashCurve | Track::BackslashCurve | Track::Cross | Track::Cart(_) => true,
+        }
+    }
+}
+
 impl DisplayWidth for Track {
     const DISPLAY_WIDTH: usize = 1;
 }
@@ -59,18 +85,36 @@ impl Default for Track {
     }
 }
 
-struct Map(aoclib::geometry::Map<Track>);
+struct Map {
+    track: aoclib::geometry::Map<Track>,
+    /// Traffic signals; empty unless the input has a signals section.
+    schedule: Schedule,
+}
 
-impl Map {
-    fn load(input: &Path) -> Result<Self, Error> {
-        let file = std::fs::File::open(input)?;
-        let reader = std::io::BufReader::new(file);
-        Ok(Self(aoclib::geometry::Map::try_from(reader)?))
+impl FromStr for Map {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (track, signals) = signals::split_input(s);
+        // the blank line before the signals section isn't part of the map
+        let track = if signals.is_some() {
+            track.trim_end_matches(|c| c == '\n' || c == '\r')
+        } else {
+            track
+        };
+        let track = aoclib::geometry::Map::try_from(std::io::Cursor::new(track))?;
+        let schedule = match signals {
+            Some(section) => signals::parse_schedule(section, &track)?,
+            None => Schedule::new(),
+        };
+        Ok(Map { track, schedule })
     }
+}
 
+impl Map {
     fn extract_carts(&mut self) -> Carts {
         let mut carts = Vec::new();
-        self.0.for_each_point_mut(|track, position| {
+        self.track.for_each_point_mut(|track, position| {
             if let Track::Cart(direction) = *track {
                 *track = match direction {
                     Direction::Right | Direction::Left => Track::Horizontal,
@@ -79,9 +123,38 @@ impl Map {
                 carts.push(Cart::new(direction, position));
             }
         });
-        debug_assert!(!self.0.iter().any(|&track| matches!(track, Track::Cart(_))));
+        debug_assert!(!self
+            .track
+            .iter()
+            .any(|&track| matches!(track, Track::Cart(_))));
+
+        let map: &Map = self;
+        Carts {
+            map,
+            carts,
+            junctions: JunctionState::new(&map.schedule),
+        }
+    }
 
-        Carts { map: self, carts }
+    /// Label every piece of track connected to `origin` with `label`.
+    fn label_component(&self, origin: Point, label: usize, labels: &mut HashMap<Point, usize>) {
+        let mut stack = vec![origin];
+        labels.insert(origin, label);
+        while let Some(point) = stack.pop() {
+            for direction in Direction::iter() {
+                if !self.track[point].connects(direction) {
+                    continue;
+                }
+                let mut neighbor = point;
+                neighbor += direction;
+                if self.track.in_bounds(neighbor)
+                    && self.track[neighbor].connects(direction.reverse())
+                    && labels.insert(neighbor, label).is_none()
+                {
+                    stack.push(neighbor);
+                }
+            }
+        }
     }
 }
 
@@ -124,6 +197,15 @@ impl PartialOrd for Cart {
     }
 }
 
+/// A crash between carts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Collision {
+    /// The tick in which the crash occurred, counting from 1.
+    pub tick: usize,
+    /// Where the crash occurred, with the origin at the top left.
+    pub position: Point,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Turn {
     Left,
@@ -151,6 +233,7 @@ impl Default for Turn {
 struct Carts<'a> {
     map: &'a Map,
     carts: Vec<Cart>,
+    junctions: JunctionState<'a>,
 }
 
 impl<'a> Carts<'a> {
@@ -158,7 +241,7 @@ impl<'a> Carts<'a> {
     ///
     /// Return `new_position`.
     fn advance(cart: &mut Cart, map: &Map) -> Point {
-        cart.direction = match (cart.direction, map.0[cart.position]) {
+        cart.direction = match (cart.direction, map.track[cart.position]) {
             (_, Track::Empty) => unreachable!("cart cannot travel off the rails"),
             (_, Track::Cart(_)) => unreachable!("carts most not be on the map"),
             (Direction::Right | Direction::Left, Track::Vertical)
@@ -194,7 +277,8 @@ impl<'a> Carts<'a> {
 
     /// Advance the simulation by one step.
     ///
-    /// If two carts crash, return the points where the crash occurred.
+    /// Carts held by a signal stay where they are. If two carts crash, return the points
+    /// where the crash occurred.
     fn tick(&mut self) -> Vec<Point> {
         self.carts.sort_unstable();
         let mut collisions = Vec::new();
@@ -206,7 +290,13 @@ impl<'a> Carts<'a> {
                     debug_assert!(collisions.contains(&cart.position));
                     continue;
                 }
-                Self::advance(cart, self.map)
+                let mut moved = *cart;
+                let new_position = Self::advance(&mut moved, self.map);
+                if !self.junctions.allows(new_position, moved.direction) {
+                    continue;
+                }
+                *cart = moved;
+                new_position
             };
 
             // there should only be one dead cart at any given point, but it can't hurt to check all of them.
@@ -228,6 +318,8 @@ impl<'a> Carts<'a> {
             }
         }
 
+        self.junctions.advance();
+
         // clean up the carts list to get rid of the dead
         let old_cart_count = self.carts.len();
 
@@ -246,37 +338,175 @@ impl<'a> Carts<'a> {
 
     /// Adjust a point's y orientation to put the implicit origin at the top, instead of the bottom.
     fn flip_y(&self, mut point: Point) -> Point {
-        point.y = self.map.0.height() as i32 - 1 - point.y;
+        point.y = self.map.track.height() as i32 - 1 - point.y;
         point
     }
 
-    /// Loop until a collision is produced. Return the point of impact.
-    fn run_until_first_collision(&mut self) -> Point {
+    /// Loop until a collision is produced.
+    ///
+    /// Return the point of impact and the number of ticks elapsed, including the tick
+    /// in which the collision occurred.
+    fn run_until_first_collision(&mut self) -> (Point, usize) {
+        let mut ticks = 0;
         let mut collisions;
         loop {
             collisions = self.tick();
+            ticks += 1;
             if !collisions.is_empty() {
                 break;
             }
         }
-        self.flip_y(collisions[0])
+        (self.flip_y(collisions[0]), ticks)
     }
 
-    /// Loop until only one cart remains. Return the position of the final cart.
-    fn run_until_last_cart(&mut self) -> Result<Point, Error> {
-        while self.carts.len() > 1 {
-            self.tick();
-        }
+    /// Loop until only one cart remains.
+    ///
+    /// Return the position of the final cart and the number of ticks elapsed.
+    ///
+    /// Surviving carts might never collide, so this gives up after `tick_limit` ticks.
+    /// It also watches for the complete cart state to repeat, which means that no further
+    /// collisions will ever occur, so most such inputs fail long before the limit.
+    fn run_until_last_cart(&mut self, tick_limit: usize) -> Result<(Point, usize), Error> {
+        let ticks = self.run_until_settled(tick_limit)?;
         if self.carts.is_empty() {
             return Err(Error::NoSolution);
         }
-        Ok(self.flip_y(self.carts[0].position))
+        Ok((self.flip_y(self.carts[0].position), ticks))
+    }
+
+    /// Loop until at most one cart remains, returning the number of ticks elapsed.
+    ///
+    /// This fails in the same ways as [`run_until_last_cart`](Self::run_until_last_cart),
+    /// but succeeds if every cart is destroyed.
+    fn run_until_settled(&mut self, tick_limit: usize) -> Result<usize, Error> {
+        self.run_until_settled_recording(tick_limit, |_| {})
+    }
+
+    /// Like [`run_until_settled`](Self::run_until_settled), but report every collision.
+    fn run_until_settled_recording(
+        &mut self,
+        tick_limit: usize,
+        mut on_collision: impl FnMut(Collision),
+    ) -> Result<usize, Error> {
+        // Brent's cycle detection: compare against a snapshot which is replaced
+        // whenever the distance from it reaches the next power of two.
+        // Signals are part of the state, so the snapshot includes their phase.
+        let mut snapshot = self.carts.clone();
+        snapshot.sort_unstable();
+        let mut snapshot_phase = self.junctions.phase();
+        let mut power = 1;
+        let mut distance = 0;
+
+        let mut ticks = 0;
+        while self.carts.len() > 1 {
+            if ticks >= tick_limit {
+                return Err(Error::TickLimitExceeded(ticks));
+            }
+            let collisions = self.tick();
+            ticks += 1;
+            self.carts.sort_unstable();
+
+            for &position in &collisions {
+                on_collision(Collision {
+                    tick: ticks,
+                    position: self.flip_y(position),
+                });
+            }
+
+            if !collisions.is_empty() {
+                // the state space just shrank; start looking for a new cycle
+                snapshot.clone_from(&self.carts);
+                snapshot_phase = self.junctions.phase();
+                power = 1;
+                distance = 0;
+                continue;
+            }
+
+            distance += 1;
+            if self.carts == snapshot && self.junctions.phase() == snapshot_phase {
+                return Err(Error::CycleDetected {
+                    ticks,
+                    period: distance,
+                });
+            }
+            if distance == power {
+                snapshot.clone_from(&self.carts);
+                snapshot_phase = self.junctions.phase();
+                power *= 2;
+                distance = 0;
+            }
+        }
+        Ok(ticks)
+    }
+
+    /// Split the carts into groups on disconnected track, which can never interact.
+    fn partition(self) -> Vec<Carts<'a>> {
+        let mut labels = HashMap::new();
+        let mut groups: Vec<Vec<Cart>> = Vec::new();
+        for cart in self.carts {
+            let label = match labels.get(&cart.position) {
+                Some(&label) => label,
+                None => {
+                    let label = groups.len();
+                    groups.push(Vec::new());
+                    self.map.label_component(cart.position, label, &mut labels);
+                    label
+                }
+            };
+            groups[label].push(cart);
+        }
+        let map = self.map;
+        let junctions = self.junctions;
+        groups
+            .into_iter()
+            .map(|carts| Carts {
+                map,
+                carts,
+                junctions: junctions.clone(),
+            })
+            .collect()
+    }
+
+    /// Like [`run_until_last_cart`](Self::run_until_last_cart), but simulate each group
+    /// of carts on disconnected track in parallel.
+    ///
+    /// Carts on different track can never collide, so if survivors remain on more than one
+    /// track, this fails with [`Error::DisconnectedSurvivors`].
+    fn run_until_last_cart_parallel(self, tick_limit: usize) -> Result<(Point, usize), Error> {
+        let settled = self
+            .partition()
+            .into_par_iter()
+            .map(|mut carts| {
+                let ticks = carts.run_until_settled(tick_limit)?;
+                Ok((carts, ticks))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // the combined simulation ends once the slowest group settles
+        let ticks = settled
+            .iter()
+            .map(|(_, ticks)| *ticks)
+            .max()
+            .unwrap_or_default();
+        let mut survivors: Vec<_> = settled
+            .into_iter()
+            .filter(|(carts, _)| !carts.carts.is_empty())
+            .collect();
+        if survivors.len() > 1 {
+            return Err(Error::DisconnectedSurvivors(survivors.len()));
+        }
+        let (mut survivor, survivor_ticks) = survivors.pop().ok_or(Error::NoSolution)?;
+        // the last cart keeps driving while the other groups finish colliding
+        for _ in survivor_ticks..ticks {
+            survivor.tick();
+        }
+        Ok((survivor.flip_y(survivor.carts[0].position), ticks))
     }
 }
 
 impl<'a> fmt::Display for Carts<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut map = self.map.0.clone();
+        let mut map = self.map.track.clone();
         for cart in self.carts.iter() {
             map[cart.position] = Track::Cart(cart.direction);
         }
@@ -285,24 +515,116 @@ impl<'a> fmt::Display for Carts<'a> {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let mut map = Map::load(input)?;
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with(input, false)
+}
+
+/// Find the first collision.
+///
+/// When `verbose`, also give the tick in which it occurred.
+pub fn part1_with(input: &str, verbose: bool) -> Result<String, Error> {
+    let mut map: Map = input.parse()?;
     let mut carts = map.extract_carts();
-    let first_collision = carts.run_until_first_collision();
+    let (first_collision, ticks) = carts.run_until_first_collision();
 
-    println!(
+    let mut out = format!(
         "first collision at {},{}",
         first_collision.x, first_collision.y
     );
-    Ok(())
+    if verbose {
+        out.push_str(&format!(" (tick {})", ticks));
+    }
+    Ok(out)
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let mut map = Map::load(input)?;
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+/// Find the position of the last cart remaining, and the number of ticks that took.
+///
+/// Fails with [`Error::TickLimitExceeded`] if more than one cart remains after `tick_limit` ticks,
+/// or with [`Error::CycleDetected`] if the carts fall into a loop in which they never collide.
+pub fn last_cart(input: &str, tick_limit: usize) -> Result<(Point, usize), Error> {
+    let mut map: Map = input.parse()?;
     let mut carts = map.extract_carts();
-    let last_cart = carts.run_until_last_cart()?;
+    carts.run_until_last_cart(tick_limit)
+}
 
-    println!("last cart at {},{}", last_cart.x, last_cart.y);
-    Ok(())
+/// Find the position of the last cart remaining, simulating carts on disconnected track in parallel.
+///
+/// This fails like [`last_cart`], except that survivors on separate tracks which can never
+/// collide produce [`Error::DisconnectedSurvivors`].
+pub fn last_cart_parallel(input: &str, tick_limit: usize) -> Result<(Point, usize), Error> {
+    let mut map: Map = input.parse()?;
+    let carts = map.extract_carts();
+    carts.run_until_last_cart_parallel(tick_limit)
+}
+
+/// Run the simulation as in part 2, recording every collision.
+///
+/// Once the carts enter a cycle, no further collisions can occur, so unlike [`last_cart`]
+/// this treats [`Error::CycleDetected`] as the end of the simulation. It still fails with
+/// [`Error::TickLimitExceeded`] if more than one cart remains after `tick_limit` ticks.
+pub fn collision_history(input: &str, tick_limit: usize) -> Result<Vec<Collision>, Error> {
+    let mut map: Map = input.parse()?;
+    record_collisions(&mut map, tick_limit)
+}
+
+fn record_collisions(map: &mut Map, tick_limit: usize) -> Result<Vec<Collision>, Error> {
+    let mut carts = map.extract_carts();
+    let mut collisions = Vec::new();
+    match carts.run_until_settled_recording(tick_limit, |collision| collisions.push(collision)) {
+        Ok(_) | Err(Error::CycleDetected { .. }) => Ok(collisions),
+        Err(err) => Err(err),
+    }
+}
+
+/// Run the simulation as in part 2, and count the collisions on each piece of track.
+///
+/// This fails like [`collision_history`].
+pub fn collision_heatmap(input: &str, tick_limit: usize) -> Result<Heatmap, Error> {
+    let mut map: Map = input.parse()?;
+    let collisions = record_collisions(&mut map, tick_limit)?;
+    Ok(Heatmap::new(&map.track, collisions))
+}
+
+pub fn collision_heatmap_from_str(input: &str) -> Result<String, Error> {
+    let heatmap = collision_heatmap(input, DEFAULT_TICK_LIMIT)?;
+    Ok(format!(
+        "{} collisions:\n{}",
+        heatmap.collisions().len(),
+        heatmap
+    ))
+}
+
+/// Derive the graph of the track, ignoring the carts and any signals.
+pub fn track_graph(input: &str) -> Result<TrackGraph, Error> {
+    let mut map: Map = input.parse()?;
+    map.extract_carts();
+    Ok(TrackGraph::new(&map.track))
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with(input, false)
+}
+
+/// Find the position of the last cart remaining.
+///
+/// When `verbose`, also give the number of ticks until only it remained.
+pub fn part2_with(input: &str, verbose: bool) -> Result<String, Error> {
+    let (last_cart, ticks) = last_cart_parallel(input, DEFAULT_TICK_LIMIT)?;
+
+    let mut out = format!("last cart at {},{}", last_cart.x, last_cart.y);
+    if verbose {
+        out.push_str(&format!(" (after {} ticks)", ticks));
+    }
+    Ok(out)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -315,11 +637,29 @@ pub enum Error {
     UnexpectedInput(String),
     #[error(transparent)]
     MapConversion(#[from] aoclib::geometry::map::MapConversionErr),
+    #[error("more than one cart remains after {0} ticks")]
+    TickLimitExceeded(usize),
+    #[error(
+        "carts entered a cycle of {period} ticks without colliding (detected at tick {ticks})"
+    )]
+    CycleDetected { ticks: usize, period: usize },
+    #[error("{0} carts survive on disconnected tracks and can never collide")]
+    DisconnectedSurvivors(usize),
+    #[error("malformed signal: {0:?}")]
+    InvalidSignal(String),
+    #[error("signal at {x},{y} is not on a junction")]
+    NotAJunction { x: i32, y: i32 },
+    #[error("unknown graph format {0:?}; expected \"dot\" or \"json\"")]
+    UnknownGraphFormat(String),
+    #[cfg(feature = "png")]
+    #[error("encoding png")]
+    Png(#[from] png::EncodingError),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use util::{assert_maps_eq, map_diff::trim_lines};
 
     const EXAMPLE_PART2: &str = "
 />-<\\..
@@ -336,25 +676,244 @@ mod tests {
         &EXAMPLE_PART2[1..]
     }
 
+    // the example's state at the start, and after each tick
+    const EXAMPLE_PART2_TICKS: &[&str] = &[
+        r"
+/>-<\
+|   |
+| /<+-\
+| | | v
+\>+</ |
+  |   ^
+  \<->/
+",
+        r"
+/---\
+|   |
+| v-+-\
+| | | |
+\-+-/ |
+  |   |
+  ^---^
+",
+        r"
+/---\
+|   |
+| /-+-\
+| v | |
+\-+-/ |
+  ^   ^
+  \---/
+",
+        r"
+/---\
+|   |
+| /-+-\
+| | | |
+\-+-/ ^
+  |   |
+  \---/
+",
+    ];
+
     #[test]
     fn test_example_part2() {
-        let mut map = Map(
-            aoclib::geometry::Map::<Track>::try_from(std::io::Cursor::new(example_part2()))
-                .unwrap(),
-        );
-        // we're going to run this simulation twice: once to show debug output, once to show correct
-        // behavior of the actual user function
+        let mut map: Map = example_part2().parse().unwrap();
+        // we're going to run this simulation twice: once to check each intermediate state,
+        // once to show correct behavior of the actual user function
         let mut carts = map.extract_carts();
         let mut carts2 = carts.clone();
 
-        eprintln!("{}", &carts);
-
-        while carts.carts.len() > 1 {
-            carts.tick();
-            eprintln!("{}", &carts);
+        for (tick, expected) in EXAMPLE_PART2_TICKS.iter().enumerate() {
+            if tick > 0 {
+                carts.tick();
+            }
+            assert_maps_eq!(expected[1..], carts.to_string(), "after {} ticks", tick);
         }
 
+        assert_eq!(carts.carts.len(), 1);
         assert_eq!(carts.flip_y(carts.carts[0].position), Point::new(6, 4));
-        assert_eq!(carts2.run_until_last_cart().unwrap(), Point::new(6, 4));
+        assert_eq!(
+            carts2.run_until_last_cart(DEFAULT_TICK_LIMIT).unwrap(),
+            (Point::new(6, 4), 3)
+        );
+        assert_eq!(part2_from_str(example_part2()).unwrap(), "last cart at 6,4");
+        assert_eq!(
+            part2_with(example_part2(), true).unwrap(),
+            "last cart at 6,4 (after 3 ticks)"
+        );
+    }
+
+    #[test]
+    fn carts_snapshot() {
+        let mut map: Map = example_part2().parse().unwrap();
+        let mut carts = map.extract_carts();
+        insta::assert_snapshot!(trim_lines(&carts.to_string()), @r###"
+        />-<\
+        |   |
+        | /<+-\
+        | | | v
+        \>+</ |
+          |   ^
+          \<->/
+        "###);
+
+        carts.tick();
+        insta::assert_snapshot!(trim_lines(&carts.to_string()), @r###"
+        /---\
+        |   |
+        | v-+-\
+        | | | |
+        \-+-/ |
+          |   |
+          ^---^
+        "###);
+    }
+
+    #[test]
+    fn tick_limit() {
+        assert!(matches!(
+            last_cart(example_part2(), 2),
+            Err(Error::TickLimitExceeded(2))
+        ));
+        assert_eq!(
+            last_cart(example_part2(), 3).unwrap(),
+            (Point::new(6, 4), 3)
+        );
+    }
+
+    // two carts, each circling its own loop forever
+    const SEPARATE_LOOPS: &str = r"
+/>-\./<-\
+|..|.|..|
+\--/.\--/
+";
+
+    #[test]
+    fn separate_loops_cycle() {
+        match last_cart(&SEPARATE_LOOPS[1..], DEFAULT_TICK_LIMIT) {
+            Err(Error::CycleDetected { ticks, period }) => {
+                assert_eq!(period, 10);
+                assert!(ticks < 100, "detected after {} ticks", ticks);
+            }
+            other => panic!("expected cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn separate_loops_parallel() {
+        assert!(matches!(
+            last_cart_parallel(&SEPARATE_LOOPS[1..], DEFAULT_TICK_LIMIT),
+            Err(Error::DisconnectedSurvivors(2))
+        ));
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        assert_eq!(
+            last_cart_parallel(example_part2(), DEFAULT_TICK_LIMIT).unwrap(),
+            last_cart(example_part2(), DEFAULT_TICK_LIMIT).unwrap(),
+        );
+    }
+
+    // the left loop's carts collide on tick 3; the right loop's cart keeps driving meanwhile
+    const TWO_LOOPS: &str = r"
+/>----<\./>-\
+|......|.|..|
+\------/.\--/
+";
+
+    #[test]
+    fn partition_two_loops() {
+        let mut map: Map = TWO_LOOPS[1..].parse().unwrap();
+        let groups = map.extract_carts().partition();
+        let mut sizes: Vec<_> = groups.iter().map(|group| group.carts.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 2]);
+    }
+
+    #[test]
+    fn survivor_keeps_driving() {
+        let expect = (Point::new(12, 1), 3);
+        assert_eq!(
+            last_cart(&TWO_LOOPS[1..], DEFAULT_TICK_LIMIT).unwrap(),
+            expect
+        );
+        assert_eq!(
+            last_cart_parallel(&TWO_LOOPS[1..], DEFAULT_TICK_LIMIT).unwrap(),
+            expect
+        );
+    }
+
+    const EXAMPLE_PART1: &str = r"
+/->-\........
+|   |  /----\
+| /-+--+-\  |
+| | |  | v  |
+\-+-/  \-+--/
+  \------/...
+";
+
+    const SIGNALED: &str = "\
+..v..
+..|..
+>-+--
+..|..
+";
+
+    #[test]
+    fn signals_hold_carts() {
+        // without a signal, both carts reach the junction on tick 2
+        assert_eq!(part1_from_str(SIGNALED).unwrap(), "first collision at 2,2");
+        assert_eq!(
+            part1_with(SIGNALED, true).unwrap(),
+            "first collision at 2,2 (tick 2)"
+        );
+
+        // the signal holds the southbound cart, so the eastbound one turns north into it
+        let input = format!("{}\nsignals:\n2,2: 3\n", SIGNALED);
+        let map: Map = input.parse().unwrap();
+        assert_eq!(map.schedule.len(), 1);
+        assert_eq!(
+            part1_with(&input, true).unwrap(),
+            "first collision at 2,1 (tick 3)"
+        );
+    }
+
+    #[test]
+    fn invalid_signals() {
+        let with_signal = |signal: &str| format!("{}\nsignals:\n{}\n", SIGNALED, signal);
+        assert!(matches!(
+            with_signal("0,0: 3").parse::<Map>(),
+            Err(Error::NotAJunction { x: 0, y: 0 })
+        ));
+        assert!(matches!(
+            with_signal("2,9: 3").parse::<Map>(),
+            Err(Error::NotAJunction { x: 2, y: 9 })
+        ));
+        assert!(matches!(
+            with_signal("2,2: 0").parse::<Map>(),
+            Err(Error::InvalidSignal(_))
+        ));
+        assert!(matches!(
+            with_signal("2;2 3").parse::<Map>(),
+            Err(Error::InvalidSignal(_))
+        ));
+    }
+
+    #[test]
+    fn test_example_part1() {
+        let mut map: Map = EXAMPLE_PART1[1..].parse().unwrap();
+        let mut carts = map.extract_carts();
+        assert_eq!(carts.run_until_first_collision(), (Point::new(7, 3), 14));
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file =
+            util::CrlfInputFile::new("day13-crlf", &format!("{}\nsignals:\n2,2: 3\n", SIGNALED))
+                .unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(part1_from_str(&input).unwrap(), "first collision at 2,1");
     }
 }
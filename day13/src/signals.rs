@@ -0,0 +1,120 @@
+//! Traffic signals at junctions: an extension to the puzzle.
+//!
+//! A signal alternates between letting carts into its junction horizontally and vertically,
+//! for `period` ticks each, beginning with horizontal. A cart which would enter the junction
+//! against the signal waits where it is instead.
+//!
+//! Signals are listed after the map, in a section headed `signals:`, one per line as
+//! `x,y: period`, with the origin at the top left:
+//!
+//! ```text
+//! ..v..
+//! ..|..
+//! >-+--
+//! ..|..
+//!
+//! signals:
+//! 2,2: 3
+//! ```
+
+use crate::{Error, Track};
+use aoclib::geometry::{Direction, Point};
+use std::collections::HashMap;
+
+type TrackMap = aoclib::geometry::Map<Track>;
+
+/// The signal period at each signaled junction, with the map's origin.
+pub(crate) type Schedule = HashMap<Point, usize>;
+
+/// Split the input into the map and the signals section, if any.
+pub(crate) fn split_input(input: &str) -> (&str, Option<&str>) {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim() == "signals:" {
+            return (&input[..offset], Some(&input[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    (input, None)
+}
+
+pub(crate) fn parse_schedule(section: &str, track: &TrackMap) -> Result<Schedule, Error> {
+    let mut schedule = Schedule::new();
+    for line in section
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let invalid = || Error::InvalidSignal(line.to_string());
+        let (position, period) = line.split_once(':').ok_or_else(invalid)?;
+        let (x, y) = position.split_once(',').ok_or_else(invalid)?;
+        let x: i32 = x.trim().parse().map_err(|_| invalid())?;
+        let y: i32 = y.trim().parse().map_err(|_| invalid())?;
+        let period: usize = period.trim().parse().map_err(|_| invalid())?;
+        if period == 0 {
+            return Err(invalid());
+        }
+
+        let point = Point::new(x, track.height() as i32 - 1 - y);
+        if !track.in_bounds(point) || !matches!(track[point], Track::Cross) {
+            return Err(Error::NotAJunction { x, y });
+        }
+        schedule.insert(point, period);
+    }
+    Ok(schedule)
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The state of every signal as the simulation runs.
+#[derive(Clone)]
+pub(crate) struct JunctionState<'a> {
+    schedule: &'a Schedule,
+    tick: usize,
+    /// Ticks after which every signal is back where it started.
+    ///
+    /// If this would overflow, it's `usize::MAX`, and the signals effectively never repeat.
+    cycle: usize,
+}
+
+impl<'a> JunctionState<'a> {
+    pub fn new(schedule: &'a Schedule) -> Self {
+        let cycle = schedule.values().try_fold(1_usize, |cycle, &period| {
+            let full_period = period.checked_mul(2)?;
+            (cycle / gcd(cycle, full_period)).checked_mul(full_period)
+        });
+        JunctionState {
+            schedule,
+            tick: 0,
+            cycle: cycle.unwrap_or(usize::MAX),
+        }
+    }
+
+    /// Whether a cart traveling in `direction` may enter `position` this tick.
+    pub fn allows(&self, position: Point, direction: Direction) -> bool {
+        let period = match self.schedule.get(&position) {
+            Some(&period) => period,
+            None => return true,
+        };
+        let horizontal_green = (self.tick / period) % 2 == 0;
+        let horizontal = matches!(direction, Direction::Left | Direction::Right);
+        horizontal == horizontal_green
+    }
+
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+
+    /// Where the signals are in their combined cycle.
+    ///
+    /// Two simulation states are the same only if their phases are equal.
+    pub fn phase(&self) -> usize {
+        self.tick % self.cycle
+    }
+}
@@ -0,0 +1,53 @@
+//! Compare the one-digit-per-byte scoreboard with the nibble-packed one.
+//!
+//! The packed scoreboard needs half the memory; see `heap_bytes` for exact figures.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day14::{recipes_before, scores_after, PackedScoreboard, Scoreboard};
+
+fn bench_scores_after(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scores_after");
+    for &generations in &[10_000, 1_000_000] {
+        group.bench_with_input(
+            BenchmarkId::new("Vec<u8>", generations),
+            &generations,
+            |b, &generations| b.iter(|| scores_after::<Vec<u8>>(generations)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("PackedScoreboard", generations),
+            &generations,
+            |b, &generations| b.iter(|| scores_after::<PackedScoreboard>(generations)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_recipes_before(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recipes_before");
+    let score = 59414;
+    group.bench_function("Vec<u8>", |b| b.iter(|| recipes_before::<Vec<u8>>(score)));
+    group.bench_function("PackedScoreboard", |b| {
+        b.iter(|| recipes_before::<PackedScoreboard>(score))
+    });
+    group.finish();
+}
+
+fn report_memory(_: &mut Criterion) {
+    let generations = 1_000_000;
+    let unpacked = Vec::<u8>::with_capacity(generations);
+    let packed = PackedScoreboard::with_capacity(generations);
+    eprintln!(
+        "heap bytes for {} digits: Vec<u8> {}, PackedScoreboard {}",
+        generations,
+        unpacked.heap_bytes(),
+        packed.heap_bytes()
+    );
+}
+
+criterion_group!(
+    benches,
+    bench_scores_after,
+    bench_recipes_before,
+    report_memory
+);
+criterion_main!(benches);
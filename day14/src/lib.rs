@@ -1,29 +1,59 @@
-use aoclib::parse;
+mod scoreboard;
+
+pub use scoreboard::{PackedScoreboard, Scoreboard};
+
 use std::path::Path;
+use util::parse_str;
 
-const INITIAL_ELVES: [usize; 2] = [0, 1];
-const INITIAL_RECIPES: [u8; 2] = [3, 7];
+/// The number of elves in the puzzle.
+pub const STANDARD_ELVES: usize = 2;
+const INITIAL_RECIPES: [u8; STANDARD_ELVES] = [3, 7];
 
-fn initial_recipes(generations: u32) -> Vec<u8> {
-    let mut recipes = Vec::with_capacity(generations as usize + 10);
-    recipes.extend(INITIAL_RECIPES);
+/// The most digits a single round can add to the scoreboard.
+fn max_new_digits(elves: usize) -> usize {
+    (9 * elves).to_string().len()
+}
+
+/// The recipes `elves` elves start with: the first scores of the standard scoreboard.
+fn seed_recipes(elves: usize) -> Vec<u8> {
+    let mut recipes = INITIAL_RECIPES.to_vec();
+    let mut positions: Vec<_> = (0..STANDARD_ELVES).collect();
+    while recipes.len() < elves {
+        make_recipe(&mut positions, &mut recipes);
+    }
+    recipes.truncate(elves);
     recipes
 }
 
-fn make_recipe(elves: &mut [usize; 2], recipes: &mut Vec<u8>) {
-    let sum = recipes[elves[0]] + recipes[elves[1]];
-    if sum > 9 {
-        recipes.push(sum / 10);
+fn initial_recipes<S: Scoreboard>(elves: usize, generations: u32) -> S {
+    assert!(elves > 0, "making recipes takes at least one elf");
+    // scoring stops once there are `generations + 10` recipes, and the final round
+    // can overshoot that
+    let mut recipes = S::with_capacity(generations as usize + 9 + max_new_digits(elves));
+    for recipe in seed_recipes(elves) {
+        recipes.push(recipe);
+    }
+    recipes
+}
+
+/// Each elf starts on its own recipe.
+fn initial_elves(elves: usize) -> Vec<usize> {
+    (0..elves).collect()
+}
+
+fn make_recipe<S: Scoreboard>(elves: &mut [usize], recipes: &mut S) {
+    let sum: usize = elves.iter().map(|&elf| recipes.get(elf) as usize).sum();
+    for digit in sum.to_string().bytes() {
+        recipes.push(digit - b'0');
     }
-    recipes.push(sum % 10);
 
     for elf in elves.iter_mut() {
-        *elf += recipes[*elf] as usize + 1;
+        *elf += recipes.get(*elf) as usize + 1;
         *elf %= recipes.len();
     }
 }
 
-fn scores(recipes: &[u8], generations: u32) -> Option<u64> {
+fn scores<S: Scoreboard>(recipes: &S, generations: u32) -> Option<u64> {
     let start = generations as usize;
     let stop = start + 10;
     if recipes.len() < stop {
@@ -31,21 +61,25 @@ fn scores(recipes: &[u8], generations: u32) -> Option<u64> {
     }
 
     let mut score = 0;
-    for recipe in &recipes[start..stop] {
+    for idx in start..stop {
         score *= 10;
-        score += *recipe as u64;
+        score += recipes.get(idx) as u64;
     }
 
     Some(score)
 }
 
-/// If a score matches at the last or second-last sequence of digits from the end,
-/// return the index of the first digit of the score.
+/// If a score ends at any of the last `max_offset + 1` digits, return the index of the
+/// first digit of the earliest such match.
 ///
-/// Note that this is _not_ a general search; it must be called
-/// once for each invocation of `make_recipe` in order to work properly.
-fn matches_score(recipes: &[u8], score: u32) -> Option<usize> {
-    fn matches_score_offset(recipes: &[u8], mut score: u32, offset: usize) -> Option<usize> {
+/// Note that this is _not_ a general search; it must be called once for each invocation
+/// of `make_recipe`, with `max_offset` one less than the most digits that can add.
+fn matches_score<S: Scoreboard>(recipes: &S, score: u32, max_offset: usize) -> Option<usize> {
+    fn matches_score_offset<S: Scoreboard>(
+        recipes: &S,
+        mut score: u32,
+        offset: usize,
+    ) -> Option<usize> {
         let mut count_score_digits = 0;
         let score_digits = std::iter::from_fn(|| {
             (score != 0).then(|| {
@@ -56,51 +90,88 @@ fn matches_score(recipes: &[u8], score: u32) -> Option<usize> {
             })
         });
 
-        recipes
-            .iter()
+        (0..recipes.len().saturating_sub(offset))
             .rev()
-            .skip(offset)
             .zip(score_digits)
-            .all(|(&r, s)| r == s)
+            .all(|(idx, s)| recipes.get(idx) == s)
             .then(move || recipes.len() - offset - count_score_digits)
     }
 
-    matches_score_offset(recipes, score, 1).or_else(|| matches_score_offset(recipes, score, 0))
+    (0..=max_offset)
+        .rev()
+        .find_map(|offset| matches_score_offset(recipes, score, offset))
 }
 
-fn build_until_matches_score(mut recipes: Vec<u8>, score: u32) -> usize {
-    let mut elves = INITIAL_ELVES;
+fn build_until_matches_score<S: Scoreboard>(mut recipes: S, elves: usize, score: u32) -> usize {
+    let max_offset = max_new_digits(elves) - 1;
+    let mut elves = initial_elves(elves);
     loop {
-        if let Some(generation) = matches_score(&recipes, score) {
+        if let Some(generation) = matches_score(&recipes, score, max_offset) {
             return generation;
         }
         make_recipe(&mut elves, &mut recipes);
     }
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    for generations in parse(input)? {
-        let mut elves = INITIAL_ELVES;
-        let mut recipes = initial_recipes(generations);
-        while scores(&recipes, generations).is_none() {
-            make_recipe(&mut elves, &mut recipes);
-        }
-        let scores = scores(&recipes, generations).unwrap();
+/// The ten scores immediately following the first `generations` recipes.
+pub fn scores_after<S: Scoreboard>(generations: u32) -> u64 {
+    scores_after_with_elves::<S>(STANDARD_ELVES, generations)
+}
 
-        println!("given {}, expect scores: {}", generations, scores);
+/// Like [`scores_after`], but with `elves` elves making recipes.
+///
+/// Each round, the elves' current scores are summed, and each elf advances independently.
+/// Elves start on the first `elves` scores of the standard scoreboard.
+pub fn scores_after_with_elves<S: Scoreboard>(elves: usize, generations: u32) -> u64 {
+    let mut recipes: S = initial_recipes(elves, generations);
+    let mut elves = initial_elves(elves);
+    loop {
+        if let Some(scores) = scores(&recipes, generations) {
+            return scores;
+        }
+        make_recipe(&mut elves, &mut recipes);
     }
+}
+
+/// The number of recipes which precede the first appearance of `score`'s digits.
+pub fn recipes_before<S: Scoreboard>(score: u32) -> usize {
+    recipes_before_with_elves::<S>(STANDARD_ELVES, score)
+}
+
+/// Like [`recipes_before`], but with `elves` elves making recipes.
+pub fn recipes_before_with_elves<S: Scoreboard>(elves: usize, score: u32) -> usize {
+    build_until_matches_score(initial_recipes::<S>(elves, 0), elves, score)
+}
+
+pub fn part1(input: &Path) -> Result<(), Error> {
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for generations in parse_str(input) {
+        let scores = scores_after::<Vec<u8>>(generations);
+        lines.push(format!("given {}, expect scores: {}", generations, scores));
+    }
+    Ok(lines.join("\n"))
+}
+
 pub fn part2(input: &Path) -> Result<(), Error> {
-    for target_score in parse(input)? {
-        let generations = build_until_matches_score(initial_recipes(0), target_score);
-        println!(
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    for target_score in parse_str(input) {
+        let generations = recipes_before::<Vec<u8>>(target_score);
+        lines.push(format!(
             "for target score {}, requires generations: {}",
             target_score, generations
-        );
+        ));
     }
-    Ok(())
+    Ok(lines.join("\n"))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -119,8 +190,8 @@ mod tests {
     #[test]
     fn part1_example() {
         const GENERATIONS: u32 = 9;
-        let mut elves = INITIAL_ELVES;
-        let mut recipes = initial_recipes(GENERATIONS);
+        let mut elves = initial_elves(STANDARD_ELVES);
+        let mut recipes: Vec<u8> = initial_recipes(STANDARD_ELVES, GENERATIONS);
         dbg!(&recipes);
 
         while scores(&recipes, GENERATIONS).is_none() {
@@ -139,8 +210,88 @@ mod tests {
     // Can't effectively test cases with a leading 0 in this implementation.
     fn part2_examples(#[case] target_score: u32, #[case] expect: usize) {
         assert_eq!(
-            build_until_matches_score(initial_recipes(0), target_score),
+            build_until_matches_score(
+                initial_recipes::<Vec<u8>>(STANDARD_ELVES, 0),
+                STANDARD_ELVES,
+                target_score
+            ),
             expect
         );
+        assert_eq!(recipes_before::<PackedScoreboard>(target_score), expect);
+        assert_eq!(
+            recipes_before_with_elves::<Vec<u8>>(2, target_score),
+            expect
+        );
+    }
+
+    #[rstest]
+    #[case(5, 124515891)]
+    #[case(9, 5158916779)]
+    #[case(18, 9251071085)]
+    #[case(2018, 5941429882)]
+    fn packed_matches_unpacked(#[case] generations: u32, #[case] expect: u64) {
+        assert_eq!(scores_after::<Vec<u8>>(generations), expect);
+        assert_eq!(scores_after::<PackedScoreboard>(generations), expect);
+        assert_eq!(scores_after_with_elves::<Vec<u8>>(2, generations), expect);
+    }
+
+    #[test]
+    fn with_elves() {
+        assert_eq!(Vec::<u8>::with_elves(2), vec![3, 7]);
+        assert_eq!(Vec::<u8>::with_elves(5), vec![3, 7, 1, 0, 1]);
+        let packed = PackedScoreboard::with_elves(5);
+        assert_eq!(
+            (0..5).map(|idx| packed.get(idx)).collect::<Vec<_>>(),
+            vec![3, 7, 1, 0, 1]
+        );
+    }
+
+    #[rstest]
+    #[case(1, 9, 3333333333)]
+    #[case(3, 9, 7273272121)]
+    #[case(3, 2018, 9927242499)]
+    #[case(5, 2018, 7271321694)]
+    // twelve elves can make three-digit sums
+    #[case(12, 2018, 5851674756)]
+    fn more_elves(#[case] elves: usize, #[case] generations: u32, #[case] expect: u64) {
+        assert_eq!(
+            scores_after_with_elves::<Vec<u8>>(elves, generations),
+            expect
+        );
+        assert_eq!(
+            scores_after_with_elves::<PackedScoreboard>(elves, generations),
+            expect
+        );
+    }
+
+    #[rstest]
+    #[case(3, 21693, 17)]
+    #[case(12, 73440, 31)]
+    fn more_elves_recipes_before(#[case] elves: usize, #[case] score: u32, #[case] expect: usize) {
+        assert_eq!(recipes_before_with_elves::<Vec<u8>>(elves, score), expect);
+        assert_eq!(
+            recipes_before_with_elves::<PackedScoreboard>(elves, score),
+            expect
+        );
+    }
+
+    #[test]
+    fn packed_uses_half_the_memory() {
+        const GENERATIONS: u32 = 100_000;
+        let unpacked: Vec<u8> = initial_recipes(STANDARD_ELVES, GENERATIONS);
+        let packed: PackedScoreboard = initial_recipes(STANDARD_ELVES, GENERATIONS);
+        // capacity is preallocated for exactly the recipes part 1 can produce
+        assert_eq!(unpacked.heap_bytes(), GENERATIONS as usize + 11);
+        assert_eq!(packed.heap_bytes(), (GENERATIONS as usize + 12) / 2);
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day14-crlf", "9\n").unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part1_from_str(&input).unwrap(),
+            "given 9, expect scores: 5158916779"
+        );
     }
 }
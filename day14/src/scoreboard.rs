@@ -0,0 +1,140 @@
+/// Storage for the sequence of recipe scores.
+///
+/// Every score is a single decimal digit, so there is some choice in how densely to store them.
+pub trait Scoreboard {
+    /// Create an empty scoreboard with room for at least `capacity` digits.
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// The number of digits recorded.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the digit at `idx`.
+    ///
+    /// Panics if `idx` is out of bounds.
+    fn get(&self, idx: usize) -> u8;
+
+    /// Record a digit. It must be less than 10.
+    fn push(&mut self, digit: u8);
+
+    /// The number of bytes of heap currently reserved for digits.
+    fn heap_bytes(&self) -> usize;
+
+    /// Create a scoreboard holding the recipes `elves` elves start with.
+    ///
+    /// Those are the first `elves` scores of the standard scoreboard, so two elves
+    /// start with the puzzle's `3, 7`.
+    fn with_elves(elves: usize) -> Self
+    where
+        Self: Sized,
+    {
+        crate::initial_recipes(elves, 0)
+    }
+}
+
+/// One digit per byte: simple and fast.
+impl Scoreboard for Vec<u8> {
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get(&self, idx: usize) -> u8 {
+        self[idx]
+    }
+
+    fn push(&mut self, digit: u8) {
+        debug_assert!(digit < 10);
+        Vec::push(self, digit)
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.capacity()
+    }
+}
+
+/// Two digits per byte, packed into nibbles.
+///
+/// Even indices occupy the low nibble and odd indices the high nibble. This halves
+/// memory use at the cost of some shifting and masking on every access.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackedScoreboard {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Scoreboard for PackedScoreboard {
+    fn with_capacity(capacity: usize) -> Self {
+        PackedScoreboard {
+            bytes: Vec::with_capacity((capacity + 1) / 2),
+            len: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, idx: usize) -> u8 {
+        assert!(
+            idx < self.len,
+            "index {} out of bounds for length {}",
+            idx,
+            self.len
+        );
+        let byte = self.bytes[idx / 2];
+        if idx % 2 == 0 {
+            byte & 0xf
+        } else {
+            byte >> 4
+        }
+    }
+
+    fn push(&mut self, digit: u8) {
+        debug_assert!(digit < 10);
+        if self.len % 2 == 0 {
+            self.bytes.push(digit);
+        } else {
+            *self.bytes.last_mut().expect("odd length implies a byte") |= digit << 4;
+        }
+        self.len += 1;
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.bytes.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_round_trip() {
+        let digits: Vec<u8> = (0..25).map(|n| (n * 7 % 10) as u8).collect();
+        let mut packed = PackedScoreboard::with_capacity(digits.len());
+        for &digit in &digits {
+            packed.push(digit);
+        }
+        assert_eq!(packed.len(), digits.len());
+        for (idx, &digit) in digits.iter().enumerate() {
+            assert_eq!(packed.get(idx), digit, "at {}", idx);
+        }
+        assert_eq!(packed.heap_bytes(), 13);
+    }
+
+    #[test]
+    #[should_panic]
+    fn packed_out_of_bounds() {
+        let mut packed = PackedScoreboard::default();
+        packed.push(3);
+        // the high nibble of the only byte exists, but isn't a recorded digit
+        packed.get(1);
+    }
+}
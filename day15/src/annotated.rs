@@ -0,0 +1,254 @@
+//! The puzzle's diagrams: a map, with the units in each row listed beside it.
+//!
+//! ```text
+//! #######
+//! #..G..#   G(200)
+//! #...EG#   E(197), G(197)
+//! #.#G#G#   G(200), G(197)
+//! #...#E#   E(197)
+//! #.....#
+//! #######
+//! ```
+//!
+//! A row with units but no listing is at full health, so a plain map is also a diagram.
+
+use crate::{Error, HitPoints, Map, UnitType, Units, DEFAULT_HIT_POINTS};
+use std::str::FromStr;
+
+/// A map whose units may have taken damage.
+#[derive(Clone)]
+pub(crate) struct AnnotatedMap {
+    map: Map,
+    /// The hit points of each unit, in reading order.
+    hit_points: Vec<HitPoints>,
+}
+
+/// Parse a row's listing, like `G(200), E(197)`, into its units.
+fn parse_listing(listing: &str) -> Option<Vec<(UnitType, HitPoints)>> {
+    listing
+        .split(',')
+        .map(|entry| {
+            let (unit_type, hit_points) = entry.trim().strip_suffix(')')?.split_once('(')?;
+            let hit_points: HitPoints = hit_points.parse().ok()?;
+            if hit_points <= 0 {
+                return None;
+            }
+            Some((unit_type.parse().ok()?, hit_points))
+        })
+        .collect()
+}
+
+impl FromStr for AnnotatedMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows = String::with_capacity(s.len());
+        let mut hit_points = Vec::new();
+        for (line_idx, line) in s.lines().enumerate() {
+            let line = line.trim_end();
+            let (row, listing) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            rows.push_str(row);
+            rows.push('\n');
+
+            let row_units: Vec<UnitType> = row
+                .chars()
+                .filter_map(|ch| ch.to_string().parse().ok())
+                .collect();
+            let listing = listing.trim();
+            if listing.is_empty() {
+                hit_points.extend(row_units.iter().map(|_| DEFAULT_HIT_POINTS));
+                continue;
+            }
+
+            let invalid = || Error::InvalidAnnotation {
+                line: line_idx + 1,
+                annotation: listing.to_string(),
+            };
+            let listed = parse_listing(listing).ok_or_else(invalid)?;
+            if !listed.iter().map(|&(unit_type, _)| unit_type).eq(row_units) {
+                return Err(invalid());
+            }
+            hit_points.extend(listed.into_iter().map(|(_, hit_points)| hit_points));
+        }
+
+        Ok(AnnotatedMap {
+            map: rows.parse()?,
+            hit_points,
+        })
+    }
+}
+
+impl AnnotatedMap {
+    /// Extract the units, with their listed hit points, as [`Map::units`] does.
+    pub fn units(&mut self) -> Units<'_> {
+        let mut units = self.map.units();
+        units.units.sort_unstable();
+        for (unit, &hit_points) in units.units.iter_mut().zip(&self.hit_points) {
+            unit.hit_points = hit_points;
+        }
+        units
+    }
+}
+
+impl<'a> Units<'a> {
+    /// Render the map with its units, listing each row's units beside it, as the puzzle does.
+    pub fn annotated(&self) -> String {
+        let mut units = self.units.clone();
+        units.sort_unstable();
+        let height = self.map.height() as i32;
+
+        let mut out = String::new();
+        for (row, line) in self.to_string().lines().enumerate() {
+            let y = height - 1 - row as i32;
+            out.push_str(line.trim_end());
+            let listing: Vec<String> = units
+                .iter()
+                .filter(|unit| unit.position.y == y)
+                .map(|unit| format!("{}({})", unit.unit_type, unit.hit_points))
+                .collect();
+            if !listing.is_empty() {
+                out.push_str("   ");
+                out.push_str(&listing.join(", "));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RoundEnd;
+    use util::assert_maps_eq;
+
+    // diagrams from the puzzle statement
+    const INITIALLY: &str = "
+#######
+#.G...#   G(200)
+#...EG#   E(200), G(200)
+#.#.#G#   G(200)
+#..G#E#   G(200), E(200)
+#.....#
+#######
+";
+
+    const AFTER: &[(usize, &str)] = &[
+        (
+            1,
+            "
+#######
+#..G..#   G(200)
+#...EG#   E(197), G(197)
+#.#G#G#   G(200), G(197)
+#...#E#   E(197)
+#.....#
+#######
+",
+        ),
+        (
+            2,
+            "
+#######
+#...G.#   G(200)
+#..GEG#   G(200), E(188), G(194)
+#.#.#G#   G(194)
+#...#E#   E(194)
+#.....#
+#######
+",
+        ),
+        (
+            23,
+            "
+#######
+#...G.#   G(200)
+#..G.G#   G(200), G(131)
+#.#.#G#   G(131)
+#...#E#   E(131)
+#.....#
+#######
+",
+        ),
+        (
+            28,
+            "
+#######
+#G....#   G(200)
+#.G...#   G(131)
+#.#.#G#   G(116)
+#...#E#   E(113)
+#....G#   G(200)
+#######
+",
+        ),
+        (
+            47,
+            "
+#######
+#G....#   G(200)
+#.G...#   G(131)
+#.#.#G#   G(59)
+#...#.#
+#....G#   G(200)
+#######
+",
+        ),
+    ];
+
+    #[test]
+    fn matches_puzzle_diagrams() {
+        let mut map: AnnotatedMap = INITIALLY.trim_start().parse().unwrap();
+        let mut units = map.units();
+        assert_eq!(units.annotated(), INITIALLY.trim_start());
+
+        let mut rounds = 0;
+        for &(after, diagram) in AFTER {
+            while rounds < after {
+                assert_eq!(units.round(false), RoundEnd::Complete);
+                rounds += 1;
+            }
+            assert_maps_eq!(
+                diagram.trim_start(),
+                units.annotated(),
+                "after {} rounds",
+                after
+            );
+        }
+        assert_eq!(units.round(false), RoundEnd::CombatOver);
+    }
+
+    #[test]
+    fn resume_from_diagram() {
+        let (_, after_23) = AFTER[2];
+        let (_, after_28) = AFTER[3];
+        let mut map: AnnotatedMap = after_23.trim_start().parse().unwrap();
+        let mut units = map.units();
+        assert_eq!(units.annotated(), after_23.trim_start());
+        for _ in 23..28 {
+            units.round(false);
+        }
+        assert_eq!(units.annotated(), after_28.trim_start());
+    }
+
+    #[test]
+    fn invalid_annotations() {
+        for diagram in &[
+            "#G.E#   G(200)\n",
+            "#G.E#   E(200), G(200)\n",
+            "#G.E#   G(200), E(0)\n",
+            "#G.E#   G(200), E200\n",
+            "#G.E#   G(200), E(x)\n",
+            "#...#   G(200)\n",
+        ] {
+            assert!(
+                matches!(
+                    diagram.parse::<AnnotatedMap>(),
+                    Err(Error::InvalidAnnotation { line: 1, .. })
+                ),
+                "{:?}",
+                diagram
+            );
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{Unit, UnitType};
+use aoclib::geometry::Point;
+
+/// The result of a complete combat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombatOutcome {
+    pub winner: UnitType,
+    /// Rounds which completed before combat ended.
+    pub full_rounds: usize,
+    /// Total hit points of all surviving units.
+    pub remaining_hp: u32,
+    /// Units left standing, in reading order.
+    pub survivors: Vec<Survivor>,
+    pub elf_deaths: usize,
+    pub goblin_deaths: usize,
+}
+
+impl CombatOutcome {
+    /// The outcome as the puzzle defines it: full rounds times remaining hit points.
+    pub fn value(&self) -> u32 {
+        self.full_rounds as u32 * self.remaining_hp
+    }
+}
+
+/// A unit left standing when combat ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Survivor {
+    /// Identifies the unit, which keeps the same id throughout combat.
+    pub id: usize,
+    pub unit_type: UnitType,
+    /// Where the unit stands, in map coordinates.
+    pub position: Point,
+    pub hit_points: i16,
+}
+
+impl From<&Unit> for Survivor {
+    fn from(unit: &Unit) -> Self {
+        Survivor {
+            id: unit.id,
+            unit_type: unit.unit_type,
+            position: unit.position,
+            hit_points: unit.hit_points,
+        }
+    }
+}
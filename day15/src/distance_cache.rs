@@ -0,0 +1,150 @@
+use crate::{Map, UnitPositions};
+use aoclib::geometry::Point;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+/// Walking distances between a single origin and every tile of the map.
+///
+/// Distances count moves: stepping onto a swamp takes two.
+pub(crate) struct DistanceField {
+    width: usize,
+    distances: Vec<Option<usize>>,
+}
+
+impl DistanceField {
+    fn index(width: usize, point: Point) -> usize {
+        point.y as usize * width + point.x as usize
+    }
+
+    /// Search outward from `origin` through open, unoccupied tiles.
+    ///
+    /// If `toward` is set, each distance is the cost of walking from that tile to the
+    /// origin; otherwise, from the origin to that tile. They differ only around swamps.
+    ///
+    /// The origin itself may be occupied; that's normally the unit doing the searching.
+    //
+    // This is Dial's algorithm: costs are small integers, so a queue of buckets, one per
+    // distance, stands in for Dijkstra's heap. When every step costs 1, it's a
+    // breadth-first search.
+    fn compute(map: &Map, positions: &UnitPositions, origin: Point, toward: bool) -> DistanceField {
+        let width = map.width();
+        let mut distances = vec![None; width * map.height()];
+        distances[Self::index(width, origin)] = Some(0);
+
+        let mut buckets: VecDeque<Vec<Point>> = VecDeque::new();
+        buckets.push_back(vec![origin]);
+        let mut distance = 0;
+        while let Some(bucket) = buckets.pop_front() {
+            for point in bucket {
+                if distances[Self::index(width, point)] != Some(distance) {
+                    // superseded by a cheaper route
+                    continue;
+                }
+                for adjacent in map.orthogonal_adjacencies(point) {
+                    if !map[adjacent].is_open() || positions.contains_key(&adjacent) {
+                        continue;
+                    }
+                    // the cost of a step is paid on arrival
+                    let cost = if toward { map[point] } else { map[adjacent] }.cost();
+                    let slot = &mut distances[Self::index(width, adjacent)];
+                    if slot.map_or(true, |existing| distance + cost < existing) {
+                        *slot = Some(distance + cost);
+                        while buckets.len() < cost {
+                            buckets.push_back(Vec::new());
+                        }
+                        buckets[cost - 1].push(adjacent);
+                    }
+                }
+            }
+            distance += 1;
+        }
+
+        DistanceField { width, distances }
+    }
+
+    /// Number of steps between the origin and `point`, or `None` if it's unreachable.
+    pub fn get(&self, point: Point) -> Option<usize> {
+        self.distances[Self::index(self.width, point)]
+    }
+}
+
+/// Caches distance fields while the set of occupied tiles stays the same.
+///
+/// Between moves, units searching the same map often search from the same tiles: several
+/// units closing on the same destination each need the field from that destination.
+/// Fields are keyed by a hash of the occupied tiles plus the origin, so a field is never
+/// reused once any unit has moved or died. Stale fields can never be hit again, so
+/// [`invalidate`](Self::invalidate) just frees their memory.
+///
+/// `hits` and `misses` accumulate across invalidations.
+///
+/// A cache serves only one map.
+#[derive(Default, Clone)]
+pub(crate) struct DistanceCache {
+    /// Keyed by occupancy hash, origin, and whether distances are toward the origin.
+    fields: HashMap<(u64, Point, bool), Rc<DistanceField>>,
+    /// Whether the map has swamps, once known. Without them, distances to and from a
+    /// tile are the same, and share a field.
+    has_swamps: Option<bool>,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl DistanceCache {
+    fn occupancy_hash(positions: &UnitPositions) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        // `UnitPositions` is ordered, so equal sets hash equally
+        for position in positions.keys() {
+            (position.x, position.y).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Get the distances from `origin` to every tile, given the current unit positions.
+    pub fn distances_from(
+        &mut self,
+        map: &Map,
+        positions: &UnitPositions,
+        origin: Point,
+    ) -> Rc<DistanceField> {
+        self.field(map, positions, origin, false)
+    }
+
+    /// Get the distances from every tile to `destination`, given the current unit positions.
+    pub fn distances_to(
+        &mut self,
+        map: &Map,
+        positions: &UnitPositions,
+        destination: Point,
+    ) -> Rc<DistanceField> {
+        self.field(map, positions, destination, true)
+    }
+
+    fn field(
+        &mut self,
+        map: &Map,
+        positions: &UnitPositions,
+        origin: Point,
+        toward: bool,
+    ) -> Rc<DistanceField> {
+        let has_swamps = *self.has_swamps.get_or_insert_with(|| map.has_swamps());
+        let toward = toward && has_swamps;
+        let key = (Self::occupancy_hash(positions), origin, toward);
+        if let Some(field) = self.fields.get(&key) {
+            self.hits += 1;
+            return field.clone();
+        }
+        self.misses += 1;
+        let field = Rc::new(DistanceField::compute(map, positions, origin, toward));
+        self.fields.insert(key, field.clone());
+        field
+    }
+
+    /// Drop all cached fields. Call this whenever a unit moves or dies.
+    pub fn invalidate(&mut self) {
+        self.fields.clear();
+    }
+}
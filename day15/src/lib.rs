@@ -1,12 +1,30 @@
+mod annotated;
+mod combat_outcome;
+mod distance_cache;
 mod map;
+mod movement;
+mod round_diff;
 mod tile;
 mod unit;
 mod unit_type;
 mod units;
 
 use aoclib::geometry::Point;
-use std::{collections::BTreeMap, path::Path};
-pub(crate) use {map::Map, tile::Tile, unit::Unit, unit_type::UnitType, units::Units};
+pub use combat_outcome::{CombatOutcome, Survivor};
+pub use movement::{Destination, MovementPolicy, ReadingOrder, WeakestEnemy};
+use rayon::prelude::*;
+use std::{collections::BTreeMap, ops::Range, path::Path};
+pub use tile::SwampRule;
+pub use unit_type::UnitType;
+pub(crate) use {
+    annotated::AnnotatedMap,
+    distance_cache::DistanceCache,
+    map::Map,
+    round_diff::RoundDiff,
+    tile::Tile,
+    unit::Unit,
+    units::{RoundEnd, Units},
+};
 
 pub(crate) type UnitPositions = BTreeMap<Point, Unit>;
 pub(crate) type HitPoints = i16;
@@ -14,11 +32,29 @@ pub(crate) type HitPoints = i16;
 const DEFAULT_ATTACK_POWER: HitPoints = 3;
 const DEFAULT_HIT_POINTS: HitPoints = 200;
 
-// run a complete combat, returning the winner and the outcome
-fn run_combat(units: &mut Units) -> (UnitType, u32) {
+/// The default maximum boost to elf attack power attempted in part 2.
+pub const DEFAULT_MAX_BOOST: i16 = 200;
+
+// run a complete combat, returning its outcome
+fn run_combat(units: &mut Units) -> CombatOutcome {
+    run_combat_with_abort(units, false).expect("combat only aborts on elf death when requested")
+}
+
+// run a combat, returning its outcome.
+//
+// if `abort_on_elf_death` is set, this returns `None` as soon as any elf dies,
+// leaving `units` as they were at that moment.
+fn run_combat_with_abort(units: &mut Units, abort_on_elf_death: bool) -> Option<CombatOutcome> {
+    let initial_elves = units.count(UnitType::Elf);
+    let initial_goblins = units.count(UnitType::Goblin);
+
     let mut round_count = 0;
-    while !units.round() {
-        round_count += 1;
+    loop {
+        match units.round(abort_on_elf_death) {
+            RoundEnd::Complete => round_count += 1,
+            RoundEnd::CombatOver => break,
+            RoundEnd::ElfDied => return None,
+        }
     }
 
     debug_assert!(
@@ -34,57 +70,178 @@ fn run_combat(units: &mut Units) -> (UnitType, u32) {
     );
     debug_assert_ne!(units.units.len(), 0, "complete annihilation is impossible");
 
-    (units.units[0].unit_type, units.outcome(round_count))
+    Some(CombatOutcome {
+        winner: units.units[0].unit_type,
+        full_rounds: round_count,
+        remaining_hp: units.units.iter().map(|unit| unit.hit_points as u32).sum(),
+        survivors: units.units.iter().map(Survivor::from).collect(),
+        elf_deaths: initial_elves - units.count(UnitType::Elf),
+        goblin_deaths: initial_goblins - units.count(UnitType::Goblin),
+    })
 }
 
-pub fn part1(input: &Path) -> Result<(), Error> {
-    let mut map = Map::load(input)?;
+/// Run a complete combat, rendering only what changed in each round.
+///
+/// Each round is headed by a summary line. Rounds in which nothing changed are omitted.
+pub fn round_diffs_from_str(input: &str) -> Result<String, Error> {
+    let mut map: Map = input.parse()?;
+    let mut units = map.units();
+    let mut out = String::new();
+
+    for round in 1.. {
+        let before = units.units.clone();
+        let round_end = units.round(false);
+        let diff = RoundDiff::between(&before, &units.units);
+        if !diff.is_empty() {
+            out.push_str(&format!(
+                "round {}: {} moved, {} hp lost, {} died\n{}",
+                round,
+                diff.moves(),
+                diff.damage(),
+                diff.deaths(),
+                diff
+            ));
+        }
+        if round_end != RoundEnd::Complete {
+            break;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Run a complete combat, drawing the map after every full round as the puzzle does.
+///
+/// The input may list units' hit points beside the map, in the same format.
+pub fn combat_diagrams_from_str(input: &str) -> Result<String, Error> {
+    let mut map: AnnotatedMap = input.parse()?;
     let mut units = map.units();
+    let mut out = format!("Initially:\n{}", units.annotated());
+
+    for round in 1.. {
+        if units.round(false) != RoundEnd::Complete {
+            break;
+        }
+        let plural = if round == 1 { "" } else { "s" };
+        out.push_str(&format!(
+            "\nAfter {} round{}:\n{}",
+            round,
+            plural,
+            units.annotated()
+        ));
+    }
 
-    let (_, outcome) = run_combat(&mut units);
+    Ok(out)
+}
 
-    println!("battle outcome: {}", outcome);
+pub fn part1(input: &Path) -> Result<(), Error> {
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
     Ok(())
 }
 
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    part1_with_policy(input, &ReadingOrder)
+}
+
+/// Solve part 1, with units choosing where to walk according to `policy`.
+pub fn part1_with_policy(input: &str, policy: &dyn MovementPolicy) -> Result<String, Error> {
+    let mut map: Map = input.parse()?;
+    let mut units = map.units();
+    units.policy = policy;
+
+    let outcome = run_combat(&mut units);
+
+    Ok(format!("battle outcome: {}", outcome.value()))
+}
+
+/// Solve part 1 on a map which may contain swamps, `~`, if `swamp_rule` allows them.
+pub fn part1_with_swamp_rule(input: &str, swamp_rule: SwampRule) -> Result<String, Error> {
+    let mut map = Map::parse_with(input, swamp_rule)?;
+    let mut units = map.units();
+
+    let outcome = run_combat(&mut units);
+
+    Ok(format!("battle outcome: {}", outcome.value()))
+}
+
+/// Run a complete combat, in which elves attack with `elf_attack_power`, and break down how
+/// it ended.
+///
+/// Goblins always attack with 3, as do the puzzle's elves until part 2 boosts them.
+pub fn combat_outcome(input: &str, elf_attack_power: i16) -> Result<CombatOutcome, Error> {
+    let mut map: Map = input.parse()?;
+    let mut units = map.units();
+    units.set_elf_attack_power(elf_attack_power);
+    Ok(run_combat(&mut units))
+}
+
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let mut map = Map::load(input)?;
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    part2_with_max_boost(input, DEFAULT_MAX_BOOST)
+}
+
+/// Solve part 2, trying elf attack boosts only up to `max_boost`.
+///
+/// Fails with [`Error::NoSolution`] if elves still die at the maximum boost.
+pub fn part2_with_max_boost(input: &str, max_boost: i16) -> Result<String, Error> {
+    let mut map: Map = input.parse()?;
     let units = map.units();
 
-    let final_outcome;
+    let final_outcome = (1..=max_boost)
+        .find_map(|boost| {
+            let mut units = units.clone();
+            units.set_elf_attack_power(DEFAULT_ATTACK_POWER.saturating_add(boost));
+            // if no elves died, the elves necessarily won
+            run_combat_with_abort(&mut units, true)
+        })
+        .ok_or(Error::NoSolution)?;
 
-    // this has to be a `loop` instead of `for boost in 1..` in order to convince rustc
-    // that `final_outcome` is always initialized after termination
-    let mut boost = 0;
-    loop {
-        boost += 1;
+    Ok(format!(
+        "final outcome with min elf boost: {}",
+        final_outcome.value()
+    ))
+}
 
-        let mut units = units.clone();
-        let initial_elf_count = units
-            .units
-            .iter()
-            .filter(|unit| unit.unit_type == UnitType::Elf)
-            .count();
-        units.set_elf_attack_power(DEFAULT_ATTACK_POWER + boost);
-        let (winner, outcome) = run_combat(&mut units);
-        if winner == UnitType::Goblin {
-            continue;
-        }
+// run a complete combat for each elf attack power in `powers`, in parallel
+fn sweep(map: &Map, powers: Range<HitPoints>) -> Vec<(HitPoints, CombatOutcome)> {
+    powers
+        .into_par_iter()
+        .map(|power| {
+            let mut map = map.clone();
+            let mut units = map.units();
+            units.set_elf_attack_power(power);
+            (power, run_combat(&mut units))
+        })
+        .collect()
+}
 
-        // also check that no elves died
-        let final_elf_count = units
-            .units
-            .iter()
-            .filter(|unit| unit.unit_type == UnitType::Elf)
-            .count();
-        if final_elf_count == initial_elf_count {
-            final_outcome = outcome;
-            break;
-        }
+/// Run a complete combat for each elf attack power in `powers`, and tabulate the outcomes.
+///
+/// Unlike part 2, combat continues after elves die, so the table shows how close each
+/// attack power comes to part 2's answer, the first at which no elf dies.
+pub fn sweep_from_str(input: &str, powers: Range<i16>) -> Result<String, Error> {
+    let map: Map = input.parse()?;
+    let mut lines = vec!["power  winner   rounds  remaining hp  elf deaths  outcome".to_string()];
+    for (power, outcome) in sweep(&map, powers) {
+        let winner = match outcome.winner {
+            UnitType::Elf => "elves",
+            UnitType::Goblin => "goblins",
+        };
+        lines.push(format!(
+            "{:>5}  {:<7}  {:>6}  {:>12}  {:>10}  {:>7}",
+            power,
+            winner,
+            outcome.full_rounds,
+            outcome.remaining_hp,
+            outcome.elf_deaths,
+            outcome.value()
+        ));
     }
-
-    println!("final outcome with min elf boost: {}", final_outcome);
-    Ok(())
+    Ok(lines.join("\n"))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -95,4 +252,422 @@ pub enum Error {
     MapConversion(#[from] aoclib::geometry::MapConversionErr),
     #[error("No solution found")]
     NoSolution,
+    #[error("line {line}: invalid unit listing \"{annotation}\"")]
+    InvalidAnnotation { line: usize, annotation: String },
+    #[error("map contains swamps, but swamps are forbidden")]
+    SwampForbidden,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoclib::geometry::Direction;
+    use util::map_diff::trim_lines;
+
+    const EXAMPLE: &str = "
+#######
+#.G...#
+#...EG#
+#.#.#G#
+#..G#E#
+#.....#
+#######
+";
+
+    fn example_map() -> Map {
+        EXAMPLE.trim_start().parse().unwrap()
+    }
+
+    #[test]
+    fn example_outcome() {
+        let mut map = example_map();
+        let mut units = map.units();
+        let outcome = run_combat(&mut units);
+
+        assert_eq!(outcome.winner, UnitType::Goblin);
+        assert_eq!(outcome.full_rounds, 47);
+        assert_eq!(outcome.remaining_hp, 590);
+        assert_eq!(outcome.value(), 27730);
+        assert_eq!(outcome.survivors.len(), 4);
+        assert_eq!(outcome.elf_deaths, 2);
+        assert_eq!(outcome.goblin_deaths, 0);
+
+        let mut survivor_hp: Vec<_> = outcome
+            .survivors
+            .iter()
+            .map(|survivor| survivor.hit_points)
+            .collect();
+        survivor_hp.sort_unstable();
+        assert_eq!(survivor_hp, vec![59, 131, 200, 200]);
+        assert!(outcome
+            .survivors
+            .iter()
+            .all(|survivor| survivor.unit_type == UnitType::Goblin));
+
+        assert_eq!(
+            combat_outcome(EXAMPLE.trim_start(), DEFAULT_ATTACK_POWER).unwrap(),
+            outcome
+        );
+        let boosted = combat_outcome(EXAMPLE.trim_start(), 15).unwrap();
+        assert_eq!(boosted.winner, UnitType::Elf);
+        assert_eq!(boosted.elf_deaths, 0);
+        assert_eq!(boosted.value(), 4988);
+    }
+
+    #[test]
+    fn units_snapshot() {
+        let mut map = example_map();
+        let mut units = map.units();
+        insta::assert_snapshot!(trim_lines(&units.to_string()), @r###"
+        #######
+        #.G...#
+        #...EG#
+        #.#.#G#
+        #..G#E#
+        #.....#
+        #######
+        "###);
+
+        assert_eq!(units.round(false), RoundEnd::Complete);
+        insta::assert_snapshot!(trim_lines(&units.to_string()), @r###"
+        #######
+        #..G..#
+        #...EG#
+        #.#G#G#
+        #...#E#
+        #.....#
+        #######
+        "###);
+
+        run_combat(&mut units);
+        insta::assert_snapshot!(trim_lines(&units.to_string()), @r###"
+        #######
+        #G....#
+        #.G...#
+        #.#.#G#
+        #...#.#
+        #....G#
+        #######
+        "###);
+    }
+
+    #[test]
+    fn example_boosted_outcome() {
+        let mut map = example_map();
+        let mut units = map.units();
+        units.set_elf_attack_power(15);
+        let outcome = run_combat(&mut units);
+
+        assert_eq!(outcome.winner, UnitType::Elf);
+        assert_eq!(outcome.full_rounds, 29);
+        assert_eq!(outcome.remaining_hp, 172);
+        assert_eq!(outcome.value(), 4988);
+        assert_eq!(outcome.elf_deaths, 0);
+        assert_eq!(outcome.goblin_deaths, 4);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(
+            part2_from_str(EXAMPLE.trim_start()).unwrap(),
+            "final outcome with min elf boost: 4988"
+        );
+    }
+
+    #[test]
+    fn sweep_example() {
+        let map = example_map();
+        let outcomes = sweep(&map, 4..20);
+        assert_eq!(outcomes.len(), 16);
+        for (power, outcome) in &outcomes {
+            let mut map = map.clone();
+            let mut units = map.units();
+            units.set_elf_attack_power(*power);
+            assert_eq!(&run_combat(&mut units), outcome, "power {}", power);
+        }
+
+        // part 2's answer is the first power at which no elf dies
+        let (power, outcome) = outcomes
+            .iter()
+            .find(|(_, outcome)| outcome.elf_deaths == 0)
+            .unwrap();
+        assert_eq!(*power, 15);
+        assert_eq!(outcome.winner, UnitType::Elf);
+        assert_eq!(outcome.value(), 4988);
+
+        let table = sweep_from_str(EXAMPLE.trim_start(), 14..16).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "power  winner   rounds  remaining hp  elf deaths  outcome"
+        );
+        assert!(lines[1].starts_with("   14  "));
+        assert_ne!(lines[1].split_whitespace().nth(4), Some("0"));
+        assert_eq!(
+            lines[2],
+            "   15  elves        29           172           0     4988"
+        );
+        assert_eq!(
+            sweep_from_str(EXAMPLE.trim_start(), 3..3).unwrap(),
+            lines[0]
+        );
+    }
+
+    #[test]
+    fn abort_on_elf_death() {
+        let mut map = example_map();
+        let mut units = map.units();
+        assert!(run_combat_with_abort(&mut units, true).is_none());
+        // the combat stopped at the first death, so the other elf survives
+        assert_eq!(units.count(UnitType::Elf), 1);
+    }
+
+    #[test]
+    fn max_boost_exceeded() {
+        // the minimal boost for the example is 12
+        assert!(matches!(
+            part2_with_max_boost(EXAMPLE.trim_start(), 11),
+            Err(Error::NoSolution)
+        ));
+        assert!(part2_with_max_boost(EXAMPLE.trim_start(), 12).is_ok());
+    }
+
+    fn outcome_value(map: &str) -> u32 {
+        let mut map: Map = map.trim_start().parse().unwrap();
+        let mut units = map.units();
+        run_combat(&mut units).value()
+    }
+
+    #[test]
+    fn more_examples() {
+        let examples = [
+            (
+                "
+#######
+#G..#E#
+#E#E.E#
+#G.##.#
+#...#E#
+#...E.#
+#######
+",
+                36334,
+            ),
+            (
+                "
+#######
+#E..EG#
+#.#G.E#
+#E.##E#
+#G..#.#
+#..E#.#
+#######
+",
+                39514,
+            ),
+            (
+                "
+#######
+#E.G#.#
+#.#G..#
+#G.#.G#
+#G..#.#
+#...E.#
+#######
+",
+                27755,
+            ),
+            (
+                "
+#######
+#.E...#
+#.#..G#
+#.###.#
+#E#G#G#
+#...#G#
+#######
+",
+                28944,
+            ),
+            (
+                "
+#########
+#G......#
+#.E.#...#
+#..##..G#
+#...##..#
+#...#...#
+#.G...G.#
+#.....G.#
+#########
+",
+                18740,
+            ),
+        ];
+        for (map, value) in examples.iter() {
+            assert_eq!(outcome_value(map), *value, "{}", map);
+        }
+    }
+
+    #[test]
+    fn weakest_enemy_policy() {
+        let input = EXAMPLE.trim_start();
+        assert_eq!(
+            part1_with_policy(input, &ReadingOrder).unwrap(),
+            part1_from_str(input).unwrap()
+        );
+
+        let weakest = part1_with_policy(input, &WeakestEnemy).unwrap();
+        assert_eq!(weakest, part1_with_policy(input, &WeakestEnemy).unwrap());
+
+        // the left goblin closes in first; the elf then picks a goblin to walk toward
+        let elf_position_after_round = |policy: &dyn MovementPolicy| {
+            let mut map: Map = "###########\n#G..E....G#\n###########\n".parse().unwrap();
+            let mut units = map.units();
+            units.policy = policy;
+            for unit in units.units.iter_mut().filter(|unit| unit.position.x == 9) {
+                unit.hit_points = 10;
+            }
+            units.round(false);
+            let elf = units
+                .units
+                .iter()
+                .find(|unit| unit.unit_type == UnitType::Elf);
+            elf.unwrap().position.x
+        };
+        assert_eq!(elf_position_after_round(&ReadingOrder), 3);
+        assert_eq!(elf_position_after_round(&WeakestEnemy), 5);
+    }
+
+    #[test]
+    fn round_diffs() {
+        let diffs = round_diffs_from_str(EXAMPLE.trim_start()).unwrap();
+        assert!(
+            diffs.starts_with("round 1: 2 moved, 12 hp lost, 0 died\n"),
+            "{}",
+            diffs
+        );
+        // combat ends as soon as round 48 begins, so nothing changes in it
+        assert!(!diffs.contains("round 48:"), "{}", diffs);
+        assert_eq!(diffs.matches("died").count(), 2);
+    }
+
+    #[test]
+    fn combat_diagrams() {
+        let diagrams = combat_diagrams_from_str(EXAMPLE.trim_start()).unwrap();
+        assert!(diagrams.starts_with("Initially:\n#######\n#.G...#   G(200)\n"));
+        assert!(diagrams.contains("\nAfter 1 round:\n"));
+        assert!(diagrams.contains("\nAfter 47 rounds:\n"));
+        assert!(!diagrams.contains("After 48 rounds"));
+    }
+
+    #[test]
+    fn distance_cache_is_used() {
+        // two lines queue up in a corridor; only the front pair can fight, so the units
+        // behind them repeat the same fruitless searches until somebody dies
+        let map = "
+#########
+#EEE.GGG#
+#########
+";
+        let mut map: Map = map.trim_start().parse().unwrap();
+        let mut units = map.units();
+        run_combat(&mut units);
+        let cache = &units.cache;
+        assert!(
+            cache.hits > cache.misses,
+            "hits: {}, misses: {}",
+            cache.hits,
+            cache.misses
+        );
+    }
+
+    fn position_of(units: &Units, unit_type: UnitType) -> Point {
+        units
+            .units
+            .iter()
+            .find(|unit| unit.unit_type == unit_type)
+            .unwrap()
+            .position
+    }
+
+    #[test]
+    fn swamps_forbidden_by_default() {
+        let map = "
+#####
+#E~G#
+#####
+";
+        assert!(matches!(
+            map.trim_start().parse::<Map>(),
+            Err(Error::SwampForbidden)
+        ));
+        assert!(matches!(
+            part1_from_str(map.trim_start()),
+            Err(Error::SwampForbidden)
+        ));
+    }
+
+    #[test]
+    fn swamps_change_nothing_without_swamps() {
+        assert_eq!(
+            part1_with_swamp_rule(EXAMPLE.trim_start(), SwampRule::Slow).unwrap(),
+            part1_from_str(EXAMPLE.trim_start()).unwrap()
+        );
+    }
+
+    #[test]
+    fn swamp_detour() {
+        // crossing the swamp costs 7 steps; going around it costs 6
+        let map = "
+########
+#E~~~.G#
+#......#
+########
+";
+        let mut map = Map::parse_with(map.trim_start(), SwampRule::Slow).unwrap();
+        let mut units = map.units();
+        let elf = position_of(&units, UnitType::Elf);
+        units.round(false);
+        assert_eq!(position_of(&units, UnitType::Elf), elf + Direction::Down);
+    }
+
+    #[test]
+    fn swamp_waits_for_even_round() {
+        let map = "
+#######
+#E~..G#
+#######
+";
+        let mut map = Map::parse_with(map.trim_start(), SwampRule::Slow).unwrap();
+        let mut units = map.units();
+        let elf = position_of(&units, UnitType::Elf);
+        let goblin = position_of(&units, UnitType::Goblin);
+
+        // the elf's first step is onto the swamp, so it waits
+        units.round(false);
+        assert_eq!(position_of(&units, UnitType::Elf), elf);
+        assert_eq!(
+            position_of(&units, UnitType::Goblin),
+            goblin + Direction::Left
+        );
+
+        units.round(false);
+        assert_eq!(position_of(&units, UnitType::Elf), elf + Direction::Right);
+        assert_eq!(
+            position_of(&units, UnitType::Goblin),
+            goblin + Direction::Left + Direction::Left
+        );
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day15-crlf", EXAMPLE.trim_start()).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part2_from_str(&input).unwrap(),
+            "final outcome with min elf boost: 4988"
+        );
+    }
 }
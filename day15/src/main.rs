@@ -1,16 +1,16 @@
 use aoclib::{config::Config, website::get_input};
-use day15::{part1, part2};
+use day15::{part1_with_swamp_rule, part2_from_str, sweep_from_str, SwampRule};
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use structopt::StructOpt;
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf};
 
 const YEAR: u32 = 2018;
 const DAY: u8 = 15;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day15 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +21,54 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// how units cross swamps (`~`) in part 1: `forbidden` or `slow`
+    #[structopt(long, default_value = "forbidden")]
+    swamps: SwampRule,
+
+    /// instead of solving, tabulate the combat outcome for each elf attack power in `START..END`
+    #[structopt(long, value_name = "START..END", parse(try_from_str = parse_range))]
+    sweep: Option<Range<i16>>,
+}
+
+fn parse_range(s: &str) -> Result<Range<i16>> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| eyre!("expected a range like `4..40`"))?;
+    Ok(start.parse()?..end.parse()?)
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
+
+    if let Some(ref powers) = args.sweep {
+        println!("{}", sweep_from_str(&input, powers.clone())?);
+        return Ok(());
+    }
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_with_swamp_rule(&input, args.swamps)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        println!("{}", part2_from_str(&input)?);
     }
     Ok(())
 }
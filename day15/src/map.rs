@@ -1,7 +1,6 @@
-use crate::{Error, Tile, Unit, Units};
+use crate::{DistanceCache, Error, ReadingOrder, SwampRule, Tile, Unit, Units};
 use std::{
     ops::{Deref, Index},
-    path::Path,
     str::FromStr,
 };
 
@@ -13,10 +12,9 @@ pub(crate) struct Map(pub(crate) InnerMap);
 impl FromStr for Map {
     type Err = Error;
 
+    /// Parse a map under [`SwampRule::Forbidden`], as the puzzle requires.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        InnerMap::try_from(std::io::Cursor::new(s))
-            .map(Map)
-            .map_err(Into::into)
+        Map::parse_with(s, SwampRule::default())
     }
 }
 
@@ -40,20 +38,38 @@ where
 }
 
 impl Map {
-    pub fn load(input: &Path) -> Result<Self, Error> {
-        std::fs::read_to_string(input)?.parse()
+    /// Parse a map, failing if it contains swamps which `swamp_rule` forbids.
+    pub fn parse_with(s: &str, swamp_rule: SwampRule) -> Result<Self, Error> {
+        let map = InnerMap::try_from(std::io::Cursor::new(s)).map(Map)?;
+        if swamp_rule == SwampRule::Forbidden && map.has_swamps() {
+            return Err(Error::SwampForbidden);
+        }
+        Ok(map)
+    }
+
+    /// Whether any tile is a swamp. Without swamps, every step costs the same.
+    pub fn has_swamps(&self) -> bool {
+        self.iter().any(|&tile| tile == Tile::Swamp)
     }
 
     /// Extract the units from this map into their own data structure,
     /// leaving only the immovable tiles of the map.
+    ///
+    /// Units move according to the puzzle's [`ReadingOrder`] policy.
     pub fn units(&mut self) -> Units {
         let mut units = Vec::new();
         self.0.for_each_point_mut(|tile, position| {
             if let Tile::Occupied(unit_type) = *tile {
                 *tile = Tile::Empty;
-                units.push(Unit::new(unit_type, position));
+                units.push(Unit::new(units.len(), unit_type, position));
             }
         });
-        Units { map: self, units }
+        Units {
+            map: self,
+            units,
+            cache: DistanceCache::default(),
+            policy: &ReadingOrder,
+            round_number: 0,
+        }
     }
 }
@@ -0,0 +1,106 @@
+use aoclib::geometry::Point;
+use std::cmp::Reverse;
+
+/// A square a unit could move to: empty, reachable, and adjacent to an enemy.
+///
+/// A square adjacent to several enemies appears once for each of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Destination {
+    pub position: Point,
+    /// The number of steps the unit needs to reach `position`. Each swamp counts twice.
+    pub steps: usize,
+    /// The position of the enemy this square is adjacent to.
+    pub enemy: Point,
+    pub enemy_hit_points: i16,
+}
+
+/// Chooses where a unit which isn't already in range of an enemy walks to.
+///
+/// Whatever the destination, the unit takes the first step of a shortest path to it,
+/// preferring steps in reading order. Combat is deterministic as long as the policy is.
+pub trait MovementPolicy {
+    /// Choose one of `destinations`, or `None` to stay put.
+    ///
+    /// `destinations` are sorted in reading order, and are never empty.
+    fn choose(&self, destinations: &[Destination]) -> Option<Destination>;
+}
+
+/// Reading order, with the origin at the bottom left.
+fn reading_order(point: Point) -> (Reverse<i32>, i32) {
+    (Reverse(point.y), point.x)
+}
+
+/// The puzzle's rule: the nearest destination, with ties broken by reading order.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReadingOrder;
+
+impl MovementPolicy for ReadingOrder {
+    fn choose(&self, destinations: &[Destination]) -> Option<Destination> {
+        destinations
+            .iter()
+            .copied()
+            .min_by_key(|destination| (destination.steps, reading_order(destination.position)))
+    }
+}
+
+/// Head for the weakest enemy which can be reached anywhere on the map.
+///
+/// Ties between enemies are broken by reading order. Among the squares next to the chosen
+/// enemy, the nearest is chosen, as by [`ReadingOrder`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WeakestEnemy;
+
+impl MovementPolicy for WeakestEnemy {
+    fn choose(&self, destinations: &[Destination]) -> Option<Destination> {
+        destinations.iter().copied().min_by_key(|destination| {
+            (
+                destination.enemy_hit_points,
+                reading_order(destination.enemy),
+                destination.steps,
+                reading_order(destination.position),
+            )
+        })
+    }
+}
+
+/// Sort destinations into the order promised to [`MovementPolicy::choose`].
+pub(crate) fn sort_destinations(destinations: &mut [Destination]) {
+    destinations.sort_unstable_by_key(|destination| {
+        (
+            reading_order(destination.position),
+            reading_order(destination.enemy),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn destination(x: i32, y: i32, steps: usize, enemy_hit_points: i16) -> Destination {
+        Destination {
+            position: Point::new(x, y),
+            steps,
+            enemy: Point::new(x + 1, y),
+            enemy_hit_points,
+        }
+    }
+
+    #[test]
+    fn policies_choose_differently() {
+        let mut destinations = vec![
+            destination(1, 1, 2, 200),
+            destination(5, 5, 2, 200),
+            destination(3, 1, 9, 14),
+        ];
+        sort_destinations(&mut destinations);
+        assert_eq!(
+            ReadingOrder.choose(&destinations),
+            Some(destination(5, 5, 2, 200))
+        );
+        assert_eq!(
+            WeakestEnemy.choose(&destinations),
+            Some(destination(3, 1, 9, 14))
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{HitPoints, Unit};
+use aoclib::geometry::Point;
+use std::{collections::BTreeMap, fmt};
+
+/// What happened to a single unit over a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UnitChange {
+    before: Unit,
+    /// `None` if the unit died.
+    after: Option<Unit>,
+}
+
+/// The differences between the units before and after a round.
+///
+/// Units which neither moved, took damage, nor died are omitted. Displays as one line
+/// per changed unit, like `G3 (5,4): moved to (5,3); hp 200 -> 197`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct RoundDiff(Vec<UnitChange>);
+
+impl RoundDiff {
+    /// Compare two states of the same combat, matching units by id.
+    pub fn between(before: &[Unit], after: &[Unit]) -> RoundDiff {
+        let after: BTreeMap<usize, Unit> = after.iter().map(|unit| (unit.id, *unit)).collect();
+        let mut changes: Vec<_> = before
+            .iter()
+            .map(|&before| UnitChange {
+                before,
+                after: after.get(&before.id).copied(),
+            })
+            .filter(|change| change.after != Some(change.before))
+            .collect();
+        changes.sort_unstable_by_key(|change| change.before);
+        RoundDiff(changes)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn moves(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|change| {
+                change
+                    .after
+                    .map(|after| after.position != change.before.position)
+                    .unwrap_or_default()
+            })
+            .count()
+    }
+
+    pub fn deaths(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|change| change.after.is_none())
+            .count()
+    }
+
+    /// Total hit points lost by units which survived the round.
+    pub fn damage(&self) -> HitPoints {
+        self.0
+            .iter()
+            .filter_map(|change| Some(change.before.hit_points - change.after?.hit_points))
+            .sum()
+    }
+}
+
+fn fmt_point(f: &mut fmt::Formatter<'_>, point: Point) -> fmt::Result {
+    write!(f, "({},{})", point.x, point.y)
+}
+
+impl fmt::Display for UnitChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let before = self.before;
+        write!(f, "{}{} ", before.unit_type, before.id)?;
+        fmt_point(f, before.position)?;
+        f.write_str(":")?;
+
+        let after = match self.after {
+            Some(after) => after,
+            None => return f.write_str(" died"),
+        };
+        let mut separator = " ";
+        if after.position != before.position {
+            write!(f, "{}moved to ", separator)?;
+            fmt_point(f, after.position)?;
+            separator = "; ";
+        }
+        if after.hit_points != before.hit_points {
+            write!(
+                f,
+                "{}hp {} -> {}",
+                separator, before.hit_points, after.hit_points
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RoundDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.0 {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Map, RoundEnd};
+
+    const EXAMPLE: &str = "
+#######
+#.G...#
+#...EG#
+#.#.#G#
+#..G#E#
+#.....#
+#######
+";
+
+    #[test]
+    fn first_round() {
+        let mut map: Map = EXAMPLE.trim_start().parse().unwrap();
+        let mut units = map.units();
+        let before = units.units.clone();
+        assert_eq!(units.round(false), RoundEnd::Complete);
+        let diff = RoundDiff::between(&before, &units.units);
+
+        // two goblins close in, and four units take a hit
+        assert_eq!(diff.moves(), 2);
+        assert_eq!(diff.deaths(), 0);
+        assert_eq!(diff.damage(), 4 * 3);
+        let rendered = diff.to_string();
+        assert_eq!(rendered.lines().count(), 6, "{}", rendered);
+        assert_eq!(rendered.matches("hp 200 -> 197").count(), 4, "{}", rendered);
+    }
+
+    #[test]
+    fn deaths_and_quiet_rounds() {
+        let mut map: Map = "#####\n#EG.#\n#####\n".parse().unwrap();
+        let mut units = map.units();
+        let before = units.units.clone();
+        assert!(RoundDiff::between(&before, &before).is_empty());
+
+        units.set_elf_attack_power(200);
+        units.round(false);
+        let diff = RoundDiff::between(&before, &units.units);
+        assert_eq!(diff.deaths(), 1);
+        assert!(diff.to_string().trim_end().ends_with("died"), "{}", diff);
+    }
+}
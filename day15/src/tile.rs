@@ -11,10 +11,48 @@ pub(crate) enum Tile {
     Empty,
     #[display("#")]
     Wall,
+    /// Slow terrain, which only appears under [`SwampRule::Slow`].
+    #[display("~")]
+    Swamp,
     #[display("{0}")]
     Occupied(UnitType),
 }
 
+impl Tile {
+    /// Whether units may walk on this tile, when nobody is standing on it.
+    pub(crate) fn is_open(self) -> bool {
+        matches!(self, Tile::Empty | Tile::Swamp)
+    }
+
+    /// The number of moves it takes to step onto this tile.
+    pub(crate) fn cost(self) -> usize {
+        match self {
+            Tile::Swamp => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// How units cross swamp tiles, drawn `~`.
+///
+/// The puzzle has no swamps; they're an extension which exercises pathfinding where
+/// steps have different costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::FromStr, parse_display::Display)]
+#[display(style = "kebab-case")]
+pub enum SwampRule {
+    /// Maps may not contain swamps, as in the puzzle.
+    Forbidden,
+    /// Stepping onto a swamp costs two moves. A unit may only step onto one during an
+    /// even-numbered round; during an odd one, it waits instead.
+    Slow,
+}
+
+impl Default for SwampRule {
+    fn default() -> Self {
+        SwampRule::Forbidden
+    }
+}
+
 impl DisplayWidth for Tile {
     const DISPLAY_WIDTH: usize = 1;
 }
@@ -24,7 +62,7 @@ impl ContextFrom<Tile> for Traversable {
 
     fn ctx_from(t: Tile, position: Point, context: &Self::Context) -> Self {
         match t {
-            Tile::Empty => {
+            Tile::Empty | Tile::Swamp => {
                 if context.contains_key(&position) {
                     Traversable::Obstructed
                 } else {
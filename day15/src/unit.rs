@@ -1,11 +1,14 @@
 use crate::{
-    HitPoints, Map, Tile, UnitPositions, UnitType, DEFAULT_ATTACK_POWER, DEFAULT_HIT_POINTS,
+    movement::sort_destinations, Destination, DistanceCache, HitPoints, Map, MovementPolicy, Tile,
+    UnitPositions, UnitType, DEFAULT_ATTACK_POWER, DEFAULT_HIT_POINTS,
 };
 use aoclib::geometry::{Direction, Point};
-use std::{cmp::Ordering, collections::BTreeMap};
+use std::cmp::Ordering;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct Unit {
+    /// Distinguishes units of the same type, so they can be followed from round to round.
+    pub id: usize,
     pub unit_type: UnitType,
     pub position: Point,
     pub hit_points: HitPoints,
@@ -23,6 +26,7 @@ impl Ord for Unit {
             .then_with(|| self.unit_type.cmp(&other.unit_type))
             .then_with(|| self.hit_points.cmp(&other.hit_points))
             .then_with(|| self.attack_power.cmp(&other.attack_power))
+            .then_with(|| self.id.cmp(&other.id))
     }
 }
 
@@ -33,8 +37,9 @@ impl PartialOrd for Unit {
 }
 
 impl Unit {
-    pub fn new(unit_type: UnitType, position: Point) -> Unit {
+    pub fn new(id: usize, unit_type: UnitType, position: Point) -> Unit {
         Self {
+            id,
             unit_type,
             position,
             hit_points: DEFAULT_HIT_POINTS,
@@ -57,6 +62,10 @@ impl Unit {
     ///    e. If there is a unique target position reachable in least steps, choose it.
     ///    f. Take a single step along the shortest path to that destination. (If there is
     ///       more than one shortest path, choose the first step with the best reading order.)
+    ///
+    ///    Steps d and e are the [`ReadingOrder`](crate::ReadingOrder) policy; `policy` may
+    ///    choose the destination differently. Stepping onto a swamp costs two steps, and
+    ///    unless `swamps_open`, a unit whose step is onto a swamp waits instead.
     /// 4. Attack.
     ///    a. Determine all targets which are in range (adjacent). If none, end turn.
     ///    b. Select target with fewest hit points. In case of tie, choose the least by reading order.
@@ -69,10 +78,15 @@ impl Unit {
     ///
     /// The map stores geographic features but must not contain any units.
     /// The `UnitPositions` struct stores all units' positions.
+    /// Pathfinding goes through `cache`, which the caller must invalidate whenever a unit
+    /// moves or dies.
     pub fn turn(
         &self,
         map: &Map,
         positions: &UnitPositions,
+        cache: &mut DistanceCache,
+        policy: &dyn MovementPolicy,
+        swamps_open: bool,
     ) -> (bool, Option<Point>, Option<Point>) {
         debug_assert!(
             !map.iter().any(|&tile| matches!(tile, Tile::Occupied(_))),
@@ -105,8 +119,9 @@ impl Unit {
         let mut adjacent_targets: Vec<_> = find_adjacent_targets(self.position);
         let move_to = adjacent_targets
             .is_empty()
-            .then(|| self.compute_move(all_targets, map, positions))
-            .flatten();
+            .then(|| self.compute_move(all_targets, map, positions, cache, policy))
+            .flatten()
+            .filter(|&dest| swamps_open || map[dest] != Tile::Swamp);
         if let Some(dest) = move_to {
             // we've moved, recompute the targets
             adjacent_targets = find_adjacent_targets(dest);
@@ -132,33 +147,45 @@ impl Unit {
     /// b. Determine which of them can be reached in orthogonal steps without moving through
     ///    any unit's current position, or walls.
     /// c. If no positions can be reached, end turn without moving.
-    /// d. If multiple positions are tied for least steps, choose the first by reading order.
-    /// e. If there is a unique target position reachable in least steps, choose it.
-    /// f. Take a single step along the shortest path to that destination. (If there is
+    /// d. Let the movement policy choose a destination. By default, that's the nearest,
+    ///    with ties broken by reading order.
+    /// e. Take a single step along the shortest path to that destination. (If there is
     ///    more than one shortest path, choose the first step with the best reading order.)
     fn compute_move(
         &self,
         targets: Vec<Point>,
         map: &Map,
         positions: &UnitPositions,
+        cache: &mut DistanceCache,
+        policy: &dyn MovementPolicy,
     ) -> Option<Point> {
-        // identify squares that are in range of targets adn empty
+        // identify squares that are in range of targets and empty
         // determine which of them can be reached without obstruction
-        let targets =
-            in_range_and_empty(targets.into_iter(), map, positions).filter_map(|destination| {
-                map.navigate_ctx(positions, self.position, destination)
-                    .map(|directions| (directions.len(), destination))
-            });
-        // determine the destination which can be reached in fewest steps
-        let mut steps_to_target = BTreeMap::<_, Vec<_>>::new();
-        for (steps_to, target) in targets {
-            steps_to_target.entry(steps_to).or_default().push(target);
+        let from_self = cache.distances_from(map, positions, self.position);
+        let mut destinations: Vec<_> = targets
+            .into_iter()
+            .flat_map(|enemy| {
+                let from_self = &from_self;
+                in_range_and_empty(std::iter::once(enemy), map, positions).filter_map(
+                    move |position| {
+                        Some(Destination {
+                            position,
+                            steps: from_self.get(position)?,
+                            enemy,
+                            enemy_hit_points: positions[&enemy].hit_points,
+                        })
+                    },
+                )
+            })
+            .collect();
+        if destinations.is_empty() {
+            return None;
         }
-        let (dist, mut nearest_targets) = steps_to_target.into_iter().next()?;
-        // if multiple are tied for least steps, choose by reading order
-        sort_unstable_book_order(&mut nearest_targets);
-        let destination = *nearest_targets.first()?;
+        sort_destinations(&mut destinations);
+        let destination = policy.choose(&destinations)?.position;
+        let dist = from_self.get(destination)?;
         // determine which path to the destination is shortest by reading order
+        let to_destination = cache.distances_to(map, positions, destination);
         let first_step = std::array::IntoIter::new([
             Direction::Up,
             Direction::Left,
@@ -167,11 +194,11 @@ impl Unit {
         ])
         .find_map(|direction| {
             let adjacent_point = self.position + direction;
-            if map[adjacent_point] != Tile::Empty || positions.contains_key(&adjacent_point) {
+            if !map[adjacent_point].is_open() || positions.contains_key(&adjacent_point) {
                 return None;
             }
-            let steps_to = map.navigate_ctx(positions, adjacent_point, destination)?;
-            (steps_to.len() == dist - 1).then(move || direction)
+            let steps_to = to_destination.get(adjacent_point)?;
+            (map[adjacent_point].cost() + steps_to == dist).then(move || direction)
         })
         .expect("at least one direction must be the first direction on the path");
 
@@ -200,7 +227,7 @@ fn in_range_and_empty<'a>(
 ) -> impl 'a + Iterator<Item = Point> {
     target_positions
         .flat_map(move |point| map.orthogonal_adjacencies(point))
-        .filter(move |&point| map[point] == Tile::Empty && !positions.contains_key(&point))
+        .filter(move |&point| map[point].is_open() && !positions.contains_key(&point))
 }
 
 /// Sort points in book order
@@ -1,3 +1,4 @@
+/// The two sides of a combat.
 #[derive(
     Debug,
     Clone,
@@ -9,7 +10,7 @@
     parse_display::FromStr,
     parse_display::Display,
 )]
-pub(crate) enum UnitType {
+pub enum UnitType {
     #[display("G")]
     Goblin,
     #[display("E")]
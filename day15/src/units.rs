@@ -1,15 +1,44 @@
-use crate::{unit_type::UnitType, HitPoints, Map, Tile, Unit, UnitPositions};
+use crate::{
+    unit_type::UnitType, DistanceCache, HitPoints, Map, MovementPolicy, Tile, Unit, UnitPositions,
+};
 use std::fmt;
 
+/// How a round of combat ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RoundEnd {
+    /// Every unit took its turn.
+    Complete,
+    /// Some unit found no enemies; combat is over.
+    CombatOver,
+    /// An elf died, and the round was aborted as requested.
+    ElfDied,
+}
+
 #[derive(Clone)]
 pub(crate) struct Units<'a> {
     pub map: &'a Map,
     pub units: Vec<Unit>,
+    /// Pathfinding cache, cleared whenever a unit moves or dies.
+    ///
+    /// A round in which nobody moves or dies leaves the occupied tiles unchanged, so the
+    /// cache deliberately survives round boundaries: blocked units waiting behind a melee
+    /// repeat the same searches every round.
+    pub cache: DistanceCache,
+    /// Chooses where units walk.
+    pub policy: &'a dyn MovementPolicy,
+    /// The number of rounds begun, including any in progress.
+    ///
+    /// Units may only step onto swamps during even-numbered rounds.
+    pub round_number: usize,
 }
 
 impl<'a> Units<'a> {
-    /// Perform a round of combat, returning `true` when combat ends due to one side's annihilation.
-    pub fn round(&mut self) -> bool {
+    /// Perform a round of combat.
+    ///
+    /// If `abort_on_elf_death` is set, the round stops immediately after any elf dies.
+    pub fn round(&mut self, abort_on_elf_death: bool) -> RoundEnd {
+        self.round_number += 1;
+        let swamps_open = self.round_number % 2 == 0;
         let mut positions: UnitPositions = self
             .units
             .iter()
@@ -17,8 +46,8 @@ impl<'a> Units<'a> {
             .map(|unit| (unit.position, unit))
             .collect();
 
-        // keep track of whether or not combat aborted due to insufficient enemies
-        let mut combat_abort = false;
+        // keep track of whether or not combat aborted due to insufficient enemies or an elf death
+        let mut round_end = RoundEnd::Complete;
 
         self.units.sort_unstable();
         // we can't do `for unit in &units` because that would cause a double-borrow conflict when
@@ -30,10 +59,16 @@ impl<'a> Units<'a> {
                 continue;
             }
 
-            let (end_combat, maybe_move, maybe_attack) = unit.turn(self.map, &positions);
+            let (end_combat, maybe_move, maybe_attack) = unit.turn(
+                self.map,
+                &positions,
+                &mut self.cache,
+                self.policy,
+                swamps_open,
+            );
             // handle end of combat
             if end_combat {
-                combat_abort = true;
+                round_end = RoundEnd::CombatOver;
                 break;
             }
             // handle movement
@@ -51,6 +86,7 @@ impl<'a> Units<'a> {
                     .expect("positions always correspond to units");
                 unit.position = move_to;
                 positions.insert(unit.position, unit);
+                self.cache.invalidate();
             }
             // handle attacks
             if let Some(attack) = maybe_attack {
@@ -62,11 +98,16 @@ impl<'a> Units<'a> {
                 // do it when the target dies. We have to update the units list entirely
                 // at the end of the function anyway.
                 if target.hit_points <= 0 {
+                    self.cache.invalidate();
                     for unit_idx in 0..self.units.len() {
                         if self.units[unit_idx].position == target.position {
                             self.units[unit_idx].hit_points = target.hit_points;
                         }
                     }
+                    if abort_on_elf_death && target.unit_type == UnitType::Elf {
+                        round_end = RoundEnd::ElfDied;
+                        break;
+                    }
                 } else {
                     // of course, if the target _didn't_ die, we need to re-add them to the
                     // positions map.
@@ -83,22 +124,15 @@ impl<'a> Units<'a> {
             .filter(|unit| unit.hit_points > 0)
             .collect();
 
-        combat_abort
+        round_end
     }
 
-    pub fn outcome(&self, full_rounds: usize) -> u32 {
-        assert!(
-            self.units
-                .windows(2)
-                .all(|window| window[0].unit_type == window[1].unit_type),
-            "outcome only reliable when one side is annihilated"
-        );
-        debug_assert!(
-            self.units.iter().all(|unit| unit.hit_points > 0),
-            "all remaining units must be live"
-        );
-        let hit_point_sum: u32 = self.units.iter().map(|unit| unit.hit_points as u32).sum();
-        full_rounds as u32 * hit_point_sum
+    /// Count the units of a particular type.
+    pub fn count(&self, unit_type: UnitType) -> usize {
+        self.units
+            .iter()
+            .filter(|unit| unit.unit_type == unit_type)
+            .count()
     }
 
     pub fn set_elf_attack_power(&mut self, attack_power: HitPoints) {
@@ -0,0 +1,84 @@
+use crate::{Error, Value};
+use std::collections::VecDeque;
+
+/// The most recent register states of a CPU with `N` registers, oldest first.
+///
+/// Once `depth` states are held, recording another forgets the oldest.
+#[derive(Debug, Clone)]
+pub(crate) struct History<const N: usize> {
+    states: VecDeque<[Value; N]>,
+    depth: usize,
+}
+
+impl<const N: usize> History<N> {
+    pub(crate) fn new(depth: usize) -> Self {
+        History {
+            states: VecDeque::with_capacity(depth),
+            depth,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub(crate) fn record(&mut self, registers: [Value; N]) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.states.len() == self.depth {
+            self.states.pop_front();
+        }
+        self.states.push_back(registers);
+    }
+
+    /// Forget the latest `n` states, returning the earliest of them.
+    ///
+    /// Fails without forgetting anything if fewer than `n` states are held.
+    pub(crate) fn rewind(&mut self, n: usize) -> Result<Option<[Value; N]>, Error> {
+        if n > self.states.len() {
+            return Err(Error::HistoryExhausted {
+                requested: n,
+                available: self.states.len(),
+            });
+        }
+        let mut earliest = None;
+        for _ in 0..n {
+            earliest = self.states.pop_back();
+        }
+        Ok(earliest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer() {
+        let mut history = History::<1>::new(3);
+        for value in 0..5 {
+            history.record([value]);
+        }
+        assert_eq!(history.len(), 3);
+        assert!(matches!(
+            history.rewind(4),
+            Err(Error::HistoryExhausted {
+                requested: 4,
+                available: 3
+            })
+        ));
+        assert_eq!(history.rewind(0).unwrap(), None);
+        assert_eq!(history.rewind(2).unwrap(), Some([3]));
+        assert_eq!(history.rewind(1).unwrap(), Some([2]));
+        assert_eq!(history.len(), 0);
+    }
+
+    #[test]
+    fn zero_depth() {
+        let mut history = History::<2>::new(0);
+        history.record([1, 2]);
+        assert_eq!(history.len(), 0);
+        assert_eq!(history.rewind(0).unwrap(), None);
+    }
+}
@@ -1,6 +1,6 @@
-use crate::{Error, Input, Sample, UnknownInstruction, Value};
+use crate::{Input, Sample, UnknownInstruction, Value};
 use pest_consume::{match_nodes, Parser};
-use std::{convert::TryInto, path::Path};
+use std::convert::TryInto;
 
 #[derive(Parser)]
 #[grammar = "parser.pest"]
@@ -71,13 +71,19 @@ impl InputParser {
         Self::input(input)
     }
 
-    pub(crate) fn parse_file(path: &Path) -> Result<Input, Error> {
-        let input = std::fs::read_to_string(path)?;
-        Self::parse_str(&input).map_err(Into::into)
-    }
-
     pub(crate) fn parse_sample(s: &str) -> ParseResult<Sample> {
         let s = InputParser::parse(Rule::sample, s)?.single()?;
         InputParser::sample(s)
     }
+
+    pub(crate) fn parse_instruction(s: &str) -> ParseResult<UnknownInstruction> {
+        let s = InputParser::parse(Rule::instruction, s)?.single()?;
+        InputParser::instruction(s)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn parse_registers(s: &str) -> ParseResult<[Value; 4]> {
+        let s = InputParser::parse(Rule::registers, s)?.single()?;
+        InputParser::registers(s)
+    }
 }
@@ -1,9 +1,17 @@
+mod history;
 mod input_parser;
+mod lift;
+#[cfg(test)]
+mod sample_generator;
+mod stats;
 
 use enum_iterator::IntoEnumIterator;
+use history::History;
 use input_parser::InputParser;
+pub use stats::ExecutionStats;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
     ops::{Deref, DerefMut, Index},
     path::Path,
     str::FromStr,
@@ -18,7 +26,7 @@ type Value = u32;
 
 /// Opcodes control the behavior of an instruction and how the inputs are interpreted.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, IntoEnumIterator)]
-enum Opcode {
+pub enum Opcode {
     // Addition
     Addr,
     Addi,
@@ -44,6 +52,13 @@ enum Opcode {
     Eqrr,
 }
 
+/// Opcodes are written as their lowercase mnemonics, like `addr`.
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct Instruction {
     opcode: Opcode,
@@ -52,7 +67,13 @@ struct Instruction {
     c: Value,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.opcode, self.a, self.b, self.c)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct UnknownInstruction {
     opcode: Value,
     a: Value,
@@ -60,51 +81,124 @@ struct UnknownInstruction {
     c: Value,
 }
 
+impl fmt::Display for UnknownInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.opcode, self.a, self.b, self.c)
+    }
+}
+
+impl FromStr for UnknownInstruction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InputParser::parse_instruction(s).map_err(Into::into)
+    }
+}
+
 impl UnknownInstruction {
     fn assume(self, opcode: Opcode) -> Instruction {
         let UnknownInstruction { a, b, c, .. } = self;
         Instruction { opcode, a, b, c }
     }
 
-    fn assume_with(self, map: &HashMap<Value, Opcode>) -> Instruction {
-        let opcode = map[&self.opcode];
-        self.assume(opcode)
+    fn assume_with(self, map: &HashMap<Value, Opcode>) -> Result<Instruction, Error> {
+        let opcode = map
+            .get(&self.opcode)
+            .ok_or(Error::UnknownOpcode(self.opcode))?;
+        Ok(self.assume(*opcode))
     }
 }
 
+/// Registers as they appear in samples, which always have four of them.
 type Registers = [Value; 4];
 
-#[derive(Default, Debug)]
-struct Cpu(Registers);
+/// Write registers in the format used by samples: `[0, 1, 2, 3]`.
+fn write_registers(f: &mut fmt::Formatter<'_>, registers: &Registers) -> fmt::Result {
+    let [r0, r1, r2, r3] = registers;
+    write!(f, "[{}, {}, {}, {}]", r0, r1, r2, r3)
+}
+
+/// A CPU with `N` registers.
+///
+/// This puzzle's device has four registers, but the instruction set doesn't depend on
+/// that; other programs need more.
+///
+/// A CPU can optionally record its registers before each instruction, so that it can
+/// step backwards. By default it doesn't, and executing allocates nothing.
+#[derive(Debug)]
+struct Cpu<const N: usize> {
+    registers: [Value; N],
+    history: Option<History<N>>,
+}
 
-impl Deref for Cpu {
-    type Target = Registers;
+impl<const N: usize> Default for Cpu<N> {
+    fn default() -> Self {
+        Cpu::from_registers([0; N])
+    }
+}
+
+impl<const N: usize> Deref for Cpu<N> {
+    type Target = [Value; N];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.registers
     }
 }
 
-impl DerefMut for Cpu {
+impl<const N: usize> DerefMut for Cpu<N> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.registers
     }
 }
 
-impl<I> Index<I> for Cpu
+impl<I, const N: usize> Index<I> for Cpu<N>
 where
-    Registers: Index<I>,
+    [Value; N]: Index<I>,
 {
-    type Output = <Registers as Index<I>>::Output;
+    type Output = <[Value; N] as Index<I>>::Output;
 
     fn index(&self, index: I) -> &Self::Output {
-        self.0.index(index)
+        self.registers.index(index)
     }
 }
 
-impl Cpu {
-    fn from_registers(registers: Registers) -> Self {
-        Self(registers)
+impl<const N: usize> Cpu<N> {
+    fn from_registers(registers: [Value; N]) -> Self {
+        Cpu {
+            registers,
+            history: None,
+        }
+    }
+
+    /// Record the registers before each instruction from now on, keeping the latest `depth`.
+    fn with_history(mut self, depth: usize) -> Self {
+        self.history = Some(History::new(depth));
+        self
+    }
+
+    /// Undo the latest `n` instructions, restoring the registers from before them.
+    ///
+    /// Fails without changing anything if fewer than `n` instructions were recorded.
+    fn rewind(&mut self, n: usize) -> Result<(), Error> {
+        let restored = match &mut self.history {
+            Some(history) => history.rewind(n)?,
+            None if n == 0 => None,
+            None => {
+                return Err(Error::HistoryExhausted {
+                    requested: n,
+                    available: 0,
+                })
+            }
+        };
+        if let Some(registers) = restored {
+            self.registers = registers;
+        }
+        Ok(())
+    }
+
+    /// The number of instructions which can be undone.
+    fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, History::len)
     }
 
     fn register(&self, index: Value) -> Result<&Value, Error> {
@@ -115,14 +209,30 @@ impl Cpu {
         self.get_mut(index as usize).ok_or(Error::InvalidRegister)
     }
 
+    /// Execute a single instruction.
+    ///
+    /// Fails if the instruction names a register which doesn't exist, or if its
+    /// arithmetic overflows.
     fn execute(&mut self, instruction: Instruction) -> Result<(), Error> {
         use Opcode::*;
 
         let value = match instruction.opcode {
-            Addr => self.register(instruction.a)? + self.register(instruction.b)?,
-            Addi => self.register(instruction.a)? + instruction.b,
-            Mulr => self.register(instruction.a)? * self.register(instruction.b)?,
-            Muli => self.register(instruction.a)? * instruction.b,
+            Addr => self
+                .register(instruction.a)?
+                .checked_add(*self.register(instruction.b)?)
+                .ok_or(Error::Overflow)?,
+            Addi => self
+                .register(instruction.a)?
+                .checked_add(instruction.b)
+                .ok_or(Error::Overflow)?,
+            Mulr => self
+                .register(instruction.a)?
+                .checked_mul(*self.register(instruction.b)?)
+                .ok_or(Error::Overflow)?,
+            Muli => self
+                .register(instruction.a)?
+                .checked_mul(instruction.b)
+                .ok_or(Error::Overflow)?,
             Banr => self.register(instruction.a)? & self.register(instruction.b)?,
             Bani => self.register(instruction.a)? & instruction.b,
             Borr => self.register(instruction.a)? | self.register(instruction.b)?,
@@ -136,13 +246,28 @@ impl Cpu {
             Eqri => (*self.register(instruction.a)? == instruction.b) as Value,
             Eqrr => (*self.register(instruction.a)? == *self.register(instruction.b)?) as Value,
         };
+        let before = self.registers;
         *self.register_mut(instruction.c)? = value;
+        if let Some(history) = &mut self.history {
+            history.record(before);
+        }
+
+        Ok(())
+    }
 
+    /// Execute an instruction, recording it in `stats` if it succeeds.
+    fn execute_recording(
+        &mut self,
+        instruction: Instruction,
+        stats: &mut ExecutionStats<N>,
+    ) -> Result<(), Error> {
+        self.execute(instruction)?;
+        stats.record(instruction.opcode, instruction.c as usize);
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Sample {
     before: Registers,
     unknown_instruction: UnknownInstruction,
@@ -155,12 +280,22 @@ impl Sample {
             let instruction = self.unknown_instruction.assume(opcode);
             let mut cpu = Cpu::from_registers(self.before);
             cpu.execute(instruction).ok()?;
-            let after: [Value; 4] = self.after;
-            (*cpu == after).then(move || opcode)
+            (*cpu == self.after).then(move || opcode)
         })
     }
 }
 
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Before: ")?;
+        write_registers(f, &self.before)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.unknown_instruction)?;
+        f.write_str("After:  ")?;
+        write_registers(f, &self.after)
+    }
+}
+
 impl FromStr for Sample {
     type Err = Error;
 
@@ -169,76 +304,140 @@ impl FromStr for Sample {
     }
 }
 
-fn discover_opcodes_map(samples: &[Sample]) -> Result<HashMap<Value, Opcode>, Error> {
-    let mut unknown_opcodes: HashSet<_> = Opcode::into_enum_iter().collect();
-    let mut opcodes_map = HashMap::new();
+/// Count the occurrences of each distinct sample.
+///
+/// Puzzle inputs repeat many samples, and a repeat tells us nothing new.
+fn dedup_samples(samples: &[Sample]) -> HashMap<Sample, usize> {
+    let mut counts = HashMap::with_capacity(samples.len());
+    for &sample in samples {
+        *counts.entry(sample).or_default() += 1;
+    }
+    counts
+}
 
-    loop {
-        let n_known = opcodes_map.len();
-        for sample in samples {
-            // if we've already figured this one out, move on
-            if opcodes_map.contains_key(&sample.unknown_instruction.opcode) {
-                continue;
-            }
+/// For each opcode number, the opcodes consistent with every sample using that number.
+fn candidate_sets(samples: impl IntoIterator<Item = Sample>) -> BTreeMap<Value, HashSet<Opcode>> {
+    let mut candidates: BTreeMap<Value, HashSet<Opcode>> = BTreeMap::new();
+    for sample in samples {
+        let behaves_like: HashSet<_> = sample.behaves_like().collect();
+        candidates
+            .entry(sample.unknown_instruction.opcode)
+            .and_modify(|opcodes| opcodes.retain(|opcode| behaves_like.contains(opcode)))
+            .or_insert(behaves_like);
+    }
+    candidates
+}
 
-            let potential_opcodes: Vec<_> = sample
-                .behaves_like()
-                .filter(|opcode| unknown_opcodes.contains(opcode))
-                .take(2)
-                .collect();
+fn discover_opcodes_map(samples: &[Sample]) -> Result<HashMap<Value, Opcode>, Error> {
+    let mut candidates = candidate_sets(dedup_samples(samples).keys().copied());
+    let mut opcodes_map = HashMap::new();
 
-            debug_assert_ne!(
-                potential_opcodes.len(),
-                0,
-                "all samples must map to at least one opcode"
-            );
-            if let [opcode] = potential_opcodes.as_slice() {
-                unknown_opcodes.remove(opcode);
-                opcodes_map.insert(sample.unknown_instruction.opcode, *opcode);
-            }
+    while !candidates.is_empty() {
+        // a number with a single candidate must be that opcode, which no other number can be
+        let (number, opcode) = candidates
+            .iter()
+            .find(|(_, opcodes)| opcodes.len() == 1)
+            .map(|(&number, opcodes)| (number, *opcodes.iter().next().expect("len is 1")))
+            .ok_or(Error::NoSolution)?;
+        candidates.remove(&number);
+        for opcodes in candidates.values_mut() {
+            opcodes.remove(&opcode);
         }
+        opcodes_map.insert(number, opcode);
+    }
 
-        if unknown_opcodes.is_empty() {
-            return Ok(opcodes_map);
-        }
-        if n_known == opcodes_map.len() {
-            dbg!(&opcodes_map);
-            // we haven't learned anything this iteration
-            return Err(Error::NoSolution);
-        }
+    // every opcode must be identified, or we can't run arbitrary programs
+    if opcodes_map.len() != Opcode::into_enum_iter().count() {
+        return Err(Error::NoSolution);
     }
+    Ok(opcodes_map)
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let input = InputParser::parse_file(input)?;
+    println!("{}", part1_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+pub fn part1_from_str(input: &str) -> Result<String, Error> {
+    let input = InputParser::parse_str(input)?;
     let samples_with_at_lest_three_possibilities = input
         .samples
         .iter()
         .filter(|sample| sample.behaves_like().count() >= 3)
         .count();
-    println!(
+    Ok(format!(
         "samples with at least three possibilities: {}",
         samples_with_at_lest_three_possibilities
-    );
-    Ok(())
+    ))
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let input = InputParser::parse_file(input)?;
+    println!("{}", part2_from_str(&util::read_input(input)?)?);
+    Ok(())
+}
+
+// decode the example program, using the opcodes discovered from the samples
+fn decode_example_program(input: &str) -> Result<Vec<Instruction>, Error> {
+    let input = InputParser::parse_str(input)?;
     let opcodes_map = discover_opcodes_map(&input.samples)?;
-    let instructions = input
+    input
         .example_program
         .into_iter()
-        .map(|unknown_instruction| unknown_instruction.assume_with(&opcodes_map));
+        .map(|unknown_instruction| unknown_instruction.assume_with(&opcodes_map))
+        .collect()
+}
+
+// run the example program, returning the final CPU state and statistics about the execution
+fn run_example_program(input: &str) -> Result<(Cpu<4>, ExecutionStats<4>), Error> {
+    let instructions = decode_example_program(input)?;
 
     // no need for an instruction pointer or internal instructions because this CPU has no jumps
     let mut cpu = Cpu::default();
+    let mut stats = ExecutionStats::default();
     for instruction in instructions {
+        cpu.execute_recording(instruction, &mut stats)?;
+    }
+
+    Ok((cpu, stats))
+}
+
+pub fn part2_from_str(input: &str) -> Result<String, Error> {
+    let (cpu, _) = run_example_program(input)?;
+    Ok(format!("value in register 0: {}", cpu[0]))
+}
+
+/// Execute the example program, returning per-opcode and per-register statistics.
+pub fn execution_stats_from_str(input: &str) -> Result<ExecutionStats<4>, Error> {
+    run_example_program(input).map(|(_, stats)| stats)
+}
+
+/// Execute the example program, keeping the latest `depth` register states, then step
+/// backwards through them.
+///
+/// Lists the registers after each of the last instructions, latest first.
+pub fn history_from_str(input: &str, depth: usize) -> Result<String, Error> {
+    let instructions = decode_example_program(input)?;
+    let mut cpu = Cpu::<4>::default().with_history(depth);
+    for &instruction in &instructions {
         cpu.execute(instruction)?;
     }
 
-    println!("value in register 0: {}", cpu[0]);
-    Ok(())
+    let mut out = Vec::with_capacity(cpu.history_len() + 1);
+    let mut executed = instructions.len();
+    loop {
+        out.push(format!("after {} instructions: {:?}", executed, *cpu));
+        if cpu.history_len() == 0 {
+            break;
+        }
+        cpu.rewind(1)?;
+        executed -= 1;
+    }
+    Ok(out.join("\n"))
+}
+
+/// Decode the example program and lift it into pseudo-C.
+pub fn lift_from_str(input: &str) -> Result<String, Error> {
+    decode_example_program(input).map(|program| lift::lift(&program, None))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -251,12 +450,20 @@ pub enum Error {
     NoSolution,
     #[error("requested a register which does not exist")]
     InvalidRegister,
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("opcode number {0} was not identified by any sample")]
+    UnknownOpcode(Value),
+    #[error("can't rewind {requested} instructions: only {available} were recorded")]
+    HistoryExhausted { requested: usize, available: usize },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use maplit::hashset;
+    use pest::error::LineColLocation;
+    use proptest::prelude::*;
     use std::collections::HashSet;
 
     const EXAMPLE_SAMPLE: &str = "Before: [3, 2, 1, 1]
@@ -270,4 +477,374 @@ After:  [3, 2, 2, 1]";
         let have: HashSet<_> = sample.behaves_like().collect();
         assert_eq!(expect, have);
     }
+
+    #[test]
+    fn dedup_and_candidates() {
+        let sample: Sample = EXAMPLE_SAMPLE.parse().unwrap();
+        // the same number, but only consistent with `seti`
+        let other: Sample = "Before: [0, 0, 0, 0]\n9 2 1 2\nAfter:  [0, 0, 2, 0]"
+            .parse()
+            .unwrap();
+        let samples = [sample, other, sample, sample];
+
+        let counts = dedup_samples(&samples);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&sample], 3);
+        assert_eq!(counts[&other], 1);
+
+        let candidates = candidate_sets(counts.keys().copied());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[&9], hashset! {Opcode::Seti});
+    }
+
+    #[test]
+    fn test_example_display() {
+        let sample: Sample = EXAMPLE_SAMPLE.parse().unwrap();
+        assert_eq!(sample.to_string(), EXAMPLE_SAMPLE);
+    }
+
+    fn instruction(opcode: Opcode, a: Value, b: Value, c: Value) -> Instruction {
+        Instruction { opcode, a, b, c }
+    }
+
+    #[test]
+    fn four_registers() {
+        let mut cpu = Cpu::from_registers([3, 2, 1, 1]);
+        cpu.execute(instruction(Opcode::Mulr, 2, 1, 3)).unwrap();
+        assert_eq!(*cpu, [3, 2, 1, 2]);
+        assert!(matches!(
+            cpu.execute(instruction(Opcode::Seti, 7, 0, 4)),
+            Err(Error::InvalidRegister)
+        ));
+        assert!(matches!(
+            cpu.execute(instruction(Opcode::Addr, 4, 0, 0)),
+            Err(Error::InvalidRegister)
+        ));
+    }
+
+    #[test]
+    fn execution_stats() {
+        let mut cpu = Cpu::<4>::default();
+        let mut stats = ExecutionStats::default();
+        for instruction in &[
+            instruction(Opcode::Seti, 3, 0, 1),
+            instruction(Opcode::Seti, 4, 0, 2),
+            instruction(Opcode::Mulr, 1, 2, 0),
+            instruction(Opcode::Addi, 0, 1, 0),
+        ] {
+            cpu.execute_recording(*instruction, &mut stats).unwrap();
+        }
+        // failed instructions aren't recorded
+        assert!(cpu
+            .execute_recording(instruction(Opcode::Seti, 0, 0, 4), &mut stats)
+            .is_err());
+
+        assert_eq!(cpu[0], 13);
+        assert_eq!(stats.instructions, 4);
+        assert_eq!(
+            stats.opcode_counts.into_iter().collect::<Vec<_>>(),
+            vec![(Opcode::Addi, 1), (Opcode::Mulr, 1), (Opcode::Seti, 2)]
+        );
+        assert_eq!(stats.register_writes, [2, 1, 1, 0]);
+    }
+
+    #[test]
+    fn six_registers() {
+        let mut cpu = Cpu::<6>::default();
+        cpu.execute(instruction(Opcode::Seti, 7, 0, 4)).unwrap();
+        cpu.execute(instruction(Opcode::Muli, 4, 6, 5)).unwrap();
+        cpu.execute(instruction(Opcode::Gtri, 5, 41, 0)).unwrap();
+        cpu.execute(instruction(Opcode::Eqrr, 4, 5, 1)).unwrap();
+        assert_eq!(*cpu, [1, 0, 0, 0, 7, 42]);
+        assert!(matches!(
+            cpu.execute(instruction(Opcode::Setr, 6, 0, 0)),
+            Err(Error::InvalidRegister)
+        ));
+    }
+
+    #[test]
+    fn rewind() {
+        let program = [
+            instruction(Opcode::Seti, 3, 0, 1),
+            instruction(Opcode::Seti, 4, 0, 2),
+            instruction(Opcode::Mulr, 1, 2, 0),
+            instruction(Opcode::Addi, 0, 1, 0),
+        ];
+        let mut cpu = Cpu::<4>::default().with_history(3);
+        for &instruction in &program {
+            cpu.execute(instruction).unwrap();
+        }
+        // failed instructions aren't recorded
+        assert!(cpu.execute(instruction(Opcode::Seti, 0, 0, 4)).is_err());
+        assert_eq!(*cpu, [13, 3, 4, 0]);
+        assert_eq!(cpu.history_len(), 3);
+
+        cpu.rewind(1).unwrap();
+        assert_eq!(*cpu, [12, 3, 4, 0]);
+        assert!(matches!(
+            cpu.rewind(3),
+            Err(Error::HistoryExhausted {
+                requested: 3,
+                available: 2
+            })
+        ));
+        assert_eq!(*cpu, [12, 3, 4, 0]);
+        cpu.rewind(2).unwrap();
+        assert_eq!(*cpu, [0, 3, 0, 0]);
+
+        // replaying from a rewound state gets the same result
+        for &instruction in &program[1..] {
+            cpu.execute(instruction).unwrap();
+        }
+        assert_eq!(*cpu, [13, 3, 4, 0]);
+    }
+
+    #[test]
+    fn rewind_without_history() {
+        let mut cpu = Cpu::from_registers([1, 2, 3, 4]);
+        cpu.execute(instruction(Opcode::Seti, 7, 0, 0)).unwrap();
+        assert!(cpu.history.is_none());
+        cpu.rewind(0).unwrap();
+        assert!(matches!(
+            cpu.rewind(1),
+            Err(Error::HistoryExhausted {
+                requested: 1,
+                available: 0
+            })
+        ));
+        assert_eq!(*cpu, [7, 2, 3, 4]);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut cpu = Cpu::from_registers([Value::MAX, 0, 2, 0]);
+        for opcode in &[Opcode::Addr, Opcode::Addi, Opcode::Mulr, Opcode::Muli] {
+            assert!(matches!(
+                cpu.execute(instruction(*opcode, 0, 2, 3)),
+                Err(Error::Overflow)
+            ));
+        }
+        assert_eq!(*cpu, [Value::MAX, 0, 2, 0]);
+
+        // an overflowing opcode can't explain a sample
+        let sample: Sample =
+            "Before: [4294967295, 2, 0, 0]\n0 0 1 2\nAfter:  [4294967295, 2, 0, 0]"
+                .parse()
+                .unwrap();
+        let have: HashSet<_> = sample.behaves_like().collect();
+        assert!(!have.contains(&Opcode::Addr));
+        assert!(have.contains(&Opcode::Seti));
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        let map: HashMap<Value, Opcode> = std::iter::once((3, Opcode::Seti)).collect();
+        let known = UnknownInstruction {
+            opcode: 3,
+            a: 1,
+            b: 2,
+            c: 0,
+        };
+        assert_eq!(
+            known.assume_with(&map).unwrap(),
+            instruction(Opcode::Seti, 1, 2, 0)
+        );
+        assert!(matches!(
+            UnknownInstruction { opcode: 4, ..known }.assume_with(&map),
+            Err(Error::UnknownOpcode(4))
+        ));
+    }
+
+    struct DisplayRegisters(Registers);
+
+    impl fmt::Display for DisplayRegisters {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_registers(f, &self.0)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn instruction_round_trip(opcode: Value, a: Value, b: Value, c: Value) {
+            let instruction = UnknownInstruction { opcode, a, b, c };
+            let parsed: UnknownInstruction = instruction.to_string().parse().unwrap();
+            prop_assert_eq!(instruction, parsed);
+        }
+
+        #[test]
+        fn registers_round_trip(registers: Registers) {
+            let displayed = DisplayRegisters(registers).to_string();
+            let parsed = InputParser::parse_registers(&displayed).unwrap();
+            prop_assert_eq!(registers, parsed);
+        }
+
+        #[test]
+        fn sample_round_trip(
+            before: Registers,
+            opcode: Value,
+            a: Value,
+            b: Value,
+            c: Value,
+            after: Registers,
+        ) {
+            let sample = Sample {
+                before,
+                unknown_instruction: UnknownInstruction { opcode, a, b, c },
+                after,
+            };
+            let parsed: Sample = sample.to_string().parse().unwrap();
+            prop_assert_eq!(sample, parsed);
+        }
+
+        #[test]
+        fn input_tolerates_line_endings_and_whitespace(
+            samples in prop::collection::vec(
+                (any::<Registers>(), any::<[Value; 4]>(), any::<Registers>()),
+                0..4,
+            ),
+            program in prop::collection::vec(any::<[Value; 4]>(), 0..4),
+            crlf: bool,
+            blank_lines in 0..3_usize,
+            trailing in "[ \t]{0,2}",
+            final_newline: bool,
+        ) {
+            let instruction = |[opcode, a, b, c]: [Value; 4]| UnknownInstruction { opcode, a, b, c };
+            let samples: Vec<Sample> = samples
+                .into_iter()
+                .map(|(before, unknown_instruction, after)| Sample {
+                    before,
+                    unknown_instruction: instruction(unknown_instruction),
+                    after,
+                })
+                .collect();
+            let program: Vec<UnknownInstruction> = program.into_iter().map(instruction).collect();
+            let line_ending = if crlf { "\r\n" } else { "\n" };
+            let input = render_input(
+                &samples,
+                &program,
+                line_ending,
+                blank_lines,
+                &trailing,
+                final_newline,
+            );
+            let input = InputParser::parse_str(&input).unwrap();
+            prop_assert_eq!(input.samples, samples);
+            prop_assert_eq!(input.example_program, program);
+        }
+    }
+
+    /// Two samples and a program, in canonical form.
+    const EXAMPLE_INPUT: &str = "Before: [3, 2, 1, 1]
+9 2 1 2
+After:  [3, 2, 2, 1]
+
+Before: [0, 0, 0, 0]
+9 2 1 2
+After:  [0, 0, 2, 0]
+
+
+
+9 2 1 2
+9 0 0 1
+";
+
+    fn parsed(input: &str) -> (Vec<Sample>, Vec<UnknownInstruction>) {
+        let input = InputParser::parse_str(input).unwrap();
+        (input.samples, input.example_program)
+    }
+
+    #[test]
+    fn tolerant_input() {
+        let expect = parsed(EXAMPLE_INPUT);
+        assert_eq!(expect.0.len(), 2);
+        assert_eq!(expect.1.len(), 2);
+
+        let crlf = EXAMPLE_INPUT.replace('\n', "\r\n");
+        assert_eq!(parsed(&crlf), expect);
+        let trailing_blank_lines = format!("{}\n\n  \n", EXAMPLE_INPUT);
+        assert_eq!(parsed(&trailing_blank_lines), expect);
+        let no_final_newline = EXAMPLE_INPUT.trim_end();
+        assert_eq!(parsed(no_final_newline), expect);
+        let trailing_spaces = EXAMPLE_INPUT.replace('\n', " \t\n");
+        assert_eq!(parsed(&trailing_spaces), expect);
+
+        // samples alone, without a final newline
+        let samples_only = EXAMPLE_INPUT.split("\n\n\n\n").next().unwrap();
+        assert_eq!(parsed(samples_only), (expect.0, Vec::new()));
+    }
+
+    /// Render the input with the given line endings, extra blank lines, and trailing whitespace.
+    fn render_input(
+        samples: &[Sample],
+        program: &[UnknownInstruction],
+        line_ending: &str,
+        blank_lines: usize,
+        trailing: &str,
+        final_newline: bool,
+    ) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        lines.extend(vec![String::new(); blank_lines]);
+        for sample in samples {
+            lines.extend(sample.to_string().lines().map(str::to_string));
+            lines.extend(vec![String::new(); 1 + blank_lines]);
+        }
+        lines.extend(vec![String::new(); blank_lines]);
+        lines.extend(program.iter().map(ToString::to_string));
+        lines.extend(vec![String::new(); blank_lines]);
+        let mut input = lines
+            .iter()
+            .map(|line| format!("{}{}", line, trailing))
+            .collect::<Vec<_>>()
+            .join(line_ending);
+        if final_newline {
+            input.push_str(line_ending);
+        }
+        input
+    }
+
+    /// Parse a malformed sample, returning the `(line, column)` at which pest reports the error.
+    ///
+    /// Pest tracks rules, not literals, so a missing bracket or separator is reported at the
+    /// start of the innermost rule which expected it.
+    fn sample_error_position(s: &str) -> (usize, usize) {
+        let err = InputParser::parse_sample(s).unwrap_err();
+        match err.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(start, _) => start,
+        }
+    }
+
+    #[test]
+    fn sample_too_few_registers() {
+        let malformed = "Before: [3, 2, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]";
+        assert_eq!(sample_error_position(malformed), (1, 9));
+    }
+
+    #[test]
+    fn sample_too_few_instruction_values() {
+        let malformed = "Before: [3, 2, 1, 1]\n9 2 1\nAfter:  [3, 2, 2, 1]";
+        assert_eq!(sample_error_position(malformed), (2, 1));
+    }
+
+    #[test]
+    fn sample_non_numeric_register() {
+        let malformed = "Before: [3, 2, 1, 1]\n9 2 1 2\nAfter:  [3, 2, x, 1]";
+        assert_eq!(sample_error_position(malformed), (3, 16));
+    }
+
+    #[test]
+    fn sample_missing_after() {
+        let malformed = "Before: [3, 2, 1, 1]\n9 2 1 2\n";
+        assert_eq!(sample_error_position(malformed), (1, 1));
+    }
+
+    #[test]
+    fn crlf_input_file() {
+        let file = util::CrlfInputFile::new("day16-crlf", EXAMPLE_INPUT).unwrap();
+        let input = util::read_input(file.path()).unwrap();
+        assert_eq!(
+            part1_from_str(&input).unwrap(),
+            "samples with at least three possibilities: 1"
+        );
+    }
 }
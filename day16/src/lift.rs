@@ -0,0 +1,382 @@
+//! Lift decoded programs into readable pseudo-C.
+//!
+//! Registers become variables `r0`, `r1`, and so on. Later puzzles bind a register to the
+//! instruction pointer: reads of that register become the address of the instruction, and
+//! writes to it become jumps. A jump to a known address is a `goto`; a jump over the next
+//! instruction, conditional on the result of the comparison just before it, is an `if`.
+//! A jump beyond the program halts it, so it's a `return`.
+
+use crate::{Instruction, Opcode, Value};
+use std::{collections::BTreeSet, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(Value),
+    Immediate(Value),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Register(register) => write!(f, "r{}", register),
+            Operand::Immediate(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Mul,
+    And,
+    Or,
+    Gt,
+    Eq,
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Mul => "*",
+            Operator::And => "&",
+            Operator::Or => "|",
+            Operator::Gt => ">",
+            Operator::Eq => "==",
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        matches!(self, Operator::Gt | Operator::Eq)
+    }
+
+    /// Apply the operator to two known values, unless the result overflows.
+    fn fold(self, a: Value, b: Value) -> Option<Value> {
+        match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Mul => a.checked_mul(b),
+            Operator::And => Some(a & b),
+            Operator::Or => Some(a | b),
+            Operator::Gt => Some((a > b) as Value),
+            Operator::Eq => Some((a == b) as Value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expr {
+    Operand(Operand),
+    Binary(Operand, Operator, Operand),
+}
+
+impl Expr {
+    fn of(instruction: Instruction) -> Expr {
+        use Opcode::*;
+        use Operand::{Immediate as I, Register as R};
+
+        let Instruction { opcode, a, b, .. } = instruction;
+        let binary = |a, operator, b| Expr::Binary(a, operator, b);
+        match opcode {
+            Addr => binary(R(a), Operator::Add, R(b)),
+            Addi => binary(R(a), Operator::Add, I(b)),
+            Mulr => binary(R(a), Operator::Mul, R(b)),
+            Muli => binary(R(a), Operator::Mul, I(b)),
+            Banr => binary(R(a), Operator::And, R(b)),
+            Bani => binary(R(a), Operator::And, I(b)),
+            Borr => binary(R(a), Operator::Or, R(b)),
+            Bori => binary(R(a), Operator::Or, I(b)),
+            Setr => Expr::Operand(R(a)),
+            Seti => Expr::Operand(I(a)),
+            Gtir => binary(I(a), Operator::Gt, R(b)),
+            Gtri => binary(R(a), Operator::Gt, I(b)),
+            Gtrr => binary(R(a), Operator::Gt, R(b)),
+            Eqir => binary(I(a), Operator::Eq, R(b)),
+            Eqri => binary(R(a), Operator::Eq, I(b)),
+            Eqrr => binary(R(a), Operator::Eq, R(b)),
+        }
+    }
+
+    /// Replace reads of `register` with `value`, and fold the expression if it's then known.
+    fn substitute(self, register: Value, value: Value) -> Expr {
+        let substitute = |operand| match operand {
+            Operand::Register(r) if r == register => Operand::Immediate(value),
+            operand => operand,
+        };
+        match self {
+            Expr::Operand(operand) => Expr::Operand(substitute(operand)),
+            Expr::Binary(a, operator, b) => match (substitute(a), substitute(b)) {
+                (Operand::Immediate(a), Operand::Immediate(b)) => match operator.fold(a, b) {
+                    Some(value) => Expr::Operand(Operand::Immediate(value)),
+                    None => Expr::Binary(Operand::Immediate(a), operator, Operand::Immediate(b)),
+                },
+                (a, b) => Expr::Binary(a, operator, b),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Operand(operand) => write!(f, "{}", operand),
+            Expr::Binary(a, operator, b) if operator.is_comparison() => {
+                write!(f, "({} {} {})", a, operator.symbol(), b)
+            }
+            Expr::Binary(a, operator, b) => write!(f, "{} {} {}", a, operator.symbol(), b),
+        }
+    }
+}
+
+/// What a single instruction does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Statement {
+    Assign(Value, Expr),
+    /// Continue at the instruction at this address.
+    Goto(usize),
+    /// Continue at `then` if the register is 1, or `otherwise` if it's 0.
+    Branch {
+        condition: Value,
+        then: usize,
+        otherwise: usize,
+    },
+    /// Continue at the address one past the expression's value.
+    Jump(Expr),
+}
+
+/// The address execution continues at after the instruction pointer is set to `value`.
+fn jump_target(value: Value) -> usize {
+    value as usize + 1
+}
+
+fn statement(
+    address: usize,
+    instruction: Instruction,
+    ip_register: Option<Value>,
+    boolean: Option<Value>,
+) -> Statement {
+    let mut expr = Expr::of(instruction);
+    let ip_register = match ip_register {
+        Some(ip_register) => ip_register,
+        None => return Statement::Assign(instruction.c, expr),
+    };
+    expr = expr.substitute(ip_register, address as Value);
+    if instruction.c != ip_register {
+        return Statement::Assign(instruction.c, expr);
+    }
+
+    match expr {
+        Expr::Operand(Operand::Immediate(value)) => Statement::Goto(jump_target(value)),
+        Expr::Binary(Operand::Immediate(base), Operator::Add, Operand::Register(condition))
+        | Expr::Binary(Operand::Register(condition), Operator::Add, Operand::Immediate(base))
+            if Some(condition) == boolean =>
+        {
+            Statement::Branch {
+                condition,
+                then: jump_target(base) + 1,
+                otherwise: jump_target(base),
+            }
+        }
+        _ => Statement::Jump(expr),
+    }
+}
+
+/// Lift a program into pseudo-C, one line per instruction.
+///
+/// If `ip_register` is set, that register is bound to the instruction pointer.
+/// Each line is commented with the instruction it came from.
+pub(crate) fn lift(program: &[Instruction], ip_register: Option<Value>) -> String {
+    let mut statements = Vec::with_capacity(program.len());
+    let mut boolean = None;
+    for (address, &instruction) in program.iter().enumerate() {
+        let statement = statement(address, instruction, ip_register, boolean);
+        boolean = match statement {
+            Statement::Assign(register, Expr::Binary(_, operator, _))
+                if operator.is_comparison() =>
+            {
+                Some(register)
+            }
+            _ => None,
+        };
+        statements.push(statement);
+    }
+
+    let labels: BTreeSet<usize> = statements
+        .iter()
+        .enumerate()
+        .flat_map(|(address, statement)| match *statement {
+            Statement::Goto(target) => vec![target],
+            Statement::Branch {
+                then, otherwise, ..
+            } if otherwise == address + 1 => vec![then],
+            Statement::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            _ => Vec::new(),
+        })
+        .filter(|&target| target < program.len())
+        .collect();
+
+    let goto = |target: usize| {
+        if target < program.len() {
+            format!("goto L{};", target)
+        } else {
+            "return;".to_string()
+        }
+    };
+
+    let mut out = String::new();
+    for (address, (statement, instruction)) in statements.iter().zip(program).enumerate() {
+        let label = if labels.contains(&address) {
+            format!("L{}:", address)
+        } else {
+            String::new()
+        };
+        let code = match *statement {
+            Statement::Assign(register, Expr::Binary(Operand::Register(a), operator, b))
+                if a == register && !operator.is_comparison() =>
+            {
+                format!("r{} {}= {};", register, operator.symbol(), b)
+            }
+            Statement::Assign(register, Expr::Binary(a, operator, Operand::Register(b)))
+                if b == register && !operator.is_comparison() =>
+            {
+                format!("r{} {}= {};", register, operator.symbol(), a)
+            }
+            Statement::Assign(register, expr) => format!("r{} = {};", register, expr),
+            Statement::Goto(target) => goto(target),
+            Statement::Branch {
+                condition,
+                then,
+                otherwise,
+            } if otherwise == address + 1 => format!("if (r{}) {}", condition, goto(then)),
+            Statement::Branch {
+                condition,
+                then,
+                otherwise,
+            } => format!(
+                "if (r{}) {} else {}",
+                condition,
+                goto(then),
+                goto(otherwise)
+            ),
+            Statement::Jump(expr) => format!("goto *labels[{} + 1];", expr),
+        };
+        out.push_str(&format!("{:<6}{:<32}// {}\n", label, code, instruction));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enum_iterator::IntoEnumIterator;
+
+    fn program(listing: &str) -> Vec<Instruction> {
+        listing
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut tokens = line.split_whitespace();
+                let mnemonic = tokens.next().unwrap();
+                let opcode = Opcode::into_enum_iter()
+                    .find(|opcode| opcode.to_string() == mnemonic)
+                    .unwrap();
+                let mut value = || tokens.next().unwrap().parse().unwrap();
+                Instruction {
+                    opcode,
+                    a: value(),
+                    b: value(),
+                    c: value(),
+                }
+            })
+            .collect()
+    }
+
+    fn code(lifted: &str) -> Vec<&str> {
+        lifted
+            .lines()
+            .map(|line| line.split("//").next().unwrap().trim_end())
+            .collect()
+    }
+
+    #[test]
+    fn straight_line() {
+        let program = program(
+            "
+            seti 3 0 1
+            mulr 1 1 2
+            addi 2 4 2
+            gtri 2 10 0
+            eqir 7 1 3
+            ",
+        );
+        assert_eq!(
+            code(&lift(&program, None)),
+            vec![
+                "      r1 = 3;",
+                "      r2 = r1 * r1;",
+                "      r2 += 4;",
+                "      r0 = (r2 > 10);",
+                "      r3 = (7 == r1);",
+            ]
+        );
+    }
+
+    #[test]
+    fn instruction_pointer() {
+        // the example from 2018 day 19
+        let program = program(
+            "
+            seti 5 0 1
+            seti 6 0 2
+            addi 0 1 0
+            addr 1 2 3
+            setr 1 0 0
+            seti 8 0 4
+            seti 9 0 5
+            ",
+        );
+        assert_eq!(
+            code(&lift(&program, Some(0))),
+            vec![
+                "      r1 = 5;",
+                "      r2 = 6;",
+                "      goto L4;",
+                "      r3 = r1 + r2;",
+                "L4:   goto *labels[r1 + 1];",
+                "      r4 = 8;",
+                "      r5 = 9;",
+            ]
+        );
+    }
+
+    #[test]
+    fn conditional_jumps() {
+        let program = program(
+            "
+            eqrr 1 2 3
+            addr 3 4 4
+            addi 4 1 4
+            addi 0 1 0
+            gtir 5 1 3
+            addr 4 3 4
+            seti 0 0 4
+            mulr 4 4 4
+            ",
+        );
+        assert_eq!(
+            code(&lift(&program, Some(4))),
+            vec![
+                "      r3 = (r1 == r2);",
+                "L1:   if (r3) goto L3;",
+                "      goto L4;",
+                "L3:   r0 += 1;",
+                "L4:   r3 = (5 > r1);",
+                "      if (r3) goto L7;",
+                "      goto L1;",
+                "L7:   return;",
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use aoclib::{config::Config, website::get_input};
-use day16::{part1, part2};
+use day16::{
+    execution_stats_from_str, history_from_str, lift_from_str, part1_from_str, part2_from_str,
+};
 
 use color_eyre::eyre::Result;
 use structopt::StructOpt;
@@ -10,7 +12,7 @@ const DAY: u8 = 16;
 
 #[derive(StructOpt, Debug)]
 struct RunArgs {
-    /// input file
+    /// input file (`-` to read from stdin, as in `cat input | day16 --input -`)
     #[structopt(long, parse(from_os_str))]
     input: Option<PathBuf>,
 
@@ -21,33 +23,55 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// print opcode and register statistics for the example program
+    #[structopt(long)]
+    stats: bool,
+
+    /// print the example program as pseudo-C
+    #[structopt(long)]
+    lift: bool,
+
+    /// step backwards through this many of the example program's final register states
+    #[structopt(long)]
+    history: Option<usize>,
 }
 
 impl RunArgs {
-    fn input(&self) -> Result<PathBuf> {
-        match self.input {
+    fn input(&self) -> Result<String> {
+        let path = match self.input {
             None => {
                 let config = Config::load()?;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, YEAR, DAY)?;
-                Ok(config.input_for(YEAR, DAY))
+                config.input_for(YEAR, DAY)
             }
-            Some(ref path) => Ok(path.clone()),
-        }
+            Some(ref path) => path.clone(),
+        };
+        Ok(util::read_input_or_stdin(path)?)
     }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    let input = args.input()?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        println!("{}", part1_from_str(&input)?);
     }
     if args.part2 {
-        part2(&input_path)?;
+        println!("{}", part2_from_str(&input)?);
+    }
+    if args.stats {
+        println!("{}", execution_stats_from_str(&input)?);
+    }
+    if args.lift {
+        print!("{}", lift_from_str(&input)?);
+    }
+    if let Some(depth) = args.history {
+        println!("{}", history_from_str(&input, depth)?);
     }
     Ok(())
 }
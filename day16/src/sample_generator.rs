@@ -0,0 +1,135 @@
+//! Random, internally consistent sample sets, for testing the opcode solver against
+//! secrets other than the one in the puzzle input.
+
+use crate::{Cpu, Opcode, Registers, Sample, UnknownInstruction, Value};
+use enum_iterator::IntoEnumIterator;
+use std::collections::HashMap;
+
+/// A deterministic pseudo-random source.
+pub(crate) struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Lcg {
+        Lcg(seed)
+    }
+
+    /// A value in `0..bound`.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 33) % bound as u64) as u32
+    }
+}
+
+/// Immediate values and register contents are kept small, so multiplication never overflows.
+const MAX_VALUE: u32 = 16;
+const N_REGISTERS: u32 = 4;
+
+/// Whether each of an opcode's inputs `a` and `b` names a register.
+///
+/// `None` means the input is ignored.
+fn register_inputs(opcode: Opcode) -> (bool, Option<bool>) {
+    use Opcode::*;
+    match opcode {
+        Addr | Mulr | Banr | Borr | Gtrr | Eqrr => (true, Some(true)),
+        Addi | Muli | Bani | Bori | Gtri | Eqri => (true, Some(false)),
+        Gtir | Eqir => (false, Some(true)),
+        Setr => (true, None),
+        Seti => (false, None),
+    }
+}
+
+/// Choose a secret assignment of every opcode to a number in `0..16`.
+pub(crate) fn random_permutation(rng: &mut Lcg) -> HashMap<Value, Opcode> {
+    let mut opcodes: Vec<Opcode> = Opcode::into_enum_iter().collect();
+    // Fisher-Yates
+    for idx in (1..opcodes.len()).rev() {
+        let swap = rng.below(idx as u32 + 1) as usize;
+        opcodes.swap(idx, swap);
+    }
+    opcodes
+        .into_iter()
+        .enumerate()
+        .map(|(number, opcode)| (number as Value, opcode))
+        .collect()
+}
+
+/// Generate `n` samples by executing random instructions under the secret `permutation`.
+pub(crate) fn random_samples(
+    permutation: &HashMap<Value, Opcode>,
+    n: usize,
+    rng: &mut Lcg,
+) -> Vec<Sample> {
+    (0..n)
+        .map(|_| {
+            let number = rng.below(permutation.len() as u32);
+            let opcode = permutation[&number];
+            let (a_register, b_register) = register_inputs(opcode);
+            let mut input =
+                |is_register: bool| rng.below(if is_register { N_REGISTERS } else { MAX_VALUE });
+            let a = input(a_register);
+            let b = input(b_register.unwrap_or_default());
+            let c = input(true);
+            let unknown_instruction = UnknownInstruction {
+                opcode: number,
+                a,
+                b,
+                c,
+            };
+
+            let mut before = Registers::default();
+            for register in before.iter_mut() {
+                *register = rng.below(MAX_VALUE);
+            }
+            let mut cpu = Cpu::from_registers(before);
+            cpu.execute(unknown_instruction.assume(opcode))
+                .expect("inputs name valid registers");
+
+            Sample {
+                before,
+                unknown_instruction,
+                after: *cpu,
+            }
+        })
+        .collect()
+}
+
+mod tests {
+    use super::*;
+    use crate::discover_opcodes_map;
+
+    #[test]
+    fn samples_are_consistent() {
+        let mut rng = Lcg::new(16);
+        let permutation = random_permutation(&mut rng);
+        let mut numbers: Vec<_> = permutation.keys().copied().collect();
+        numbers.sort_unstable();
+        assert_eq!(numbers, (0..16).collect::<Vec<_>>());
+
+        for sample in random_samples(&permutation, 200, &mut rng) {
+            let secret = permutation[&sample.unknown_instruction.opcode];
+            assert!(
+                sample.behaves_like().any(|opcode| opcode == secret),
+                "{}",
+                sample
+            );
+        }
+    }
+
+    #[test]
+    fn discover_recovers_permutation() {
+        for seed in 0..32 {
+            let mut rng = Lcg::new(seed);
+            let permutation = random_permutation(&mut rng);
+            let samples = random_samples(&permutation, 1000, &mut rng);
+            assert_eq!(
+                discover_opcodes_map(&samples).unwrap(),
+                permutation,
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
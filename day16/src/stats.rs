@@ -0,0 +1,46 @@
+use crate::Opcode;
+use std::{collections::BTreeMap, fmt};
+
+/// Counts of what a program did while executing on a CPU with `N` registers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionStats<const N: usize> {
+    /// Total instructions executed.
+    pub instructions: usize,
+    /// Executions of each opcode. Opcodes which never executed are absent.
+    pub opcode_counts: BTreeMap<Opcode, usize>,
+    /// Number of writes to each register.
+    pub register_writes: [usize; N],
+}
+
+impl<const N: usize> Default for ExecutionStats<N> {
+    fn default() -> Self {
+        ExecutionStats {
+            instructions: 0,
+            opcode_counts: BTreeMap::new(),
+            register_writes: [0; N],
+        }
+    }
+}
+
+impl<const N: usize> ExecutionStats<N> {
+    /// Record the successful execution of an instruction with `opcode`, writing to `register`.
+    pub(crate) fn record(&mut self, opcode: Opcode, register: usize) {
+        self.instructions += 1;
+        *self.opcode_counts.entry(opcode).or_default() += 1;
+        self.register_writes[register] += 1;
+    }
+}
+
+impl<const N: usize> fmt::Display for ExecutionStats<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "instructions executed: {}", self.instructions)?;
+        for (opcode, count) in &self.opcode_counts {
+            writeln!(f, "  {:?}: {}", opcode, count)?;
+        }
+        f.write_str("register writes:")?;
+        for (register, writes) in self.register_writes.iter().enumerate() {
+            write!(f, " r{}={}", register, writes)?;
+        }
+        Ok(())
+    }
+}
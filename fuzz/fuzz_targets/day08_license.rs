@@ -0,0 +1,11 @@
+//! Solve arbitrary day08 input: the license's tree of nodes.
+//!
+//! Malformed input must produce an error, never a panic.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day08::part1_from_str(input);
+    let _ = day08::part2_from_str(input);
+});
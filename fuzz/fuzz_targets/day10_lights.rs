@@ -0,0 +1,11 @@
+//! Solve arbitrary day10 input: the lights' positions and velocities.
+//!
+//! Malformed input must produce an error, never a panic.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day10::part1_from_str(input);
+    let _ = day10::part2_from_str(input);
+});
@@ -0,0 +1,11 @@
+//! Solve arbitrary day12 input: the initial state and rules.
+//!
+//! Malformed input must produce an error, never a panic.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day12::part1_from_str(input);
+    let _ = day12::part2_from_str(input);
+});
@@ -0,0 +1,12 @@
+//! Solve arbitrary day16 input: the samples and example program.
+//!
+//! Malformed input must produce an error, never a panic.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day16::part1_from_str(input);
+    let _ = day16::part2_from_str(input);
+    let _ = day16::lift_from_str(input);
+});
@@ -0,0 +1,171 @@
+use crate::Point;
+
+/// An axis-aligned box of integer points, with inclusive bounds on each axis.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BoundingBox<const N: usize> {
+    pub min: Point<N>,
+    pub max: Point<N>,
+}
+
+impl<const N: usize> BoundingBox<N> {
+    /// Create a box spanning `min..=max` on every axis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` exceeds `max` on any axis.
+    pub fn new(min: Point<N>, max: Point<N>) -> Self {
+        assert!(
+            (0..N).all(|axis| min[axis] <= max[axis]),
+            "min must not exceed max"
+        );
+        BoundingBox { min, max }
+    }
+
+    /// A box containing only `point`.
+    pub fn from_point(point: Point<N>) -> Self {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// The smallest box containing every point, or `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Point<N>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let mut bounds = Self::from_point(points.next()?);
+        for point in points {
+            bounds.include(point);
+        }
+        Some(bounds)
+    }
+
+    /// Grow the box just enough to contain `point`.
+    pub fn include(&mut self, point: Point<N>) {
+        self.min = self.min.zip_with(point, i64::min);
+        self.max = self.max.zip_with(point, i64::max);
+    }
+
+    pub fn contains(&self, point: Point<N>) -> bool {
+        (0..N).all(|axis| (self.min[axis]..=self.max[axis]).contains(&point[axis]))
+    }
+
+    /// The number of points spanned along `axis`.
+    pub fn extent(&self, axis: usize) -> u64 {
+        (self.max[axis] - self.min[axis]) as u64 + 1
+    }
+
+    /// The number of points in the box.
+    pub fn volume(&self) -> u128 {
+        (0..N).map(|axis| self.extent(axis) as u128).product()
+    }
+
+    /// The Manhattan distance from `point` to the nearest point of the box; 0 if it's inside.
+    pub fn distance_to(&self, point: Point<N>) -> i64 {
+        (0..N)
+            .map(|axis| {
+                (self.min[axis] - point[axis])
+                    .max(point[axis] - self.max[axis])
+                    .max(0)
+            })
+            .sum()
+    }
+
+    /// Split the box in half along every axis which spans more than one point.
+    ///
+    /// This is the step of an octree (or its analogue in `N` dimensions): the children
+    /// don't overlap, and together they cover exactly the same points as their parent.
+    /// A box of a single point has no children.
+    pub fn subdivide(&self) -> Vec<BoundingBox<N>> {
+        let mut children = vec![*self];
+        for axis in 0..N {
+            if self.extent(axis) < 2 {
+                continue;
+            }
+            let mid = self.min[axis] + (self.max[axis] - self.min[axis]) / 2;
+            children = children
+                .into_iter()
+                .flat_map(|child| {
+                    let (mut low, mut high) = (child, child);
+                    low.max[axis] = mid;
+                    high.min[axis] = mid + 1;
+                    vec![low, high]
+                })
+                .collect();
+        }
+        if children.len() == 1 {
+            children.clear();
+        }
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point3;
+    use std::collections::HashSet;
+
+    fn points_in(bounds: &BoundingBox<3>) -> Vec<Point3> {
+        let mut points = Vec::new();
+        for x in bounds.min[0]..=bounds.max[0] {
+            for y in bounds.min[1]..=bounds.max[1] {
+                for z in bounds.min[2]..=bounds.max[2] {
+                    points.push(Point::new([x, y, z]));
+                }
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn from_points() {
+        let points = vec![
+            Point::new([1, -2, 3]),
+            Point::new([-4, 5, 0]),
+            Point::new([0, 0, 7]),
+        ];
+        let bounds = BoundingBox::from_points(points.clone()).unwrap();
+        assert_eq!(bounds.min, Point::new([-4, -2, 0]));
+        assert_eq!(bounds.max, Point::new([1, 5, 7]));
+        assert!(points.into_iter().all(|point| bounds.contains(point)));
+        assert_eq!(bounds.volume(), 6 * 8 * 8);
+        assert_eq!(BoundingBox::<3>::from_points(vec![]), None);
+    }
+
+    #[test]
+    fn distance_to() {
+        let bounds = BoundingBox::new(Point::new([0, 0, 0]), Point::new([2, 2, 2]));
+        assert_eq!(bounds.distance_to(Point::new([1, 1, 1])), 0);
+        assert_eq!(bounds.distance_to(Point::new([2, 0, 2])), 0);
+        assert_eq!(bounds.distance_to(Point::new([5, 1, -1])), 4);
+        for point in points_in(&bounds) {
+            let outside = point + Point::new([4, -3, 1]);
+            let brute = points_in(&bounds)
+                .into_iter()
+                .map(|inside| inside.manhattan_distance(outside))
+                .min()
+                .unwrap();
+            assert_eq!(bounds.distance_to(outside), brute);
+        }
+    }
+
+    #[test]
+    fn subdivide_partitions() {
+        let bounds = BoundingBox::new(Point::new([-2, 0, 5]), Point::new([2, 3, 5]));
+        let children = bounds.subdivide();
+        // the z axis spans a single point, so it isn't split
+        assert_eq!(children.len(), 4);
+
+        let mut seen = HashSet::new();
+        for child in &children {
+            for point in points_in(child) {
+                assert!(bounds.contains(point));
+                assert!(seen.insert(point), "{:?} covered twice", point);
+            }
+        }
+        assert_eq!(seen.len() as u128, bounds.volume());
+
+        let unit = BoundingBox::from_point(Point::new([1, 2, 3]));
+        assert!(unit.subdivide().is_empty());
+    }
+}
@@ -0,0 +1,12 @@
+//! N-dimensional integer geometry, for days which outgrow `aoclib`'s 2-d `Point`.
+//!
+//! Distances are all Manhattan distances.
+
+mod bounding_box;
+mod point;
+
+pub use bounding_box::BoundingBox;
+pub use point::{ParsePointError, Point};
+
+pub type Point3 = Point<3>;
+pub type Point4 = Point<4>;
@@ -0,0 +1,231 @@
+use std::{
+    fmt,
+    num::ParseIntError,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+/// A point, or a displacement, in `N` dimensions.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Point<const N: usize>(pub [i64; N]);
+
+impl<const N: usize> Default for Point<N> {
+    fn default() -> Self {
+        Point([0; N])
+    }
+}
+
+impl<const N: usize> From<[i64; N]> for Point<N> {
+    fn from(coords: [i64; N]) -> Self {
+        Point(coords)
+    }
+}
+
+impl<const N: usize> Point<N> {
+    pub fn new(coords: [i64; N]) -> Self {
+        Point(coords)
+    }
+
+    pub fn origin() -> Self {
+        Self::default()
+    }
+
+    pub fn coords(&self) -> &[i64; N] {
+        &self.0
+    }
+
+    /// The Manhattan norm: the sum of the absolute values of the coordinates.
+    pub fn manhattan(self) -> i64 {
+        self.0.iter().map(|coord| coord.abs()).sum()
+    }
+
+    /// The Manhattan distance between two points.
+    pub fn manhattan_distance(self, other: Self) -> i64 {
+        (self - other).manhattan()
+    }
+
+    /// Combine two points coordinate by coordinate.
+    pub fn zip_with(self, other: Self, mut f: impl FnMut(i64, i64) -> i64) -> Self {
+        let mut out = self;
+        for (coord, &other) in out.0.iter_mut().zip(other.0.iter()) {
+            *coord = f(*coord, other);
+        }
+        out
+    }
+
+    /// Apply a function to each coordinate.
+    pub fn map(mut self, mut f: impl FnMut(i64) -> i64) -> Self {
+        for coord in self.0.iter_mut() {
+            *coord = f(*coord);
+        }
+        self
+    }
+}
+
+impl<const N: usize> Index<usize> for Point<N> {
+    type Output = i64;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        &self.0[axis]
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for Point<N> {
+    fn index_mut(&mut self, axis: usize) -> &mut Self::Output {
+        &mut self.0[axis]
+    }
+}
+
+impl<const N: usize> Add for Point<N> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        self.zip_with(other, |a, b| a + b)
+    }
+}
+
+impl<const N: usize> AddAssign for Point<N> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: usize> Sub for Point<N> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.zip_with(other, |a, b| a - b)
+    }
+}
+
+impl<const N: usize> SubAssign for Point<N> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: usize> Neg for Point<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.map(|coord| -coord)
+    }
+}
+
+impl<const N: usize> Mul<i64> for Point<N> {
+    type Output = Self;
+
+    fn mul(self, scale: i64) -> Self::Output {
+        self.map(|coord| coord * scale)
+    }
+}
+
+/// Points display as comma-separated coordinates, like `1,-2,3`.
+impl<const N: usize> fmt::Display for Point<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (axis, coord) in self.0.iter().enumerate() {
+            if axis > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", coord)?;
+        }
+        Ok(())
+    }
+}
+
+/// Points parse from comma-separated coordinates, optionally wrapped in angle brackets,
+/// like `1,-2,3` or `<1, -2, 3>`.
+impl<const N: usize> FromStr for Point<N> {
+    type Err = ParsePointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .unwrap_or(s);
+
+        let mut point = Point::default();
+        let mut found = 0;
+        for coord in s.split(',') {
+            if found < N {
+                point.0[found] =
+                    coord
+                        .trim()
+                        .parse()
+                        .map_err(|source| ParsePointError::InvalidCoordinate {
+                            coordinate: coord.trim().to_string(),
+                            source,
+                        })?;
+            }
+            found += 1;
+        }
+        if found != N {
+            return Err(ParsePointError::WrongDimension { expected: N, found });
+        }
+        Ok(point)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParsePointError {
+    #[error("expected {expected} coordinates; found {found}")]
+    WrongDimension { expected: usize, found: usize },
+    #[error("invalid coordinate \"{coordinate}\"")]
+    InvalidCoordinate {
+        coordinate: String,
+        #[source]
+        source: ParseIntError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Point3, Point4};
+
+    #[test]
+    fn arithmetic() {
+        let a = Point3::new([1, -2, 3]);
+        let b = Point3::new([-4, 5, 0]);
+        assert_eq!(a + b, Point::new([-3, 3, 3]));
+        assert_eq!(a - b, Point::new([5, -7, 3]));
+        assert_eq!(-a, Point::new([-1, 2, -3]));
+        assert_eq!(a * 2, Point::new([2, -4, 6]));
+        assert_eq!(a.manhattan(), 6);
+        assert_eq!(a.manhattan_distance(b), 15);
+        assert_eq!(Point4::origin().manhattan(), 0);
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let point: Point4 = "-1,2,2,0".parse().unwrap();
+        assert_eq!(point, Point::new([-1, 2, 2, 0]));
+        assert_eq!(point.to_string(), "-1,2,2,0");
+
+        let point: Point3 = "<10, -12, 12>".parse().unwrap();
+        assert_eq!(point, Point::new([10, -12, 12]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "1,2".parse::<Point3>(),
+            Err(ParsePointError::WrongDimension {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            "1,2,3,4".parse::<Point3>(),
+            Err(ParsePointError::WrongDimension {
+                expected: 3,
+                found: 4
+            })
+        );
+        assert!(matches!(
+            "1,x,3".parse::<Point3>(),
+            Err(ParsePointError::InvalidCoordinate { coordinate, .. }) if coordinate == "x"
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+//! A small string interner, for days which juggle many repetitions of a few short labels.
+//!
+//! Interning a label once turns it into a [`Symbol`], which is `Copy`, compares and hashes
+//! as a single `u32`, and can be resolved back into the label when needed.
+
+use std::{collections::HashMap, iter::FromIterator};
+
+/// An interned string.
+///
+/// Symbols are assigned in the order their strings were first interned, so they compare in
+/// that order. They're meaningful only to the [`Interner`] which produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The position of this symbol in its interner: `0` for the first string interned, and so on.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A table mapping strings to symbols and back.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Interner {
+    symbols: HashMap<Box<str>, Symbol>,
+    strings: Vec<Box<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the symbol for `s`, interning it if it's new.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(s) {
+            return symbol;
+        }
+        let symbol = Symbol(self.strings.len() as u32);
+        self.strings.push(s.into());
+        self.symbols.insert(s.into(), symbol);
+        symbol
+    }
+
+    /// Get the symbol for `s`, if it has been interned.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.symbols.get(s).copied()
+    }
+
+    /// Get the string a symbol stands for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the symbol was not produced by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Every symbol and its string, in symbol order.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (Symbol, &str)> {
+        self.strings
+            .iter()
+            .enumerate()
+            .map(|(idx, s)| (Symbol(idx as u32), s.as_ref()))
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for Interner {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut interner = Interner::new();
+        interner.extend(iter);
+        interner
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for Interner {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for s in iter {
+            self.intern(s.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut interner = Interner::new();
+        let fire = interner.intern("fire");
+        let cold = interner.intern("cold");
+        assert_eq!(interner.intern("fire"), fire);
+        assert_ne!(fire, cold);
+        assert!(fire < cold);
+        assert_eq!(interner.resolve(cold), "cold");
+        assert_eq!(interner.get("cold"), Some(cold));
+        assert_eq!(interner.get("slashing"), None);
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn collect_in_order() {
+        let interner: Interner = "b a b c a".split_whitespace().collect();
+        assert_eq!(
+            interner.iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            vec!["b", "a", "c"]
+        );
+        assert!(interner
+            .iter()
+            .enumerate()
+            .all(|(idx, (symbol, _))| symbol.index() == idx));
+    }
+}
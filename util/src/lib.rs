@@ -0,0 +1,159 @@
+//! Helpers shared between days which `aoclib` doesn't provide.
+
+pub mod map_diff;
+pub mod spatial;
+
+use std::{borrow::Cow, fmt::Display, io::Read, path::Path, str::FromStr};
+
+/// Normalize input saved by Windows tools: strip a leading byte-order mark, and convert
+/// CRLF line endings to LF.
+///
+/// Input which needs neither is borrowed unchanged.
+pub fn normalize_input(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    if input.contains("\r\n") {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Read an input file, normalized as by [`normalize_input`].
+pub fn read_input(path: impl AsRef<Path>) -> std::io::Result<String> {
+    let input = std::fs::read_to_string(path)?;
+    Ok(normalize_input(&input).into_owned())
+}
+
+/// Read all of stdin, normalized as by [`normalize_input`].
+pub fn read_stdin() -> std::io::Result<String> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    Ok(normalize_input(&input).into_owned())
+}
+
+/// Read a binary's `--input`: stdin if `path` is `-`, or else the named file.
+///
+/// Either way, the input is normalized as by [`normalize_input`].
+pub fn read_input_or_stdin(path: impl AsRef<Path>) -> std::io::Result<String> {
+    let path = path.as_ref();
+    if path == Path::new("-") {
+        read_stdin()
+    } else {
+        read_input(path)
+    }
+}
+
+/// Convert `input` to CRLF line endings, with a leading byte-order mark.
+///
+/// This is how some Windows tools save text, so it's useful for testing that parsers
+/// accept such input after [`normalize_input`]. Only built for tests, or with the
+/// `test-helpers` feature.
+#[cfg(any(test, feature = "test-helpers"))]
+pub fn to_crlf_with_bom(input: &str) -> String {
+    format!("\u{feff}{}", input.replace('\n', "\r\n"))
+}
+
+/// A temporary input file, saved as [`to_crlf_with_bom`] converts it.
+///
+/// The file is removed when this is dropped. Only built for tests, or with the
+/// `test-helpers` feature.
+#[cfg(any(test, feature = "test-helpers"))]
+#[derive(Debug)]
+pub struct CrlfInputFile(std::path::PathBuf);
+
+#[cfg(any(test, feature = "test-helpers"))]
+impl CrlfInputFile {
+    /// Save `input` to a temporary file, named for `name` and this process so that
+    /// concurrent test runs don't share it.
+    pub fn new(name: &str, input: &str) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::write(&path, to_crlf_with_bom(input))?;
+        Ok(CrlfInputFile(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(any(test, feature = "test-helpers"))]
+impl Drop for CrlfInputFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Parse each line of `input` as a `T`.
+///
+/// This mirrors `aoclib::parse`, but for input which is already in memory:
+/// lines are trimmed, empty lines are skipped, and lines which fail to parse
+/// are reported on stderr and skipped.
+pub fn parse_str<'a, T>(input: &'a str) -> impl 'a + Iterator<Item = T>
+where
+    T: 'a + FromStr,
+    <T as FromStr>::Err: Display,
+{
+    input.lines().enumerate().filter_map(|(idx, line)| {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        line.parse()
+            .map_err(|err| eprintln!("line {}: {}", idx + 1, err))
+            .ok()
+    })
+}
+
+/// Parse each line of `input` as a `T`, failing on the first line which doesn't parse.
+///
+/// Lines are trimmed and empty lines are skipped, as with [`parse_str`].
+pub fn try_parse_str<T>(input: &str) -> Result<Vec<T>, <T as FromStr>::Err>
+where
+    T: FromStr,
+{
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_blank_and_invalid_lines() {
+        let parsed: Vec<i32> = parse_str("1\n\n  -2  \nthree\n4\n").collect();
+        assert_eq!(parsed, vec![1, -2, 4]);
+    }
+
+    #[test]
+    fn normalize() {
+        assert!(matches!(normalize_input("1\n2\n"), Cow::Borrowed("1\n2\n")));
+        assert_eq!(normalize_input("\u{feff}1\r\n2\r\n"), "1\n2\n");
+        assert_eq!(normalize_input(&to_crlf_with_bom("1\n\n2")), "1\n\n2");
+        // only a leading byte-order mark is stripped, and only whole CRLF pairs converted
+        assert_eq!(normalize_input("a\u{feff}\rb"), "a\u{feff}\rb");
+    }
+
+    #[test]
+    fn read_normalized() {
+        let file = CrlfInputFile::new("util-read-input", "+1\n-2\n").unwrap();
+        assert_eq!(read_input(file.path()).unwrap(), "+1\n-2\n");
+        assert_eq!(read_input_or_stdin(file.path()).unwrap(), "+1\n-2\n");
+        let path = file.path().to_owned();
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn try_parse_fails_on_invalid_lines() {
+        assert_eq!(
+            try_parse_str::<i32>("1\n\n  -2  \n4\n").unwrap(),
+            vec![1, -2, 4]
+        );
+        assert!(try_parse_str::<i32>("1\nthree\n4\n").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Compare rendered maps, for test assertions which show where they differ.
+//!
+//! Maps are compared character by character. Rows may differ in length: missing characters,
+//! and missing rows, count as spaces, so trailing whitespace never matters.
+
+use std::fmt::Write;
+
+/// The characters of a rendered map, row by row.
+fn grid(map: &str) -> Vec<Vec<char>> {
+    map.lines().map(|line| line.chars().collect()).collect()
+}
+
+fn at(grid: &[Vec<char>], row: usize, column: usize) -> char {
+    grid.get(row)
+        .and_then(|row| row.get(column))
+        .copied()
+        .unwrap_or(' ')
+}
+
+/// The positions at which two rendered maps differ, as `(row, column)` from the top left.
+pub fn map_mismatches(expected: &str, actual: &str) -> Vec<(usize, usize)> {
+    let (expected, actual) = (grid(expected), grid(actual));
+    let rows = expected.len().max(actual.len());
+    (0..rows)
+        .flat_map(|row| {
+            let width = [&expected, &actual]
+                .iter()
+                .filter_map(|grid| grid.get(row))
+                .map(Vec::len)
+                .max()
+                .unwrap_or_default();
+            (0..width).map(move |column| (row, column))
+        })
+        .filter(|&(row, column)| at(&expected, row, column) != at(&actual, row, column))
+        .collect()
+}
+
+/// Describe how two rendered maps differ, or `None` if they're the same.
+///
+/// The description lists each mismatch, then shows the maps side by side. Rows which
+/// differ are marked with `>`, and the differing columns with `^` beneath them.
+pub fn map_diff(expected: &str, actual: &str) -> Option<String> {
+    let mismatches = map_mismatches(expected, actual);
+    if mismatches.is_empty() {
+        return None;
+    }
+
+    let (expected_grid, actual_grid) = (grid(expected), grid(actual));
+    let mut out = String::new();
+    let _ = writeln!(out, "maps differ at {} positions:", mismatches.len());
+    for &(row, column) in &mismatches {
+        let _ = writeln!(
+            out,
+            "  row {}, column {}: expected {:?}, found {:?}",
+            row,
+            column,
+            at(&expected_grid, row, column),
+            at(&actual_grid, row, column),
+        );
+    }
+
+    let width = expected_grid.iter().map(Vec::len).max().unwrap_or_default();
+    let rows = expected_grid.len().max(actual_grid.len());
+    let _ = writeln!(out, "  {:<width$} | actual", "expected", width = width);
+    for row in 0..rows {
+        let columns: Vec<usize> = mismatches
+            .iter()
+            .filter(|&&(mismatch_row, _)| mismatch_row == row)
+            .map(|&(_, column)| column)
+            .collect();
+        let line = |grid: &[Vec<char>]| -> String {
+            grid.get(row)
+                .map(|row| row.iter().collect())
+                .unwrap_or_default()
+        };
+        let marker = if columns.is_empty() { ' ' } else { '>' };
+        let _ = writeln!(
+            out,
+            "{} {:<width$} | {}",
+            marker,
+            line(&expected_grid),
+            line(&actual_grid),
+            width = width
+        );
+        if !columns.is_empty() {
+            let last = columns.last().copied().unwrap_or_default();
+            let carets: String = (0..=last)
+                .map(|column| if columns.contains(&column) { '^' } else { ' ' })
+                .collect();
+            let _ = writeln!(out, "  {}", carets);
+        }
+    }
+    Some(out)
+}
+
+/// Strip trailing whitespace from each row of a rendered map.
+///
+/// Snapshots compare trailing whitespace, which editors tend to strip; this makes them
+/// agree with [`map_diff`] that it doesn't matter.
+pub fn trim_lines(map: &str) -> String {
+    map.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Assert that two rendered maps are the same, showing where they differ if not.
+///
+/// Trailing whitespace is ignored, as by [`map_diff`](crate::map_diff::map_diff).
+#[macro_export]
+macro_rules! assert_maps_eq {
+    ($expected:expr, $actual:expr $(,)?) => {
+        if let Some(diff) = $crate::map_diff::map_diff(&$expected, &$actual) {
+            panic!("assertion failed: maps are equal\n{}", diff);
+        }
+    };
+    ($expected:expr, $actual:expr, $($arg:tt)+) => {
+        if let Some(diff) = $crate::map_diff::map_diff(&$expected, &$actual) {
+            panic!("assertion failed: maps are equal: {}\n{}", format_args!($($arg)+), diff);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_whitespace_ignored() {
+        assert_eq!(map_diff("#.#\n# #\n", "#.#  \n# #\n\n"), None);
+        assert_maps_eq!("#.#\n# #", "#.#\n# #\n");
+    }
+
+    #[test]
+    fn lines_trimmed() {
+        assert_eq!(trim_lines("#.#  \n# #\n\n"), "#.#\n# #\n");
+        assert_eq!(map_diff(&trim_lines("#.#  \n# #\n"), "#.#\n# #"), None);
+    }
+
+    #[test]
+    fn mismatches_located() {
+        let expected = "#####\n#.G.#\n#####";
+        let actual = "#####\n#G..#\n#####\n#";
+        assert_eq!(
+            map_mismatches(expected, actual),
+            vec![(1, 1), (1, 2), (3, 0)]
+        );
+
+        let diff = map_diff(expected, actual).unwrap();
+        assert!(diff.starts_with("maps differ at 3 positions:\n"));
+        assert!(diff.contains("  row 1, column 2: expected 'G', found '.'\n"));
+        assert!(diff.contains("> #.G.# | #G..#\n   ^^\n"), "{}", diff);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 0, column 1")]
+    fn assertion_fails() {
+        assert_maps_eq!("ab", "ac", "after {} ticks", 3);
+    }
+}
@@ -0,0 +1,253 @@
+//! Spatial indexing for nearest-point queries under the Manhattan (L1) metric.
+
+use aoclib::geometry::Point;
+use std::collections::HashMap;
+
+/// An index over a fixed set of points, answering Manhattan-distance queries.
+///
+/// Points are bucketed into a uniform grid of square cells sized so that each cell holds
+/// about one point. Nearest-point queries search rings of cells outward from the query,
+/// stopping once no unsearched cell could hold anything closer.
+///
+/// Sums of distances are computed from sorted, prefix-summed coordinates: the L1 metric
+/// separates by axis, so each sum takes two binary searches regardless of the number of points.
+///
+/// Points are identified by their index in the slice the index was built from.
+#[derive(Debug, Clone)]
+pub struct L1Index {
+    points: Vec<Point>,
+    cell_size: i32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    min_cell: (i32, i32),
+    max_cell: (i32, i32),
+    axes: [Axis; 2],
+}
+
+/// Sorted coordinates along a single axis, with prefix sums.
+#[derive(Debug, Clone, Default)]
+struct Axis {
+    sorted: Vec<i64>,
+    // prefix[i] is the sum of sorted[..i]
+    prefix: Vec<i64>,
+}
+
+impl Axis {
+    fn new(mut sorted: Vec<i64>) -> Axis {
+        sorted.sort_unstable();
+        let mut prefix = Vec::with_capacity(sorted.len() + 1);
+        prefix.push(0);
+        for value in &sorted {
+            prefix.push(prefix.last().unwrap() + value);
+        }
+        Axis { sorted, prefix }
+    }
+
+    /// Sum of `|value - c|` over all coordinates `c`.
+    fn sum_of_distances(&self, value: i64) -> i64 {
+        let below = self.sorted.partition_point(|&c| c < value);
+        let above = self.sorted.len() - below;
+        let sum_below = self.prefix[below];
+        let sum_above = self.prefix[self.sorted.len()] - sum_below;
+        (value * below as i64 - sum_below) + (sum_above - value * above as i64)
+    }
+}
+
+impl L1Index {
+    pub fn new(points: &[Point]) -> L1Index {
+        let points = points.to_vec();
+
+        let (mut min, mut max) = (Point::new(0, 0), Point::new(0, 0));
+        if let Some(&first) = points.first() {
+            min = first;
+            max = first;
+        }
+        for point in &points {
+            min.x = min.x.min(point.x);
+            min.y = min.y.min(point.y);
+            max.x = max.x.max(point.x);
+            max.y = max.y.max(point.y);
+        }
+
+        // aim for about one point per cell
+        let area = (max.x - min.x + 1) as f64 * (max.y - min.y + 1) as f64;
+        let cell_size = ((area / points.len().max(1) as f64).sqrt().ceil() as i32).max(1);
+
+        let mut cells: HashMap<_, Vec<_>> = HashMap::new();
+        for (idx, &point) in points.iter().enumerate() {
+            cells
+                .entry(Self::cell_of(cell_size, point))
+                .or_default()
+                .push(idx);
+        }
+
+        let axes = [
+            Axis::new(points.iter().map(|point| point.x as i64).collect()),
+            Axis::new(points.iter().map(|point| point.y as i64).collect()),
+        ];
+
+        L1Index {
+            min_cell: Self::cell_of(cell_size, min),
+            max_cell: Self::cell_of(cell_size, max),
+            points,
+            cell_size,
+            cells,
+            axes,
+        }
+    }
+
+    fn cell_of(cell_size: i32, point: Point) -> (i32, i32) {
+        (point.x.div_euclid(cell_size), point.y.div_euclid(cell_size))
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The indexed points, in their original order.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Find the point nearest `point`, returning its index and distance.
+    ///
+    /// When several points are equally near, the one with the lowest index is chosen.
+    pub fn nearest(&self, point: Point) -> Option<(usize, i32)> {
+        self.k_nearest(point, 1).first().copied()
+    }
+
+    /// Find up to `k` points nearest `point`, as `(index, distance)`.
+    ///
+    /// Results are sorted by distance, then by index.
+    pub fn k_nearest(&self, point: Point, k: usize) -> Vec<(usize, i32)> {
+        let mut best: Vec<(i32, usize)> = Vec::with_capacity(k + 1);
+        if k == 0 || self.is_empty() {
+            return Vec::new();
+        }
+
+        let (cx, cy) = Self::cell_of(self.cell_size, point);
+        // rings beyond this radius contain no cells with points
+        let max_radius = [
+            cx - self.min_cell.0,
+            self.max_cell.0 - cx,
+            cy - self.min_cell.1,
+            self.max_cell.1 - cy,
+        ]
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(0);
+
+        for radius in 0..=max_radius {
+            for dy in -radius..=radius {
+                // only the cells on the ring's perimeter are new
+                let step = if dy.abs() == radius {
+                    1
+                } else {
+                    2 * radius.max(1)
+                };
+                let mut dx = -radius;
+                while dx <= radius {
+                    for &idx in self.cells.get(&(cx + dx, cy + dy)).into_iter().flatten() {
+                        let dist = (self.points[idx] - point).manhattan();
+                        best.push((dist, idx));
+                    }
+                    dx += step;
+                }
+            }
+            best.sort_unstable();
+            best.truncate(k);
+
+            // every point in the next ring is more than `radius` cells away on some axis
+            if best.len() == k && best[k - 1].0 <= radius * self.cell_size {
+                break;
+            }
+        }
+
+        best.into_iter().map(|(dist, idx)| (idx, dist)).collect()
+    }
+
+    /// Sum of the Manhattan distances from `point` to every indexed point.
+    pub fn sum_of_distances(&self, point: Point) -> i64 {
+        self.axes[0].sum_of_distances(point.x as i64)
+            + self.axes[1].sum_of_distances(point.y as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Point> {
+        vec![
+            Point::new(1, 1),
+            Point::new(1, 6),
+            Point::new(8, 3),
+            Point::new(3, 4),
+            Point::new(5, 5),
+            Point::new(8, 9),
+        ]
+    }
+
+    fn brute_k_nearest(points: &[Point], point: Point, k: usize) -> Vec<(usize, i32)> {
+        let mut all: Vec<_> = points
+            .iter()
+            .enumerate()
+            .map(|(idx, &p)| ((p - point).manhattan(), idx))
+            .collect();
+        all.sort_unstable();
+        all.into_iter().take(k).map(|(d, i)| (i, d)).collect()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let points = example();
+        let index = L1Index::new(&points);
+        for y in -5..15 {
+            for x in -5..15 {
+                let point = Point::new(x, y);
+                for k in 1..=3 {
+                    assert_eq!(
+                        index.k_nearest(point, k),
+                        brute_k_nearest(&points, point, k),
+                        "k={} at {:?}",
+                        k,
+                        point
+                    );
+                }
+                let sum: i64 = points.iter().map(|&p| (p - point).manhattan() as i64).sum();
+                assert_eq!(index.sum_of_distances(point), sum);
+            }
+        }
+    }
+
+    #[test]
+    fn scattered_points() {
+        // a deterministic pseudo-random scatter, including negative coordinates
+        let mut state = 12345_u32;
+        let mut next = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((state >> 16) % 400) as i32 - 200
+        };
+        let points: Vec<_> = (0..200).map(|_| Point::new(next(), next())).collect();
+        let index = L1Index::new(&points);
+        for _ in 0..200 {
+            let point = Point::new(next() * 2, next() * 2);
+            assert_eq!(
+                index.k_nearest(point, 2),
+                brute_k_nearest(&points, point, 2)
+            );
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let index = L1Index::new(&[]);
+        assert_eq!(index.nearest(Point::new(0, 0)), None);
+        assert_eq!(index.sum_of_distances(Point::new(3, 4)), 0);
+    }
+}